@@ -0,0 +1,475 @@
+//! The desktop application run by the `search` binary: the GUI, the
+//! background indexing threads and the command-line mode.
+
+use crate::blacklist::Blacklist;
+use crate::cli;
+use crate::config::{Config, CONFIG_FILE};
+use crate::drives::{
+    default_roots, drive_kind, mounted_drives, DriveTracker, DEFAULT_DRIVE_KINDS,
+    DRIVE_POLL_INTERVAL,
+};
+#[cfg(feature = "http")]
+use crate::http;
+use crate::instance::{InstanceLock, LOCK_FILE};
+use crate::message::EngineMessage;
+use crate::schedule::UpdateSchedule;
+use crate::search_engine::{
+    default_exclude_dirs, index_file_name, IndexMode, IndexProgress, IndexSnapshot, Search,
+    SearchEngine, PARTIAL_INDEX_START, PROGRESS_INTERVAL, UPDATE_TIME_FILE,
+};
+use crate::streams::streams_enabled;
+use crate::ui_handle::{SearchApp, SearchAppEngine};
+use crate::watch::{IndexWatcher, WATCH_INTERVAL};
+use egui::{IconData, ViewportBuilder};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, sleep, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Runs a headless search when arguments are given and the GUI otherwise.
+pub fn run() {
+    migrate_config();
+    match cli::CliArgs::parse(std::env::args().skip(1)) {
+        Ok(None) => run_gui_mode(),
+        Ok(Some(args)) => {
+            cli::attach_console();
+            if let Err(e) = cli::run(&args, configured_engine(), &mut std::io::stdout().lock()) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            cli::attach_console();
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Where the background threads put an index once it has been built.
+#[derive(Clone)]
+enum IndexSink {
+    Disk,
+    Memory(Sender<IndexSnapshot>),
+}
+
+fn run_gui_mode() {
+    // Held until the window has closed and the background threads have stopped.
+    let _lock = match InstanceLock::acquire(Path::new(LOCK_FILE)) {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => {
+            cli::attach_console();
+            eprintln!("Search is already running");
+            return;
+        }
+        // Better to run unguarded than not at all, e.g. in a read-only folder.
+        Err(e) => {
+            eprintln!("Failed to create {}: {}", LOCK_FILE, e);
+            None
+        }
+    };
+    let (send, recv) = channel();
+    let (index_send, index_recv) = channel();
+    let (progress_send, progress_recv) = channel();
+    let sink = match load_config().index_mode {
+        IndexMode::Disk => IndexSink::Disk,
+        IndexMode::Memory => IndexSink::Memory(index_send),
+    };
+    let icon_data = load_icon_data("ico.ico").unwrap_or_default();
+    let viewport = ViewportBuilder::default();
+    let native_options = eframe::NativeOptions {
+        viewport: viewport.with_icon(icon_data),
+        ..Default::default()
+    };
+
+    let _ = eframe::run_native(
+        "Search",
+        native_options,
+        Box::new(|cc| {
+            let mut app = SearchApp::new(cc);
+            app.set_message_sender(send);
+            app.set_index_receiver(index_recv);
+            app.set_progress_receiver(progress_recv);
+            app.set_background_threads(start_background_threads(recv, sink, progress_send));
+            Ok(Box::new(app))
+        }),
+    );
+}
+
+fn load_icon_data(path: &str) -> Option<IconData> {
+    let image_data = image::ImageReader::open(path).ok()?;
+    let image = image_data.decode().ok()?;
+    let rgba = image.as_bytes().to_vec();
+    Some(IconData {
+        rgba,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+/// Starts the indexing threads and returns those that stop on
+/// [`EngineMessage::Shutdown`].
+fn start_background_threads(
+    recv: Receiver<EngineMessage>,
+    sink: IndexSink,
+    progress: Sender<IndexProgress>,
+) -> Vec<JoinHandle<()>> {
+    let (sender, receiver) = channel();
+    let paused = Arc::new(AtomicBool::new(false));
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut threads = start_search_thread(
+        recv,
+        sender,
+        sink.clone(),
+        progress.clone(),
+        paused.clone(),
+        stop.clone(),
+    );
+    threads.extend(start_watch_thread(sink.clone(), stop.clone()));
+    threads.push(start_update_thread(receiver, sink, progress, paused, stop));
+    #[cfg(feature = "http")]
+    if let Err(e) = http::start(http::HttpConfig::from_config(&load_config())) {
+        eprintln!("Failed to start the search endpoint: {}", e);
+    }
+    threads
+}
+
+/// Moves the settings files of earlier versions into `config.toml`, once. A
+/// config that cannot be read is left alone so that it is not overwritten.
+fn migrate_config() {
+    let Ok(mut config) = Config::load(CONFIG_FILE) else {
+        return;
+    };
+    match config.migrate(Path::new(".")) {
+        Ok(moved) if !moved.is_empty() => {
+            eprintln!("Moved {} into {}", moved.join(", "), CONFIG_FILE);
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to move the settings into {}: {}", CONFIG_FILE, e),
+    }
+}
+
+/// The settings in `config.toml`, or the defaults when it cannot be read.
+fn load_config() -> Config {
+    Config::load(CONFIG_FILE).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Config::default()
+    })
+}
+
+/// Whether every drive of a chosen kind is indexed, including drives mounted
+/// later. That is the case when `config.toml` sets no roots.
+pub(crate) fn follows_drives(config: &Config) -> bool {
+    config.roots.is_empty()
+}
+
+/// An engine set up from the indexing settings of `config.toml`.
+fn configured_engine() -> Search {
+    let config = load_config();
+    let mut engine = Search::new();
+    engine.set_resource_limits(config.resource_limits);
+    engine.set_blacklist(Blacklist::from_entries(
+        config.blacklist.iter().map(String::as_str),
+    ));
+    engine.set_exclude_dirs(config.exclude_dirs.unwrap_or_else(default_exclude_dirs));
+    engine.set_max_depth(config.max_depth);
+    engine.set_ignore_case(!config.match_case);
+    engine.set_index_created_time(config.index_created_time);
+    engine.set_respect_gitignore(config.respect_gitignore);
+    engine.set_skip_hidden(config.skip_hidden);
+    engine.set_index_streams(streams_enabled(config.index_streams));
+    engine
+}
+
+fn start_search_thread(
+    recv: Receiver<EngineMessage>,
+    sender: Sender<EngineMessage>,
+    sink: IndexSink,
+    progress: Sender<IndexProgress>,
+    paused: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+) -> Vec<JoinHandle<()>> {
+    let mut engine = configured_engine();
+    engine.set_progress_sender(Some(progress), PROGRESS_INTERVAL);
+    engine.set_pause_flag(Some(paused.clone()));
+    engine.set_stop_flag(Some(stop.clone()));
+    let (index_sender, index_receiver) = channel();
+    // Messages are read on their own thread so that pausing and stopping take
+    // effect while an index is being built.
+    let dispatcher = thread::spawn(move || {
+        while let Ok(message) = recv.recv() {
+            if !dispatch(message, &paused, &stop, &index_sender, &sender) {
+                break;
+            }
+        }
+    });
+    // Ends once the dispatcher has dropped `index_sender`.
+    let indexer = thread::spawn(move || {
+        while let Ok(roots) = index_receiver.recv() {
+            process_search_request(&mut engine, &roots, &sink);
+        }
+    });
+    vec![dispatcher, indexer]
+}
+
+/// Applies pausing, resuming and stopping at once and hands the roots to
+/// reindex to the indexing thread and interval changes to the update thread.
+/// Returns false once the threads are told to shut down.
+fn dispatch(
+    message: EngineMessage,
+    paused: &AtomicBool,
+    stop: &AtomicBool,
+    index_sender: &Sender<Vec<PathBuf>>,
+    update_sender: &Sender<EngineMessage>,
+) -> bool {
+    match message {
+        EngineMessage::PauseIndexing => paused.store(true, Ordering::Relaxed),
+        EngineMessage::ResumeIndexing => paused.store(false, Ordering::Relaxed),
+        EngineMessage::Reindex(roots) => {
+            let _ = index_sender.send(roots);
+        }
+        EngineMessage::SetInterval(_) => {
+            let _ = update_sender.send(message);
+        }
+        EngineMessage::Shutdown => {
+            stop.store(true, Ordering::Relaxed);
+            let _ = update_sender.send(message);
+            return false;
+        }
+    }
+    true
+}
+
+/// Indexes `roots` into a single index.
+fn process_search_request(engine: &mut Search, roots: &[PathBuf], sink: &IndexSink) {
+    let Some((first, rest)) = roots.split_first() else {
+        return;
+    };
+    engine.set_root_dir(first.clone());
+    for root in rest {
+        engine.add_root_dir(root.clone());
+    }
+    index_roots(engine, sink);
+}
+
+fn index_root(engine: &mut Search, root: PathBuf, sink: &IndexSink) {
+    engine.set_root_dir(root);
+    index_roots(engine, sink);
+}
+
+fn index_roots(engine: &mut Search, sink: &IndexSink) {
+    // Re-read on every run so the setting changed in the UI applies to the next index.
+    engine.set_skip_hidden(load_config().skip_hidden);
+    let generated = match sink {
+        IndexSink::Disk => engine.generate_index(),
+        IndexSink::Memory(sender) => {
+            // Publish partial indexes so the UI can search while a large root indexes.
+            let roots = engine.get_root_dirs();
+            engine.generate_index_with_progress(PARTIAL_INDEX_START, &mut |files| {
+                let _ = sender.send(IndexSnapshot {
+                    roots: roots.clone(),
+                    files: files.to_vec(),
+                    complete: false,
+                });
+            })
+        }
+    };
+    if let Err(e) = generated {
+        eprintln!("{}", e);
+        return;
+    }
+    match sink {
+        IndexSink::Disk => {
+            if let Err(e) = engine.save_index() {
+                eprintln!("Failed to save index: {}", e);
+            }
+            engine.clear_index_files();
+        }
+        IndexSink::Memory(sender) => {
+            let _ = sender.send(IndexSnapshot {
+                roots: engine.get_root_dirs(),
+                files: engine.take_index(),
+                complete: true,
+            });
+        }
+    }
+}
+
+fn start_update_thread(
+    recv: Receiver<EngineMessage>,
+    sink: IndexSink,
+    progress: Sender<IndexProgress>,
+    paused: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+) -> JoinHandle<()> {
+    let config = load_config();
+    let mut update_time = config
+        .update_interval
+        .unwrap_or_else(|| Duration::from_secs(read_update_time(UPDATE_TIME_FILE).unwrap_or(600)));
+    let follow_drives = follows_drives(&config);
+    let drive_kinds = config
+        .drive_kinds
+        .unwrap_or_else(|| DEFAULT_DRIVE_KINDS.to_vec());
+    let roots = if follow_drives {
+        default_roots(&drive_kinds)
+    } else {
+        config.roots
+    };
+    let mut schedule = UpdateSchedule::configured(config.schedule, roots, Instant::now());
+    let follow_drives = follow_drives && schedule.uses_defaults();
+
+    let mut engine = configured_engine();
+    engine.set_progress_sender(Some(progress), PROGRESS_INTERVAL);
+    engine.set_pause_flag(Some(paused));
+    engine.set_stop_flag(Some(stop));
+    let mut drives = mounted_drives().map(DriveTracker::new);
+    thread::spawn(move || loop {
+        let next_due = schedule.next_due(update_time).unwrap_or_else(Instant::now);
+        let mut wait = next_due.saturating_duration_since(Instant::now());
+        if drives.is_some() {
+            wait = wait.min(DRIVE_POLL_INTERVAL);
+        }
+        match recv.recv_timeout(wait) {
+            Ok(EngineMessage::SetInterval(interval)) => {
+                update_time = interval;
+                continue;
+            }
+            Ok(EngineMessage::Shutdown) => break,
+            Ok(_) => continue,
+            Err(RecvTimeoutError::Disconnected) => sleep(wait),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if let (Some(tracker), Some(mounted)) = (&mut drives, mounted_drives()) {
+            let changes = tracker.update(mounted);
+            for root in changes.mounted {
+                if follow_drives && drive_kinds.contains(&drive_kind(&root)) {
+                    schedule.add(root.clone(), Instant::now());
+                }
+                if schedule.contains(&root) {
+                    index_root(&mut engine, root.clone(), &sink);
+                    schedule.mark_updated(&root, Instant::now());
+                }
+            }
+            for root in changes.removed {
+                if schedule.contains(&root) {
+                    drop_index(root, &sink);
+                }
+            }
+        }
+        for root in schedule.due(Instant::now(), update_time) {
+            index_root(&mut engine, root.clone(), &sink);
+            schedule.mark_updated(&root, Instant::now());
+        }
+    })
+}
+
+/// Keeps the roots listed under `watch` in `config.toml` indexed between
+/// scheduled updates, publishing the index each time a burst of changes has
+/// settled. They are indexed together like the roots of a search request.
+fn start_watch_thread(sink: IndexSink, stop: Arc<AtomicBool>) -> Option<JoinHandle<()>> {
+    let roots = load_config().watch;
+    let (first, rest) = roots.split_first()?;
+    let mut engine = configured_engine();
+    engine.set_root_dir(first.clone());
+    for root in rest {
+        engine.add_root_dir(root.clone());
+    }
+    engine.set_stop_flag(Some(stop.clone()));
+    Some(thread::spawn(move || {
+        let mut watcher = match IndexWatcher::new(engine) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        publish_index(watcher.engine(), &sink);
+        while !stop.load(Ordering::Relaxed) {
+            sleep(WATCH_INTERVAL);
+            match watcher.poll() {
+                Ok(true) => publish_index(watcher.engine(), &sink),
+                Ok(false) => {}
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    }))
+}
+
+/// Hands a finished index to `sink` while keeping it in `engine`.
+fn publish_index(engine: &Search, sink: &IndexSink) {
+    match sink {
+        IndexSink::Disk => {
+            if let Err(e) = engine.save_index() {
+                eprintln!("Failed to save index: {}", e);
+            }
+        }
+        IndexSink::Memory(sender) => {
+            let _ = sender.send(IndexSnapshot {
+                roots: engine.get_root_dirs(),
+                files: engine.get_index().clone(),
+                complete: true,
+            });
+        }
+    }
+}
+
+/// Forgets the index of a root that is no longer available, e.g. a removed drive.
+fn drop_index(root: PathBuf, sink: &IndexSink) {
+    match sink {
+        IndexSink::Disk => {
+            let _ = std::fs::remove_file(index_file_name(&root));
+        }
+        IndexSink::Memory(sender) => {
+            let _ = sender.send(IndexSnapshot {
+                roots: vec![root],
+                files: Vec::new(),
+                complete: true,
+            });
+        }
+    }
+}
+
+fn read_update_time(path: &str) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let mut buf = String::new();
+    file.read_to_string(&mut buf).ok()?;
+    buf.trim().parse::<u64>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dispatch_routes_each_message() {
+        let paused = AtomicBool::new(false);
+        let stop = AtomicBool::new(false);
+        let (index_sender, index_receiver) = channel();
+        let (update_sender, update_receiver) = channel();
+        let send = |message| dispatch(message, &paused, &stop, &index_sender, &update_sender);
+
+        assert!(send(EngineMessage::PauseIndexing));
+        assert!(paused.load(Ordering::Relaxed));
+        let roots = vec![PathBuf::from("C:\\"), PathBuf::from("D:\\")];
+        send(EngineMessage::Reindex(roots.clone()));
+        send(EngineMessage::SetInterval(Duration::from_secs(900)));
+        send(EngineMessage::ResumeIndexing);
+        assert!(!paused.load(Ordering::Relaxed));
+        assert!(!stop.load(Ordering::Relaxed));
+        assert!(!send(EngineMessage::Shutdown));
+        assert!(stop.load(Ordering::Relaxed));
+
+        assert_eq!(index_receiver.try_iter().collect::<Vec<_>>(), [roots]);
+        assert_eq!(
+            update_receiver.try_iter().collect::<Vec<_>>(),
+            [
+                EngineMessage::SetInterval(Duration::from_secs(900)),
+                EngineMessage::Shutdown
+            ]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::{collections::HashSet, path::Path};
+
+use crate::search_engine::normalize_path;
+
+/// Files that are never indexed nor shown, listed under `blacklist` in
+/// `config.toml`.
+///
+/// Each entry is either a bare name such as `Thumbs.db`, which suppresses every
+/// file or folder with that name, or a full path, which suppresses only that one.
+/// Both kinds are compared without regard to case.
+#[derive(Clone, Debug, Default)]
+pub struct Blacklist {
+    names: HashSet<String>,
+    paths: HashSet<String>,
+}
+
+impl Blacklist {
+    /// The blacklist of `entries`; blank ones are skipped.
+    pub(crate) fn from_entries<'a>(entries: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut blacklist = Blacklist::default();
+        for entry in entries
+            .into_iter()
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            if entry.contains(['\\', '/']) {
+                blacklist.paths.insert(normalize_path(entry).to_lowercase());
+            } else {
+                blacklist.names.insert(entry.to_lowercase());
+            }
+        }
+        blacklist
+    }
+
+    /// Whether `path` is suppressed, either by its name or by its full path.
+    pub(crate) fn blocks(&self, path: &Path) -> bool {
+        if self.names.is_empty() && self.paths.is_empty() {
+            return false;
+        }
+        let path = path.to_string_lossy();
+        let name = path.rsplit(['\\', '/']).next().unwrap_or_default();
+        self.names.contains(&name.to_lowercase())
+            || (!self.paths.is_empty()
+                && self.paths.contains(&normalize_path(&path).to_lowercase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_name_entries_block_everywhere() {
+        let blacklist = Blacklist::from_entries(["Thumbs.db", "", "  desktop.ini  "]);
+        assert!(blacklist.blocks(Path::new("C:\\Photos\\Thumbs.db")));
+        assert!(blacklist.blocks(Path::new("D:\\a\\b\\thumbs.DB")));
+        assert!(blacklist.blocks(Path::new("C:\\Users\\me\\Desktop\\desktop.ini")));
+        assert!(!blacklist.blocks(Path::new("C:\\Photos\\Thumbs.db.bak")));
+    }
+
+    #[test]
+    fn test_path_entries_block_one_file() {
+        let blacklist = Blacklist::from_entries(["C:/Logs//debug.log"]);
+        assert!(blacklist.blocks(Path::new("C:\\Logs\\debug.log")));
+        assert!(blacklist.blocks(Path::new("c:\\logs\\DEBUG.log")));
+        assert!(!blacklist.blocks(Path::new("C:\\Other\\debug.log")));
+        assert!(!Blacklist::default().blocks(&PathBuf::from("C:\\Logs\\debug.log")));
+    }
+
+    #[test]
+    fn test_blacklisted_files_are_not_indexed_or_shown() {
+        use crate::search_engine::{Search, SearchEngine};
+
+        let dir = std::env::temp_dir().join("search_blacklist_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["keep.log", "Thumbs.db", "noisy.log"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let noisy = dir.join("noisy.log");
+        let blacklist = Blacklist::from_entries(["thumbs.db", &noisy.to_string_lossy()]);
+
+        let mut engine = Search::new();
+        engine.set_root_dir(dir.clone());
+        engine.set_blacklist(blacklist.clone());
+        engine.generate_index().unwrap();
+        let paths: Vec<_> = engine.get_index().iter().map(|file| &file.path).collect();
+        assert_eq!(paths, [&dir.join("keep.log")]);
+
+        // Entries indexed before the blacklist changed are hidden from results.
+        engine.set_blacklist(Blacklist::default());
+        engine.generate_index().unwrap();
+        engine.set_blacklist(blacklist);
+        engine.search("").unwrap();
+        let shown: Vec<&PathBuf> = engine.iter_results().map(|(path, _)| path).collect();
+        assert_eq!(shown, vec![&dir.join("keep.log")]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+/// Named groups of file extensions used to narrow results by file type.
+///
+/// The built-in groups can be overridden or extended by the `[categories]` table
+/// of `config.toml`, e.g. `image = ["jpg", "png", "webp"]`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Categories {
+    groups: Vec<(String, Vec<String>)>,
+}
+
+const DEFAULT_CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "document",
+        &[
+            "txt", "md", "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "rtf", "csv",
+        ],
+    ),
+    (
+        "image",
+        &[
+            "jpg", "jpeg", "png", "gif", "bmp", "webp", "tif", "tiff", "svg", "ico", "heic",
+        ],
+    ),
+    ("audio", &["mp3", "wav", "flac", "aac", "ogg", "m4a", "wma"]),
+    ("video", &["mp4", "mkv", "avi", "mov", "wmv", "flv", "webm"]),
+    (
+        "archive",
+        &["zip", "rar", "7z", "tar", "gz", "bz2", "xz", "zst"],
+    ),
+    (
+        "code",
+        &[
+            "rs", "c", "h", "cpp", "hpp", "cs", "java", "py", "js", "ts", "go", "rb", "php",
+            "toml", "json", "yaml", "yml", "html", "css", "sh", "ps1",
+        ],
+    ),
+];
+
+impl Default for Categories {
+    fn default() -> Self {
+        Categories {
+            groups: DEFAULT_CATEGORIES
+                .iter()
+                .map(|(name, extensions)| {
+                    (
+                        name.to_string(),
+                        extensions.iter().map(|ext| ext.to_string()).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Categories {
+    /// The built-in categories with `overrides` applied: a group of a built-in
+    /// name replaces its extensions, any other is added.
+    pub(crate) fn with_overrides(overrides: &[(String, Vec<String>)]) -> Self {
+        let mut categories = Categories::default();
+        for (name, extensions) in overrides {
+            let name = name.trim().to_lowercase();
+            let extensions: Vec<String> = extensions
+                .iter()
+                .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+            match categories
+                .groups
+                .iter_mut()
+                .find(|(group, _)| *group == name)
+            {
+                Some((_, group_extensions)) => *group_extensions = extensions,
+                None => categories.groups.push((name, extensions)),
+            }
+        }
+        categories
+    }
+
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.groups.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub(crate) fn extensions(&self, name: &str) -> Option<&[String]> {
+        let name = name.to_lowercase();
+        self.groups
+            .iter()
+            .find(|(group, _)| *group == name)
+            .map(|(_, extensions)| extensions.as_slice())
+    }
+
+    /// Returns whether `path` has an extension belonging to the category `name`.
+    /// Unknown categories and extensionless files never match.
+    pub(crate) fn matches(&self, name: &str, path: &Path) -> bool {
+        let Some(extensions) = self.extensions(name) else {
+            return false;
+        };
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_replaces_and_extends() {
+        let overrides = [
+            ("image".to_string(), vec!["png".to_string()]),
+            (
+                "Model".to_string(),
+                vec!["obj".to_string(), ".FBX".to_string()],
+            ),
+        ];
+        let categories = Categories::with_overrides(&overrides);
+        assert_eq!(categories.extensions("image").unwrap(), ["png"]);
+        assert_eq!(categories.extensions("model").unwrap(), ["obj", "fbx"]);
+        assert!(categories.names().any(|name| name == "code"));
+    }
+
+    #[test]
+    fn test_matches_ignores_case() {
+        let categories = Categories::default();
+        assert!(categories.matches("image", Path::new("Photo.JPG")));
+        assert!(!categories.matches("image", Path::new("notes.txt")));
+        assert!(!categories.matches("image", Path::new("ntoskrnl")));
+        assert!(!categories.matches("unknown", Path::new("a.png")));
+    }
+}
@@ -0,0 +1,260 @@
+use std::{io::Write, path::PathBuf};
+
+use crate::search_engine::{parse_path_list, Search, SearchEngine};
+use crate::transfer::write_results;
+
+pub(crate) const USAGE: &str = "\
+Usage: search --root <path> --query <pattern> [--format <format>] [--limit <n>] [--reindex]
+
+Searches the index of the given roots and prints the matching paths, one per line.
+
+Options:
+  --root <path>      Folder to search; repeat it or separate paths with `;`
+  --query <pattern>  Pattern matched against file names
+  --format <format>  `lines` (default), `json` for a JSON array, or `binary`
+                     for the framed encoding read by `search::read_results`
+  --json             Same as `--format json`
+  --limit <n>        Print at most n matches (default: all)
+  --reindex          Index the roots again instead of reading the saved index
+  --help             Show this message
+
+Without arguments the graphical interface starts.";
+
+/// How a headless search writes its matches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    /// One path per line.
+    #[default]
+    Lines,
+    /// A JSON array, see [`SearchEngine::results_to_json`].
+    Json,
+    /// The framed binary encoding of [`write_results`].
+    Binary,
+}
+
+impl OutputFormat {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "lines" => Some(OutputFormat::Lines),
+            "json" => Some(OutputFormat::Json),
+            "binary" => Some(OutputFormat::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// The options of a headless search, e.g. `search --root C:\ --query cmd`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct CliArgs {
+    pub(crate) roots: Vec<PathBuf>,
+    pub(crate) query: String,
+    pub(crate) format: OutputFormat,
+    pub(crate) limit: Option<usize>,
+    pub(crate) reindex: bool,
+    pub(crate) help: bool,
+}
+
+impl CliArgs {
+    /// Parses the arguments after the program name. Returns `Ok(None)` when there
+    /// are none, in which case the GUI starts.
+    pub(crate) fn parse<I>(args: I) -> Result<Option<Self>, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter().peekable();
+        if args.peek().is_none() {
+            return Ok(None);
+        }
+        let mut parsed = CliArgs::default();
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => {
+                    (name.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} needs a value", name))
+            };
+            match name.as_str() {
+                "--root" | "-r" => parsed.roots.extend(parse_path_list(&value()?)),
+                "--query" | "-q" => parsed.query = value()?,
+                "--limit" | "-n" => {
+                    let limit = value()?;
+                    parsed.limit = Some(
+                        limit
+                            .parse()
+                            .map_err(|_| format!("Invalid limit: {}", limit))?,
+                    );
+                }
+                "--format" => {
+                    let format = value()?;
+                    parsed.format = OutputFormat::parse(&format)
+                        .ok_or_else(|| format!("Invalid format: {}", format))?;
+                }
+                "--json" => parsed.format = OutputFormat::Json,
+                "--reindex" => parsed.reindex = true,
+                "--help" | "-h" => parsed.help = true,
+                _ => return Err(format!("Unknown argument: {}", name)),
+            }
+        }
+        if !parsed.help && parsed.roots.is_empty() {
+            return Err("--root is required".to_string());
+        }
+        Ok(Some(parsed))
+    }
+}
+
+/// Searches the roots of `args` with `engine` and writes the matches to `out`.
+///
+/// The saved index of the roots is used when there is one; otherwise, or with
+/// `--reindex`, the roots are indexed first. A fresh index is not saved.
+pub(crate) fn run(args: &CliArgs, mut engine: Search, out: &mut impl Write) -> Result<(), String> {
+    if args.help {
+        return writeln!(out, "{}", USAGE).map_err(|e| e.to_string());
+    }
+    let Some((first, rest)) = args.roots.split_first() else {
+        return Err("--root is required".to_string());
+    };
+    engine.set_root_dir(first.clone());
+    for root in rest {
+        engine.add_root_dir(root.clone());
+    }
+    if !args.reindex {
+        engine.load_index().map_err(|e| e.to_string())?;
+    }
+    if engine.get_index().is_empty() {
+        engine.generate_index().map_err(|e| e.to_string())?;
+    }
+    engine.set_search_results_limit(args.limit.unwrap_or(usize::MAX));
+    engine.search(&args.query).map_err(|e| e.to_string())?;
+
+    match args.format {
+        OutputFormat::Lines => {
+            for (path, _) in engine.iter_results() {
+                writeln!(out, "{}", path.display()).map_err(|e| e.to_string())?;
+            }
+        }
+        OutputFormat::Json => {
+            writeln!(out, "{}", engine.results_to_json()).map_err(|e| e.to_string())?;
+        }
+        OutputFormat::Binary => {
+            write_results(&mut *out, engine.get_results()).map_err(|e| e.to_string())?;
+        }
+    }
+    out.flush().map_err(|e| e.to_string())
+}
+
+/// Lets a GUI subsystem build print to the console it was started from.
+#[cfg(windows)]
+pub(crate) fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // SAFETY: AttachConsole has no preconditions; it fails harmlessly without a parent console.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn attach_console() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transfer::read_results;
+
+    fn args(list: &[&str]) -> Result<Option<CliArgs>, String> {
+        CliArgs::parse(list.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(args(&[]), Ok(None));
+        let parsed = args(&[
+            "--root",
+            "a;b",
+            "-r",
+            "c",
+            "--query=cmd",
+            "--json",
+            "-n",
+            "5",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            parsed.roots,
+            vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")]
+        );
+        assert_eq!(parsed.query, "cmd");
+        assert_eq!(parsed.format, OutputFormat::Json);
+        assert_eq!(parsed.limit, Some(5));
+        let parsed = args(&["--root", "a", "--format", "binary"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(parsed.format, OutputFormat::Binary);
+        assert!(args(&["--root", "a", "--format", "xml"]).is_err());
+        assert!(args(&["--query", "cmd"]).is_err());
+        assert!(args(&["--root"]).is_err());
+        assert!(args(&["--root", "a", "--limit", "x"]).is_err());
+        assert!(args(&["--root", "a", "--verbose"]).is_err());
+        assert!(args(&["--help"]).unwrap().unwrap().help);
+    }
+
+    #[test]
+    fn test_run_against_temp_tree() {
+        let dir = std::env::temp_dir().join("search_cli_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+        std::fs::write(dir.join("bin").join("cmd.exe"), "").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        let root = dir.to_string_lossy().into_owned();
+
+        let parsed = args(&["--root", &root, "--query", "cmd", "--reindex"])
+            .unwrap()
+            .unwrap();
+        let mut out = Vec::new();
+        run(&parsed, Search::new(), &mut out).unwrap();
+        let expected = dir.join("bin").join("cmd.exe");
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{}\n", expected.display())
+        );
+
+        let parsed = args(&["--root", &root, "--query", "cmd", "--reindex", "--json"])
+            .unwrap()
+            .unwrap();
+        let mut out = Vec::new();
+        run(&parsed, Search::new(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("[{\"path\":"));
+        assert!(out.contains("\"name\":\"cmd.exe\",\"size\":0,\"modified\":"));
+        assert!(out.ends_with("}]\n"));
+
+        let parsed = args(&[
+            "--root",
+            &root,
+            "--query",
+            "cmd",
+            "--reindex",
+            "--format=binary",
+        ])
+        .unwrap()
+        .unwrap();
+        let mut out = Vec::new();
+        run(&parsed, Search::new(), &mut out).unwrap();
+        assert_eq!(
+            read_results(out.as_slice()).unwrap(),
+            [(expected.clone(), "cmd".to_string())]
+        );
+
+        let parsed = args(&["--root", &root, "--query", "(", "--reindex"])
+            .unwrap()
+            .unwrap();
+        assert!(run(&parsed, Search::new(), &mut Vec::new()).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
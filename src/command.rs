@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+/// The character that turns the search box into a command prompt.
+pub(crate) const COMMAND_SIGIL: char = '>';
+
+/// The shortest automatic update interval that can be set, in seconds.
+pub(crate) const MIN_UPDATE_INTERVAL: u64 = 60;
+
+/// The longest automatic update interval that can be set, a day in seconds.
+pub(crate) const MAX_UPDATE_INTERVAL: u64 = 86400;
+
+/// Reads an update interval in whole seconds, rejecting anything outside
+/// [`MIN_UPDATE_INTERVAL`]..=[`MAX_UPDATE_INTERVAL`].
+pub(crate) fn parse_update_interval(text: &str) -> Result<Duration, String> {
+    let range = MIN_UPDATE_INTERVAL..=MAX_UPDATE_INTERVAL;
+    match text.trim().parse::<u64>() {
+        Ok(seconds) if range.contains(&seconds) => Ok(Duration::from_secs(seconds)),
+        _ => Err(format!(
+            "The update interval must be a number of seconds from {} to {}",
+            MIN_UPDATE_INTERVAL, MAX_UPDATE_INTERVAL
+        )),
+    }
+}
+
+/// Actions that can be triggered from the search box instead of a search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AppCommand {
+    Reindex,
+    Settings,
+    Clear,
+    /// Shows how many files are indexed and how large the index file is.
+    Stats,
+    /// Sets the automatic update interval, given in seconds after the name.
+    Interval(Duration),
+    /// Lists the available commands.
+    Help,
+    /// Closes the window even when closing only minimizes it.
+    Quit,
+}
+
+impl AppCommand {
+    /// Fills in the argument typed after the command name. Only
+    /// [`AppCommand::Interval`] takes one; the others must be given alone.
+    fn with_argument(self, name: &str, argument: &str) -> Result<AppCommand, String> {
+        match self {
+            AppCommand::Interval(_) if argument.is_empty() => {
+                Err(format!("Usage: {}{} <seconds>", COMMAND_SIGIL, name))
+            }
+            AppCommand::Interval(_) => parse_update_interval(argument).map(AppCommand::Interval),
+            _ if argument.is_empty() => Ok(self),
+            _ => Err(format!(
+                "{}{} does not take an argument",
+                COMMAND_SIGIL, name
+            )),
+        }
+    }
+}
+
+/// Maps command names typed after [`COMMAND_SIGIL`] to the action they trigger.
+///
+/// Names are matched case-insensitively against the first word of the input, the
+/// rest being the command's argument, so `>interval 300` sets the update interval.
+/// Any input starting with the sigil is a command: `>unknown` or `>reindex now`
+/// are reported as errors rather than searched for.
+#[derive(Clone, Debug)]
+pub(crate) struct CommandRegistry {
+    commands: Vec<(String, AppCommand)>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        let mut registry = CommandRegistry {
+            commands: Vec::new(),
+        };
+        registry.register("reindex", AppCommand::Reindex);
+        registry.register("settings", AppCommand::Settings);
+        registry.register("clear", AppCommand::Clear);
+        registry.register("stats", AppCommand::Stats);
+        // The interval is replaced by the one typed after the name.
+        registry.register("interval", AppCommand::Interval(Duration::ZERO));
+        registry.register("help", AppCommand::Help);
+        registry.register("quit", AppCommand::Quit);
+        registry
+    }
+}
+
+impl CommandRegistry {
+    /// Registers `name` as a shortcut for `command`, replacing any previous binding.
+    pub(crate) fn register(&mut self, name: &str, command: AppCommand) {
+        let name = name.trim().to_lowercase();
+        match self.commands.iter_mut().find(|(bound, _)| *bound == name) {
+            Some((_, bound)) => *bound = command,
+            None => self.commands.push((name, command)),
+        }
+    }
+
+    /// Returns the command typed in `input`, or why it is not one, or `None` if
+    /// `input` does not start with [`COMMAND_SIGIL`] and should be searched for.
+    pub(crate) fn parse(&self, input: &str) -> Option<Result<AppCommand, String>> {
+        let line = input.trim().strip_prefix(COMMAND_SIGIL)?.trim();
+        let (name, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let name = name.to_lowercase();
+        Some(
+            match self.commands.iter().find(|(bound, _)| *bound == name) {
+                Some((_, command)) => command.with_argument(&name, argument.trim()),
+                None if name.is_empty() => Err(format!("Commands: {}", self.usage())),
+                None => Err(format!(
+                    "Unknown command {}{}, type {}help for the list",
+                    COMMAND_SIGIL, name, COMMAND_SIGIL
+                )),
+            },
+        )
+    }
+
+    /// The commands as they are typed, e.g. `>interval <seconds>`, comma separated.
+    pub(crate) fn usage(&self) -> String {
+        let usages: Vec<String> = self
+            .commands
+            .iter()
+            .map(|(name, command)| match command {
+                AppCommand::Interval(_) => format!("{}{} <seconds>", COMMAND_SIGIL, name),
+                _ => format!("{}{}", COMMAND_SIGIL, name),
+            })
+            .collect();
+        usages.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_builtin_commands() {
+        let registry = CommandRegistry::default();
+        assert_eq!(registry.parse(">reindex"), Some(Ok(AppCommand::Reindex)));
+        assert_eq!(
+            registry.parse(" > Settings "),
+            Some(Ok(AppCommand::Settings))
+        );
+        assert_eq!(registry.parse(">clear"), Some(Ok(AppCommand::Clear)));
+        assert_eq!(registry.parse(">stats"), Some(Ok(AppCommand::Stats)));
+        assert_eq!(registry.parse(">HELP"), Some(Ok(AppCommand::Help)));
+        assert_eq!(registry.parse(">quit"), Some(Ok(AppCommand::Quit)));
+        assert_eq!(
+            registry.parse(">interval  300 "),
+            Some(Ok(AppCommand::Interval(Duration::from_secs(300))))
+        );
+        assert_eq!(registry.parse("clear"), None);
+        assert_eq!(registry.parse("report > draft"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let registry = CommandRegistry::default();
+        for input in [
+            ">unknown",
+            ">reindex now",
+            ">",
+            ">interval",
+            ">interval soon",
+        ] {
+            assert!(
+                matches!(registry.parse(input), Some(Err(_))),
+                "{} should be rejected",
+                input
+            );
+        }
+        assert!(registry
+            .parse(">unknown")
+            .unwrap()
+            .unwrap_err()
+            .contains(">help"));
+        assert!(registry
+            .parse(">")
+            .unwrap()
+            .unwrap_err()
+            .contains(">interval <seconds>"));
+    }
+
+    #[test]
+    fn test_update_interval_range() {
+        assert_eq!(parse_update_interval("60"), Ok(Duration::from_secs(60)));
+        assert_eq!(
+            parse_update_interval(" 86400 "),
+            Ok(Duration::from_secs(86400))
+        );
+        for text in ["59", "86401", "0", "-300", "1.5", "", "ten"] {
+            assert!(
+                parse_update_interval(text).is_err(),
+                "{} should be rejected",
+                text
+            );
+        }
+        let registry = CommandRegistry::default();
+        assert!(matches!(registry.parse(">interval 30"), Some(Err(_))));
+        assert!(matches!(registry.parse(">interval 100000"), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_register_alias() {
+        let mut registry = CommandRegistry::default();
+        registry.register("r", AppCommand::Reindex);
+        registry.register("clear", AppCommand::Settings);
+        assert_eq!(registry.parse(">r"), Some(Ok(AppCommand::Reindex)));
+        assert_eq!(registry.parse(">clear"), Some(Ok(AppCommand::Settings)));
+        assert_eq!(registry.commands.len(), 8);
+    }
+}
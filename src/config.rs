@@ -0,0 +1,764 @@
+use std::{
+    fs::{read_to_string, rename},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use toml_edit::{value, Array, Document, InlineTable, Item, Table};
+
+use crate::drives::DriveKind;
+use crate::guard::{ResourceLimits, MB};
+use crate::schedule::parse_roots;
+use crate::search_engine::{parse_path_list, IndexMode};
+
+/// The settings file read at startup.
+pub(crate) const CONFIG_FILE: &str = "config.toml";
+
+/// The colors of the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Theme {
+    /// Whatever the operating system prefers.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// The settings in `config.toml`, for example:
+///
+/// ```toml
+/// update_interval = 600  # seconds between reindexing runs
+/// roots = ["C:\\", "D:\\"]
+/// exclude = ["node_modules", "C:\\Windows"]
+/// theme = "dark"         # "light", "dark" or "system"
+/// match_case = false
+/// index_created_time = false  # store creation times for `created:` filters
+/// minimize_on_close = false  # keep indexing in the background when closed
+/// max_depth = 20         # folder levels indexed below each root
+/// drives = ["fixed", "removable"]  # drive kinds indexed when no roots are set
+/// font = "fonts/NotoSansJP-Regular.otf"  # defaults to font.otf, then the built-in font
+/// index_mode = "disk"    # "memory" keeps the index in the window until it closes
+/// skip_hidden = true     # leave hidden and system files out of the index
+/// respect_gitignore = false  # skip what .gitignore files inside the roots ignore
+/// index_streams = false  # NTFS alternate data streams, with the `ads` feature
+/// blacklist = ["Thumbs.db", "C:\\pagefile.sys"]  # never indexed nor shown
+/// watch = ["C:\\Projects"]  # kept current between scheduled updates
+/// schedule = [{ root = "D:\\Data", interval = 3600 }, { root = "E:\\" }]
+/// min_free_disk_mb = 100     # 0 turns the guard off
+/// min_free_memory_mb = 256
+///
+/// [categories]           # replaces or adds extension groups
+/// image = ["jpg", "png", "webp"]
+///
+/// [http]                 # the endpoint of the `http` feature
+/// port = 7878
+/// root = "C:\\"
+/// ```
+///
+/// Every key is optional. The update interval falls back to `updateTime.ini`
+/// when missing; the other files earlier versions read are moved into the config
+/// once by [`Config::migrate`]. Keys this version does not know are kept when
+/// the file is saved.
+#[derive(Clone, Debug)]
+pub(crate) struct Config {
+    pub(crate) update_interval: Option<Duration>,
+    /// The roots to index; empty uses the built-in default.
+    pub(crate) roots: Vec<PathBuf>,
+    pub(crate) exclude_dirs: Option<Vec<PathBuf>>,
+    pub(crate) theme: Theme,
+    pub(crate) match_case: bool,
+    /// Whether indexing stores the creation time of each file.
+    pub(crate) index_created_time: bool,
+    /// Whether closing the window minimizes it instead of quitting.
+    pub(crate) minimize_on_close: bool,
+    pub(crate) max_depth: Option<usize>,
+    /// The kinds of drives indexed when `roots` is empty, see [`DriveKind::parse`].
+    pub(crate) drive_kinds: Option<Vec<DriveKind>>,
+    /// The font file of the UI, see [`load_font`](crate::fonts::load_font).
+    pub(crate) font: Option<PathBuf>,
+    pub(crate) index_mode: IndexMode,
+    pub(crate) skip_hidden: bool,
+    pub(crate) respect_gitignore: bool,
+    pub(crate) index_streams: bool,
+    /// Entries of the [`Blacklist`](crate::Blacklist), names or full paths.
+    pub(crate) blacklist: Vec<String>,
+    /// The folders watched for changes; empty turns watching off.
+    pub(crate) watch: Vec<PathBuf>,
+    /// The roots updated on a schedule of their own, each with its interval or
+    /// `None` for the global one. Empty schedules `roots`.
+    pub(crate) schedule: Vec<(PathBuf, Option<Duration>)>,
+    pub(crate) resource_limits: ResourceLimits,
+    /// Extension groups by name, see [`Categories::with_overrides`](crate::category::Categories::with_overrides).
+    pub(crate) categories: Vec<(String, Vec<String>)>,
+    pub(crate) http_port: Option<u16>,
+    pub(crate) http_root: Option<PathBuf>,
+    document: Document,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            update_interval: None,
+            roots: Vec::new(),
+            exclude_dirs: None,
+            theme: Theme::default(),
+            match_case: false,
+            index_created_time: false,
+            minimize_on_close: false,
+            max_depth: None,
+            drive_kinds: None,
+            font: None,
+            index_mode: IndexMode::default(),
+            skip_hidden: true,
+            respect_gitignore: false,
+            index_streams: false,
+            blacklist: Vec::new(),
+            watch: Vec::new(),
+            schedule: Vec::new(),
+            resource_limits: ResourceLimits::default(),
+            categories: Vec::new(),
+            http_port: None,
+            http_root: None,
+            document: Document::new(),
+        }
+    }
+}
+
+impl PartialEq for Config {
+    fn eq(&self, other: &Self) -> bool {
+        self.update_interval == other.update_interval
+            && self.roots == other.roots
+            && self.exclude_dirs == other.exclude_dirs
+            && self.theme == other.theme
+            && self.match_case == other.match_case
+            && self.index_created_time == other.index_created_time
+            && self.minimize_on_close == other.minimize_on_close
+            && self.max_depth == other.max_depth
+            && self.drive_kinds == other.drive_kinds
+            && self.font == other.font
+            && self.index_mode == other.index_mode
+            && self.skip_hidden == other.skip_hidden
+            && self.respect_gitignore == other.respect_gitignore
+            && self.index_streams == other.index_streams
+            && self.blacklist == other.blacklist
+            && self.watch == other.watch
+            && self.schedule == other.schedule
+            && self.resource_limits == other.resource_limits
+            && self.categories == other.categories
+            && self.http_port == other.http_port
+            && self.http_root == other.http_root
+    }
+}
+
+/// The settings files of earlier versions, with the key of `config.toml` each
+/// one is moved to by [`Config::migrate`].
+const LEGACY_FILES: &[(&str, &str)] = &[
+    ("category.ini", "categories"),
+    ("roots.ini", "schedule"),
+    ("indexMode.ini", "index_mode"),
+    ("limits.ini", "min_free_disk_mb"),
+    ("blacklist.ini", "blacklist"),
+    ("ignore.ini", "exclude"),
+    ("http.ini", "http"),
+    ("gitignore.ini", "respect_gitignore"),
+    ("indexStreams.ini", "index_streams"),
+    ("watch.ini", "watch"),
+    ("skipHidden.ini", "skip_hidden"),
+];
+
+impl Config {
+    /// Reads the settings from `path`. No file gives the defaults; a file that is
+    /// not valid TOML is an error, so it is not overwritten unnoticed.
+    pub(crate) fn load(path: &str) -> Result<Self, String> {
+        match read_to_string(path) {
+            Ok(buf) => Config::parse(&buf).map_err(|e| format!("Invalid {}: {}", path, e)),
+            Err(_) => Ok(Config::default()),
+        }
+    }
+
+    /// Parses the text of a config file. Values of the wrong type are ignored.
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let document: Document = text.parse().map_err(|e| format!("{}", e))?;
+        let strings = |key: &str| -> Option<Vec<String>> {
+            let array = document.get(key)?.as_array()?;
+            Some(
+                array
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .map(String::from)
+                    .collect(),
+            )
+        };
+        let paths = |key: &str| -> Option<Vec<PathBuf>> {
+            strings(key).map(|paths| paths.into_iter().map(PathBuf::from).collect())
+        };
+        let integer = |key: &str| -> Option<u64> {
+            document
+                .get(key)?
+                .as_integer()
+                .and_then(|n| u64::try_from(n).ok())
+        };
+        let flag = |key: &str, default: bool| -> bool {
+            document.get(key).and_then(Item::as_bool).unwrap_or(default)
+        };
+        let theme = match document.get("theme").and_then(Item::as_str) {
+            Some(theme) if theme.eq_ignore_ascii_case("light") => Theme::Light,
+            Some(theme) if theme.eq_ignore_ascii_case("dark") => Theme::Dark,
+            _ => Theme::System,
+        };
+        let mut resource_limits = ResourceLimits::default();
+        if let Some(megabytes) = integer("min_free_disk_mb") {
+            resource_limits.min_free_disk = megabytes.saturating_mul(MB);
+        }
+        if let Some(megabytes) = integer("min_free_memory_mb") {
+            resource_limits.min_free_memory = megabytes.saturating_mul(MB);
+        }
+        let schedule = document
+            .get("schedule")
+            .and_then(Item::as_array)
+            .map(|roots| {
+                roots
+                    .iter()
+                    .filter_map(|root| {
+                        let root = root.as_inline_table()?;
+                        let path = PathBuf::from(root.get("root")?.as_str()?);
+                        let interval = root
+                            .get("interval")
+                            .and_then(|interval| interval.as_integer())
+                            .and_then(|secs| u64::try_from(secs).ok())
+                            .map(Duration::from_secs);
+                        Some((path, interval))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let categories = document
+            .get("categories")
+            .and_then(Item::as_table_like)
+            .map(|groups| {
+                groups
+                    .iter()
+                    .filter_map(|(name, extensions)| {
+                        let extensions = extensions
+                            .as_array()?
+                            .iter()
+                            .filter_map(|ext| ext.as_str())
+                            .map(String::from)
+                            .collect();
+                        Some((name.to_string(), extensions))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let http = document.get("http").and_then(Item::as_table_like);
+        Ok(Config {
+            update_interval: integer("update_interval").map(Duration::from_secs),
+            roots: paths("roots").unwrap_or_default(),
+            exclude_dirs: paths("exclude"),
+            theme,
+            match_case: flag("match_case", false),
+            index_created_time: flag("index_created_time", false),
+            minimize_on_close: flag("minimize_on_close", false),
+            max_depth: integer("max_depth").map(|depth| depth as usize),
+            drive_kinds: document
+                .get("drives")
+                .and_then(Item::as_array)
+                .map(|kinds| {
+                    kinds
+                        .iter()
+                        .filter_map(|kind| kind.as_str().and_then(DriveKind::parse))
+                        .collect()
+                }),
+            font: document
+                .get("font")
+                .and_then(Item::as_str)
+                .map(PathBuf::from),
+            index_mode: document
+                .get("index_mode")
+                .and_then(Item::as_str)
+                .and_then(IndexMode::parse)
+                .unwrap_or_default(),
+            skip_hidden: flag("skip_hidden", true),
+            respect_gitignore: flag("respect_gitignore", false),
+            index_streams: flag("index_streams", false),
+            blacklist: strings("blacklist").unwrap_or_default(),
+            watch: paths("watch").unwrap_or_default(),
+            schedule,
+            resource_limits,
+            categories,
+            http_port: http
+                .and_then(|http| http.get("port")?.as_integer())
+                .and_then(|port| u16::try_from(port).ok()),
+            http_root: http
+                .and_then(|http| http.get("root")?.as_str())
+                .map(PathBuf::from),
+            document,
+        })
+    }
+
+    /// Writes the settings to `path`, keeping the comments and unknown keys of
+    /// the file they were loaded from.
+    pub(crate) fn save(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let document = &mut self.document;
+        match self.update_interval {
+            Some(interval) => document["update_interval"] = value(interval.as_secs() as i64),
+            None => {
+                document.remove("update_interval");
+            }
+        }
+        let strings = |strings: &[String]| value(strings.iter().collect::<Array>());
+        let paths = |paths: &[PathBuf]| {
+            let array: Array = paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            value(array)
+        };
+        if self.roots.is_empty() {
+            document.remove("roots");
+        } else {
+            document["roots"] = paths(&self.roots);
+        }
+        match &self.exclude_dirs {
+            Some(exclude_dirs) => document["exclude"] = paths(exclude_dirs),
+            None => {
+                document.remove("exclude");
+            }
+        }
+        document["theme"] = value(match self.theme {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        });
+        document["match_case"] = value(self.match_case);
+        document["index_created_time"] = value(self.index_created_time);
+        document["minimize_on_close"] = value(self.minimize_on_close);
+        match self.max_depth {
+            Some(depth) => document["max_depth"] = value(depth as i64),
+            None => {
+                document.remove("max_depth");
+            }
+        }
+        match &self.drive_kinds {
+            Some(kinds) => {
+                let kinds: Array = kinds.iter().map(|kind| kind.name()).collect();
+                document["drives"] = value(kinds);
+            }
+            None => {
+                document.remove("drives");
+            }
+        }
+        match &self.font {
+            Some(font) => document["font"] = value(font.to_string_lossy().into_owned()),
+            None => {
+                document.remove("font");
+            }
+        }
+        document["index_mode"] = value(self.index_mode.name());
+        document["skip_hidden"] = value(self.skip_hidden);
+        document["respect_gitignore"] = value(self.respect_gitignore);
+        document["index_streams"] = value(self.index_streams);
+        if self.blacklist.is_empty() {
+            document.remove("blacklist");
+        } else {
+            document["blacklist"] = strings(&self.blacklist);
+        }
+        if self.watch.is_empty() {
+            document.remove("watch");
+        } else {
+            document["watch"] = paths(&self.watch);
+        }
+        if self.schedule.is_empty() {
+            document.remove("schedule");
+        } else {
+            let schedule: Array = self
+                .schedule
+                .iter()
+                .map(|(root, interval)| {
+                    let mut entry = InlineTable::new();
+                    entry.insert("root", root.to_string_lossy().into_owned().into());
+                    if let Some(interval) = interval {
+                        entry.insert("interval", (interval.as_secs() as i64).into());
+                    }
+                    entry
+                })
+                .collect();
+            document["schedule"] = value(schedule);
+        }
+        let defaults = ResourceLimits::default();
+        for (key, limit, default) in [
+            (
+                "min_free_disk_mb",
+                self.resource_limits.min_free_disk,
+                defaults.min_free_disk,
+            ),
+            (
+                "min_free_memory_mb",
+                self.resource_limits.min_free_memory,
+                defaults.min_free_memory,
+            ),
+        ] {
+            if limit == default {
+                document.remove(key);
+            } else {
+                document[key] = value((limit / MB) as i64);
+            }
+        }
+        if self.categories.is_empty() {
+            document.remove("categories");
+        } else {
+            let mut categories = Table::new();
+            for (name, extensions) in &self.categories {
+                categories[name.as_str()] = strings(extensions);
+            }
+            document["categories"] = Item::Table(categories);
+        }
+        if self.http_port.is_none() && self.http_root.is_none() {
+            document.remove("http");
+        } else {
+            let mut http = Table::new();
+            if let Some(port) = self.http_port {
+                http["port"] = value(i64::from(port));
+            }
+            if let Some(root) = &self.http_root {
+                http["root"] = value(root.to_string_lossy().into_owned());
+            }
+            document["http"] = Item::Table(http);
+        }
+        std::fs::write(path, document.to_string())
+    }
+
+    /// Moves the settings of the files earlier versions read from `dir` into
+    /// this config and saves it there as `config.toml`. A setting the config
+    /// already has wins over its file. Each file moved is then renamed to
+    /// `<name>.migrated`, so this happens once. Returns the files moved.
+    pub(crate) fn migrate(&mut self, dir: &Path) -> io::Result<Vec<&'static str>> {
+        let mut moved = Vec::new();
+        for &(file, key) in LEGACY_FILES {
+            let Ok(text) = read_to_string(dir.join(file)) else {
+                continue;
+            };
+            let set = self.document.contains_key(key)
+                || (key == "min_free_disk_mb" && self.document.contains_key("min_free_memory_mb"));
+            if !set {
+                self.apply_legacy_file(file, &text);
+            }
+            moved.push(file);
+        }
+        if moved.is_empty() {
+            return Ok(moved);
+        }
+        self.save(dir.join(CONFIG_FILE))?;
+        for file in &moved {
+            let mut migrated = dir.join(file).into_os_string();
+            migrated.push(".migrated");
+            rename(dir.join(file), migrated)?;
+        }
+        Ok(moved)
+    }
+
+    /// Takes the setting held by `text`, the contents of the legacy `file`.
+    fn apply_legacy_file(&mut self, file: &str, text: &str) {
+        let lines = || {
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+        };
+        let enabled = |word: &str| text.trim().eq_ignore_ascii_case(word);
+        match file {
+            "category.ini" => {
+                self.categories = lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .filter_map(|line| {
+                        let (name, extensions) = line.split_once('=')?;
+                        let extensions = extensions
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|ext| !ext.is_empty())
+                            .map(String::from)
+                            .collect();
+                        Some((name.trim().to_string(), extensions))
+                    })
+                    .collect();
+            }
+            "roots.ini" => self.schedule = parse_roots(text),
+            "indexMode.ini" => {
+                self.index_mode = if enabled("memory") {
+                    IndexMode::Memory
+                } else {
+                    IndexMode::Disk
+                }
+            }
+            "limits.ini" => self.resource_limits = ResourceLimits::parse(text),
+            "blacklist.ini" => self.blacklist = lines().collect(),
+            "ignore.ini" => self.exclude_dirs = Some(lines().map(PathBuf::from).collect()),
+            "http.ini" => {
+                for line in text.lines() {
+                    match line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                        Some(("port", port)) => self.http_port = port.parse().ok(),
+                        Some(("root", root)) if !root.is_empty() => {
+                            self.http_root = Some(PathBuf::from(root));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "gitignore.ini" => self.respect_gitignore = enabled("true"),
+            "indexStreams.ini" => self.index_streams = enabled("true"),
+            "watch.ini" => self.watch = parse_path_list(text),
+            "skipHidden.ini" => self.skip_hidden = !enabled("false"),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_every_setting() {
+        let config = Config::parse(
+            r#"
+            update_interval = 900
+            roots = ["C:\\", "D:\\Data"]
+            exclude = ["node_modules", "C:\\Windows"]
+            theme = "Dark"
+            match_case = true
+            index_created_time = true
+            minimize_on_close = true
+            max_depth = 12
+            drives = ["fixed", "network", "floppy"]
+            font = "fonts/custom.ttf"
+            index_mode = "Memory"
+            skip_hidden = false
+            respect_gitignore = true
+            index_streams = true
+            blacklist = ["Thumbs.db", "C:\\pagefile.sys"]
+            watch = ["C:\\Projects"]
+            schedule = [{ root = "D:\\Data", interval = 3600 }, { root = "E:\\" }, { interval = 5 }]
+            min_free_disk_mb = 0
+
+            [categories]
+            image = ["png"]
+            model = ["obj", "fbx"]
+
+            [http]
+            port = 8080
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.update_interval, Some(Duration::from_secs(900)));
+        assert_eq!(
+            config.roots,
+            [PathBuf::from("C:\\"), PathBuf::from("D:\\Data")]
+        );
+        assert_eq!(
+            config.exclude_dirs,
+            Some(vec![
+                PathBuf::from("node_modules"),
+                PathBuf::from("C:\\Windows")
+            ])
+        );
+        assert_eq!(config.theme, Theme::Dark);
+        assert!(config.match_case);
+        assert!(config.index_created_time);
+        assert!(config.minimize_on_close);
+        assert_eq!(config.max_depth, Some(12));
+        assert_eq!(
+            config.drive_kinds,
+            Some(vec![DriveKind::Fixed, DriveKind::Network])
+        );
+        assert_eq!(config.font, Some(PathBuf::from("fonts/custom.ttf")));
+        assert_eq!(config.index_mode, IndexMode::Memory);
+        assert!(!config.skip_hidden);
+        assert!(config.respect_gitignore);
+        assert!(config.index_streams);
+        assert_eq!(config.blacklist, ["Thumbs.db", "C:\\pagefile.sys"]);
+        assert_eq!(config.watch, [PathBuf::from("C:\\Projects")]);
+        assert_eq!(
+            config.schedule,
+            [
+                (PathBuf::from("D:\\Data"), Some(Duration::from_secs(3600))),
+                (PathBuf::from("E:\\"), None)
+            ]
+        );
+        assert_eq!(config.resource_limits.min_free_disk, 0);
+        assert_eq!(
+            config.resource_limits.min_free_memory,
+            ResourceLimits::default().min_free_memory
+        );
+        assert_eq!(
+            config.categories,
+            [
+                ("image".to_string(), vec!["png".to_string()]),
+                (
+                    "model".to_string(),
+                    vec!["obj".to_string(), "fbx".to_string()]
+                )
+            ]
+        );
+        assert_eq!(config.http_port, Some(8080));
+        assert_eq!(config.http_root, None);
+    }
+
+    #[test]
+    fn test_missing_keys_use_defaults() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        let config = Config::parse("theme = \"purple\"\nmax_depth = -1\nroots = 3").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.theme, Theme::System);
+        assert!(Config::parse("roots = [").is_err());
+    }
+
+    #[test]
+    fn test_save_keeps_unknown_keys() {
+        let path = std::env::temp_dir().join("search_config_test.toml");
+        let path = path.to_str().unwrap();
+        std::fs::write(
+            path,
+            "# my settings\nupdate_interval = 600\nfuture_option = \"kept\"\n\n[plugins]\nenabled = true\n",
+        )
+        .unwrap();
+        let mut config = Config::load(path).unwrap();
+        config.update_interval = Some(Duration::from_secs(1200));
+        config.theme = Theme::Light;
+        config.save(path).unwrap();
+
+        let saved = read_to_string(path).unwrap();
+        assert!(saved.contains("# my settings"));
+        assert!(saved.contains("future_option = \"kept\""));
+        assert!(saved.contains("[plugins]\nenabled = true"));
+        let reloaded = Config::load(path).unwrap();
+        assert_eq!(reloaded, config);
+        assert_eq!(reloaded.update_interval, Some(Duration::from_secs(1200)));
+        let _ = std::fs::remove_file(path);
+        assert_eq!(Config::load(path).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_save_and_reload_every_setting() {
+        let path = std::env::temp_dir().join("search_config_every_setting_test.toml");
+        let path = path.to_str().unwrap();
+        let mut config = Config {
+            index_mode: IndexMode::Memory,
+            skip_hidden: false,
+            blacklist: vec!["Thumbs.db".to_string()],
+            watch: vec![PathBuf::from("/projects")],
+            schedule: vec![
+                (PathBuf::from("/data"), Some(Duration::from_secs(60))),
+                (PathBuf::from("/media"), None),
+            ],
+            resource_limits: ResourceLimits {
+                min_free_disk: 0,
+                ..ResourceLimits::default()
+            },
+            categories: vec![("model".to_string(), vec!["obj".to_string()])],
+            http_port: Some(8080),
+            http_root: Some(PathBuf::from("/srv")),
+            ..Config::default()
+        };
+        config.save(path).unwrap();
+        assert_eq!(Config::load(path).unwrap(), config);
+
+        // Settings back at their defaults leave no key behind.
+        let mut config = Config::load(path).unwrap();
+        config.blacklist.clear();
+        config.resource_limits = ResourceLimits::default();
+        config.categories.clear();
+        config.http_port = None;
+        config.http_root = None;
+        config.save(path).unwrap();
+        let saved = read_to_string(path).unwrap();
+        for key in ["blacklist", "min_free_disk_mb", "categories", "http"] {
+            assert!(!saved.contains(key), "{} left in\n{}", key, saved);
+        }
+        assert_eq!(Config::load(path).unwrap(), config);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_legacy_files() {
+        let dir = std::env::temp_dir().join("search_config_migrate_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = [
+            ("category.ini", "# mine\nimage = png\nmodel=obj,.FBX\n"),
+            ("roots.ini", "C:\\Projects=60\nD:\\\n"),
+            ("indexMode.ini", "memory\n"),
+            ("limits.ini", "min_free_disk_mb=2048\n"),
+            ("blacklist.ini", "Thumbs.db\n\ndesktop.ini\n"),
+            ("ignore.ini", "node_modules\ncache-*\n"),
+            ("http.ini", "port=8080\nroot=D:\\\n"),
+            ("gitignore.ini", "true"),
+            ("indexStreams.ini", "TRUE\n"),
+            ("watch.ini", "C:\\Projects;D:\\Notes"),
+            ("skipHidden.ini", "false"),
+        ];
+        for (file, text) in files {
+            std::fs::write(dir.join(file), text).unwrap();
+        }
+        // A setting already in the config wins over its file.
+        std::fs::write(
+            dir.join(CONFIG_FILE),
+            "theme = \"dark\"\nblacklist = [\"keep\"]\n",
+        )
+        .unwrap();
+
+        let config_file = dir.join(CONFIG_FILE);
+        let mut config = Config::load(config_file.to_str().unwrap()).unwrap();
+        let moved = config.migrate(&dir).unwrap();
+        assert_eq!(moved.len(), files.len());
+        for (file, text) in files {
+            assert!(!dir.join(file).exists());
+            let migrated = dir.join(format!("{}.migrated", file));
+            assert_eq!(read_to_string(migrated).unwrap(), text);
+        }
+
+        let config = Config::load(config_file.to_str().unwrap()).unwrap();
+        assert_eq!(config.theme, Theme::Dark);
+        assert_eq!(
+            config.categories,
+            [
+                ("image".to_string(), vec!["png".to_string()]),
+                (
+                    "model".to_string(),
+                    vec!["obj".to_string(), ".FBX".to_string()]
+                )
+            ]
+        );
+        assert_eq!(
+            config.schedule,
+            [
+                (PathBuf::from("C:\\Projects"), Some(Duration::from_secs(60))),
+                (PathBuf::from("D:\\"), None)
+            ]
+        );
+        assert_eq!(config.index_mode, IndexMode::Memory);
+        assert_eq!(config.resource_limits.min_free_disk, 2048 * MB);
+        assert_eq!(config.blacklist, ["keep"]);
+        assert_eq!(
+            config.exclude_dirs,
+            Some(vec![
+                PathBuf::from("node_modules"),
+                PathBuf::from("cache-*")
+            ])
+        );
+        assert_eq!(config.http_port, Some(8080));
+        assert_eq!(config.http_root, Some(PathBuf::from("D:\\")));
+        assert!(config.respect_gitignore);
+        assert!(config.index_streams);
+        assert_eq!(
+            config.watch,
+            [PathBuf::from("C:\\Projects"), PathBuf::from("D:\\Notes")]
+        );
+        assert!(!config.skip_hidden);
+
+        // Once moved, nothing is left to migrate.
+        let mut config = Config::load(config_file.to_str().unwrap()).unwrap();
+        assert!(config.migrate(&dir).unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
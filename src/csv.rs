@@ -0,0 +1,118 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use crate::search_engine::IndexedFile;
+
+/// The first line of every results export.
+const CSV_HEADER: &str = "path,name,size,modified";
+
+/// Quotes a field for CSV as RFC 4180 describes: fields holding a comma, a quote
+/// or a line break are enclosed in quotes, with their own quotes doubled.
+pub(crate) fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Writes the index entries of the results to `out` as CSV with the columns
+/// `path`, `name`, `size` in bytes and `modified` in seconds since the Unix epoch.
+/// Size and modification time are left empty where the index does not know them.
+pub(crate) fn write_results_csv(files: &[IndexedFile], out: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(out)?);
+    write!(writer, "{}\r\n", CSV_HEADER)?;
+    for file in files {
+        let path = &file.path;
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        let size = file
+            .known_size()
+            .map_or(String::new(), |size| size.to_string());
+        let modified = file
+            .modified_time()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(String::new(), |modified| modified.as_secs().to_string());
+        write!(
+            writer,
+            "{},{},{},{}\r\n",
+            csv_field(&path.to_string_lossy()),
+            csv_field(&name.to_string_lossy()),
+            size,
+            modified
+        )?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits CSV text into records of fields, undoing `csv_field`.
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        let mut records = Vec::new();
+        let mut record = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                ('"', _) => quoted = !quoted,
+                (',', false) => record.push(std::mem::take(&mut field)),
+                ('\r', false) => {}
+                ('\n', false) => {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                (c, _) => field.push(c),
+            }
+        }
+        records
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("plain.txt"), "plain.txt");
+        assert_eq!(csv_field("a,b.txt"), "\"a,b.txt\"");
+        assert_eq!(csv_field("say \"hi\".txt"), "\"say \"\"hi\"\".txt\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_results_round_trip() {
+        let dir = std::env::temp_dir().join("search_csv_export_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let tricky = dir.join("report, \"final\".txt");
+        std::fs::write(&tricky, "12345").unwrap();
+        let missing = dir.join("missing.txt");
+        let out = dir.join("results.csv");
+        let files = vec![
+            IndexedFile::read(tricky.clone()),
+            IndexedFile::read(missing.clone()),
+        ];
+        std::fs::remove_file(&tricky).unwrap();
+        write_results_csv(&files, &out).unwrap();
+
+        let records = parse_csv(&std::fs::read_to_string(&out).unwrap());
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], ["path", "name", "size", "modified"]);
+        assert_eq!(records[1][0], tricky.to_string_lossy());
+        assert_eq!(records[1][1], "report, \"final\".txt");
+        assert_eq!(records[1][2], "5");
+        assert!(records[1][3].parse::<u64>().unwrap() > 0);
+        assert_eq!(
+            records[2],
+            [&*missing.to_string_lossy(), "missing.txt", "", ""]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
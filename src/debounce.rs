@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+/// How long the search box must be left alone before typing runs a search.
+pub(crate) const DEFAULT_SEARCH_DELAY: Duration = Duration::from_millis(150);
+
+/// Delays an action until its trigger has been quiet for a while.
+///
+/// Every `changed` restarts the wait; `poll` reports once that the wait is over.
+/// The last change always fires eventually, so no input is dropped.
+#[derive(Clone, Debug)]
+pub(crate) struct Debounce {
+    delay: Duration,
+    changed_at: Option<Instant>,
+}
+
+impl Debounce {
+    pub(crate) fn new(delay: Duration) -> Self {
+        Debounce {
+            delay,
+            changed_at: None,
+        }
+    }
+
+    pub(crate) fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    pub(crate) fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Records a change at `now`, restarting the wait.
+    pub(crate) fn changed(&mut self, now: Instant) {
+        self.changed_at = Some(now);
+    }
+
+    /// Drops a pending change, e.g. because the action already ran.
+    pub(crate) fn cancel(&mut self) {
+        self.changed_at = None;
+    }
+
+    /// Returns whether the action is due at `now`, and if so clears the pending
+    /// change so that it fires only once.
+    pub(crate) fn poll(&mut self, now: Instant) -> bool {
+        match self.changed_at {
+            Some(changed_at) if now.saturating_duration_since(changed_at) >= self.delay => {
+                self.changed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// How long after `now` a pending change becomes due, or `None` when nothing
+    /// is pending.
+    pub(crate) fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.changed_at
+            .map(|changed_at| (changed_at + self.delay).saturating_duration_since(now))
+    }
+}
+
+impl Default for Debounce {
+    fn default() -> Self {
+        Debounce::new(DEFAULT_SEARCH_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replays keystrokes at the given millisecond offsets, polling every 10 ms,
+    /// and returns the offsets at which the action fired.
+    fn fired_at(delay_ms: u64, changes_ms: &[u64]) -> Vec<u64> {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut debounce = Debounce::new(Duration::from_millis(delay_ms));
+        let mut fired = Vec::new();
+        for ms in (0..=1000).step_by(10) {
+            if changes_ms.contains(&ms) {
+                debounce.changed(at(ms));
+            }
+            if debounce.poll(at(ms)) {
+                fired.push(ms);
+            }
+        }
+        fired
+    }
+
+    #[test]
+    fn test_fires_once_typing_stops() {
+        // Fast typing only searches after the last keystroke.
+        assert_eq!(fired_at(150, &[0, 50, 100, 150, 200]), [350]);
+        // A pause longer than the delay searches in between.
+        assert_eq!(fired_at(150, &[0, 50, 300, 320]), [200, 470]);
+        // Without a delay every keystroke searches at once.
+        assert_eq!(fired_at(0, &[0, 50, 100]), [0, 50, 100]);
+        assert!(fired_at(150, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_remaining_and_cancel() {
+        let start = Instant::now();
+        let mut debounce = Debounce::default();
+        assert_eq!(debounce.remaining(start), None);
+        debounce.changed(start);
+        assert_eq!(
+            debounce.remaining(start + Duration::from_millis(100)),
+            Some(Duration::from_millis(50))
+        );
+        assert_eq!(
+            debounce.remaining(start + Duration::from_secs(1)),
+            Some(Duration::ZERO)
+        );
+        debounce.cancel();
+        assert!(!debounce.poll(start + Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// How often the update thread checks for drives being mounted or removed.
+pub(crate) const DRIVE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The kind of device behind a drive letter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DriveKind {
+    Fixed,
+    Removable,
+    Network,
+    Optical,
+    RamDisk,
+    Unknown,
+}
+
+impl DriveKind {
+    /// Parses a kind as written in `config.toml`: `fixed`, `removable`,
+    /// `network`, `optical` or `ram`.
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "fixed" => Some(DriveKind::Fixed),
+            "removable" => Some(DriveKind::Removable),
+            "network" => Some(DriveKind::Network),
+            "optical" => Some(DriveKind::Optical),
+            "ram" => Some(DriveKind::RamDisk),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            DriveKind::Fixed => "fixed",
+            DriveKind::Removable => "removable",
+            DriveKind::Network => "network",
+            DriveKind::Optical => "optical",
+            DriveKind::RamDisk => "ram",
+            DriveKind::Unknown => "unknown",
+        }
+    }
+}
+
+/// The drives indexed unless configured otherwise. Optical and network drives
+/// are left out: they are slow to read and often empty or disconnected.
+pub(crate) const DEFAULT_DRIVE_KINDS: &[DriveKind] = &[DriveKind::Fixed, DriveKind::Removable];
+
+/// The roots of those `drives` whose kind is one of `kinds`.
+pub(crate) fn indexable_drives(drives: &[(char, DriveKind)], kinds: &[DriveKind]) -> Vec<PathBuf> {
+    drives
+        .iter()
+        .filter(|(_, kind)| kinds.contains(kind))
+        .map(|(letter, _)| drive_root(letter))
+        .collect()
+}
+
+/// Where indexing starts when no roots are configured: the existing drives of
+/// the given kinds on Windows, and `/` elsewhere.
+pub(crate) fn default_roots(kinds: &[DriveKind]) -> Vec<PathBuf> {
+    if cfg!(windows) {
+        indexable_drives(&logical_drives(), kinds)
+    } else {
+        vec![PathBuf::from("/")]
+    }
+}
+
+/// The mounted drive letters with the kind of each.
+pub(crate) fn logical_drives() -> Vec<(char, DriveKind)> {
+    mounted_drives()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|letter| (letter, drive_kind(&drive_root(&letter))))
+        .collect()
+}
+
+/// The kind of the drive whose root is `root`, such as `D:\`.
+#[cfg(windows)]
+pub(crate) fn drive_kind(root: &Path) -> DriveKind {
+    use std::os::windows::ffi::OsStrExt;
+
+    let root: Vec<u16> = root.as_os_str().encode_wide().chain(Some(0)).collect();
+    // The DRIVE_* values returned by GetDriveTypeW.
+    match unsafe { windows_sys::Win32::Storage::FileSystem::GetDriveTypeW(root.as_ptr()) } {
+        2 => DriveKind::Removable,
+        3 => DriveKind::Fixed,
+        4 => DriveKind::Network,
+        5 => DriveKind::Optical,
+        6 => DriveKind::RamDisk,
+        _ => DriveKind::Unknown,
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn drive_kind(_root: &Path) -> DriveKind {
+    DriveKind::Unknown
+}
+
+/// Drives that appeared or disappeared since the previous check, as roots like `D:\`.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct DriveChanges {
+    pub(crate) mounted: Vec<PathBuf>,
+    pub(crate) removed: Vec<PathBuf>,
+}
+
+/// Remembers which drive letters are mounted so changes can be reported.
+pub(crate) struct DriveTracker {
+    mounted: BTreeSet<char>,
+}
+
+impl DriveTracker {
+    pub(crate) fn new(mounted: BTreeSet<char>) -> Self {
+        DriveTracker { mounted }
+    }
+
+    /// Replaces the tracked drives with `mounted` and returns what changed.
+    pub(crate) fn update(&mut self, mounted: BTreeSet<char>) -> DriveChanges {
+        let changes = DriveChanges {
+            mounted: mounted.difference(&self.mounted).map(drive_root).collect(),
+            removed: self.mounted.difference(&mounted).map(drive_root).collect(),
+        };
+        self.mounted = mounted;
+        changes
+    }
+}
+
+fn drive_root(letter: &char) -> PathBuf {
+    PathBuf::from(format!("{}:\\", letter))
+}
+
+/// Letters of the currently mounted drives, or `None` where drive letters do not exist.
+#[cfg(windows)]
+pub(crate) fn mounted_drives() -> Option<BTreeSet<char>> {
+    let mask = unsafe { windows_sys::Win32::Storage::FileSystem::GetLogicalDrives() };
+    if mask == 0 {
+        return None;
+    }
+    Some(
+        ('A'..='Z')
+            .enumerate()
+            .filter(|(bit, _)| mask & (1 << bit) != 0)
+            .map(|(_, letter)| letter)
+            .collect(),
+    )
+}
+
+#[cfg(not(windows))]
+pub(crate) fn mounted_drives() -> Option<BTreeSet<char>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_configured_kinds_are_indexed() {
+        let drives = [
+            ('C', DriveKind::Fixed),
+            ('D', DriveKind::Optical),
+            ('E', DriveKind::Removable),
+            ('Z', DriveKind::Network),
+        ];
+        assert_eq!(
+            indexable_drives(&drives, DEFAULT_DRIVE_KINDS),
+            [PathBuf::from("C:\\"), PathBuf::from("E:\\")]
+        );
+        assert_eq!(
+            indexable_drives(&drives, &[DriveKind::Network]),
+            [PathBuf::from("Z:\\")]
+        );
+        assert!(indexable_drives(&drives, &[]).is_empty());
+        assert_eq!(DriveKind::parse(" Removable "), Some(DriveKind::Removable));
+        assert_eq!(DriveKind::parse("floppy"), None);
+        assert_eq!(
+            DriveKind::parse(DriveKind::RamDisk.name()),
+            Some(DriveKind::RamDisk)
+        );
+    }
+
+    #[test]
+    fn test_tracker_reports_mounts_and_removals() {
+        let mut tracker = DriveTracker::new(BTreeSet::from(['C', 'D']));
+        assert_eq!(
+            tracker.update(BTreeSet::from(['C', 'D'])),
+            DriveChanges::default()
+        );
+
+        let changes = tracker.update(BTreeSet::from(['C', 'E', 'F']));
+        assert_eq!(
+            changes.mounted,
+            vec![PathBuf::from("E:\\"), PathBuf::from("F:\\")]
+        );
+        assert_eq!(changes.removed, vec![PathBuf::from("D:\\")]);
+        assert_eq!(tracker.mounted, BTreeSet::from(['C', 'E', 'F']));
+
+        let changes = tracker.update(BTreeSet::from(['C']));
+        assert!(changes.mounted.is_empty());
+        assert_eq!(
+            changes.removed,
+            vec![PathBuf::from("E:\\"), PathBuf::from("F:\\")]
+        );
+    }
+}
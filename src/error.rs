@@ -0,0 +1,77 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Why a fallible [`SearchEngine`](crate::SearchEngine) method failed.
+#[derive(Debug)]
+pub enum SearchError {
+    /// A file the engine was asked to read does not exist.
+    NotFound(PathBuf),
+    /// The operation needs an index, but none has been built or loaded.
+    IndexNotLoaded,
+    /// The search key is not a valid pattern in the current search mode.
+    InvalidPattern(regex::Error),
+    /// An index file is corrupt, truncated or of an unknown version.
+    InvalidIndex(String),
+    /// Too little memory or disk space is left, see
+    /// [`ResourceLimits`](crate::ResourceLimits).
+    ResourceLimit(String),
+    /// Indexing was given up because its stop flag was set.
+    Stopped,
+    /// Reading or writing a file failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::NotFound(path) => write!(f, "{} not found", path.display()),
+            SearchError::IndexNotLoaded => write!(f, "No index has been built or loaded"),
+            SearchError::InvalidPattern(e) => write!(f, "Invalid pattern: {}", e),
+            SearchError::InvalidIndex(message) | SearchError::ResourceLimit(message) => {
+                write!(f, "{}", message)
+            }
+            SearchError::Stopped => write!(f, "Indexing stopped"),
+            SearchError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SearchError::InvalidPattern(e) => Some(e),
+            SearchError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SearchError {
+    fn from(e: io::Error) -> Self {
+        SearchError::Io(e)
+    }
+}
+
+impl From<regex::Error> for SearchError {
+    fn from(e: regex::Error) -> Self {
+        SearchError::InvalidPattern(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display_and_source() {
+        let missing = SearchError::NotFound(PathBuf::from("index.json"));
+        assert_eq!(missing.to_string(), "index.json not found");
+        assert!(missing.source().is_none());
+        let pattern = SearchError::from(regex::Error::Syntax("unclosed group".to_string()));
+        assert!(pattern.to_string().starts_with("Invalid pattern: "));
+        assert!(pattern.source().is_some());
+        let io = SearchError::from(io::Error::other("disk gone"));
+        assert_eq!(io.to_string(), "disk gone");
+        assert!(matches!(io, SearchError::Io(_)));
+    }
+}
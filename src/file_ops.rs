@@ -0,0 +1,208 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::search_engine::SearchEngine;
+
+/// A destructive operation on a search result that is waiting for confirmation.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum FileAction {
+    Rename { path: PathBuf, new_name: String },
+    Delete(PathBuf),
+    OpenElevated(PathBuf),
+}
+
+/// Renames `path` within its directory and updates the index to match.
+///
+/// `new_name` must be a plain file name; an existing target is never overwritten.
+pub(crate) fn rename_indexed<E: SearchEngine>(
+    engine: &mut E,
+    path: &Path,
+    new_name: &str,
+) -> io::Result<PathBuf> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() || new_name == "." || new_name == ".." || new_name.contains(['/', '\\'])
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the new name must be a plain file name",
+        ));
+    }
+    let target = path.with_file_name(new_name);
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    fs::rename(path, &target)?;
+    engine.remove_path(path);
+    engine.insert_path(target.clone());
+    Ok(target)
+}
+
+/// Moves `path` to the recycle bin and drops it from the index.
+pub(crate) fn trash_indexed<E: SearchEngine>(engine: &mut E, path: &Path) -> Result<(), String> {
+    trash::delete(path).map_err(|e| e.to_string())?;
+    engine.remove_path(path);
+    Ok(())
+}
+
+/// Maps every source file to a destination inside `dest_dir`.
+///
+/// Names that already exist according to `exists`, or that an earlier source in the
+/// plan already claimed, get a ` (n)` suffix before the extension.
+pub(crate) fn copy_plan(
+    sources: &[PathBuf],
+    dest_dir: &Path,
+    exists: impl Fn(&Path) -> bool,
+) -> Vec<(PathBuf, PathBuf)> {
+    let mut plan: Vec<(PathBuf, PathBuf)> = Vec::with_capacity(sources.len());
+    for source in sources {
+        let Some(file_name) = source.file_name() else {
+            continue;
+        };
+        let stem = Path::new(file_name)
+            .file_stem()
+            .unwrap_or(file_name)
+            .to_string_lossy()
+            .into_owned();
+        let extension = Path::new(file_name)
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        let mut target = dest_dir.join(file_name);
+        let mut n = 1;
+        while exists(&target) || plan.iter().any(|(_, planned)| *planned == target) {
+            target = dest_dir.join(format!("{} ({}){}", stem, n, extension));
+            n += 1;
+        }
+        plan.push((source.clone(), target));
+    }
+    plan
+}
+
+/// Copies every planned file and returns a summary, reporting failures per file.
+pub(crate) fn execute_copy_plan(plan: &[(PathBuf, PathBuf)]) -> String {
+    let mut failures = Vec::new();
+    for (source, target) in plan {
+        if let Err(e) = fs::copy(source, target) {
+            failures.push(format!("{}: {}", source.display(), e));
+        }
+    }
+    let copied = plan.len() - failures.len();
+    if failures.is_empty() {
+        format!("Copied {} files", copied)
+    } else {
+        format!(
+            "Copied {} files, {} failed:\n{}",
+            copied,
+            failures.len(),
+            failures.join("\n")
+        )
+    }
+}
+
+/// Builds the program and arguments that open `path` with the Windows `runas` verb,
+/// which shows a UAC prompt before opening the file elevated.
+pub(crate) fn elevated_open_command(path: &Path) -> (String, Vec<String>) {
+    // PowerShell single-quoted strings only need embedded quotes doubled.
+    let quoted = path.to_string_lossy().replace('\'', "''");
+    (
+        "powershell".to_string(),
+        vec![
+            "-NoProfile".to_string(),
+            "-WindowStyle".to_string(),
+            "Hidden".to_string(),
+            "-Command".to_string(),
+            format!("Start-Process -FilePath '{}' -Verb RunAs", quoted),
+        ],
+    )
+}
+
+/// Opens `path` elevated. Only supported on Windows.
+pub(crate) fn open_elevated(path: &Path) -> io::Result<()> {
+    if !cfg!(windows) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "opening as administrator is only supported on Windows",
+        ));
+    }
+    let (program, args) = elevated_open_command(path);
+    Command::new(program).args(args).spawn().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_engine::Search;
+
+    #[test]
+    fn test_copy_plan_resolves_collisions() {
+        let dest = Path::new("/out");
+        let sources: Vec<PathBuf> = ["/a/report.pdf", "/b/report.pdf", "/c/notes", "/d/taken.txt"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let existing = [
+            PathBuf::from("/out/taken.txt"),
+            PathBuf::from("/out/taken (1).txt"),
+        ];
+        let plan = copy_plan(&sources, dest, |path| existing.iter().any(|e| e == path));
+        let targets: Vec<_> = plan.iter().map(|(_, target)| target.clone()).collect();
+        assert_eq!(
+            targets,
+            vec![
+                PathBuf::from("/out/report.pdf"),
+                PathBuf::from("/out/report (1).pdf"),
+                PathBuf::from("/out/notes"),
+                PathBuf::from("/out/taken (2).txt"),
+            ]
+        );
+        assert_eq!(plan[1].0, PathBuf::from("/b/report.pdf"));
+    }
+
+    #[test]
+    fn test_elevated_open_command() {
+        let (program, args) =
+            elevated_open_command(Path::new("C:\\Program Files\\Bob's Tool\\tool.exe"));
+        assert_eq!(program, "powershell");
+        assert_eq!(
+            args.last().unwrap(),
+            "Start-Process -FilePath 'C:\\Program Files\\Bob''s Tool\\tool.exe' -Verb RunAs"
+        );
+        assert!(args.contains(&"-NoProfile".to_string()));
+    }
+
+    #[test]
+    fn test_rename_updates_index() {
+        let dir = std::env::temp_dir().join("search_rename_indexed_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let original = dir.join("draft.txt");
+        let other = dir.join("other.txt");
+        fs::write(&original, "draft").unwrap();
+        fs::write(&other, "other").unwrap();
+
+        let mut engine = Search::new();
+        engine.set_root_dir(dir.clone());
+        engine.generate_index().unwrap();
+
+        assert!(rename_indexed(&mut engine, &original, "other.txt").is_err());
+        assert!(rename_indexed(&mut engine, &original, "../escape.txt").is_err());
+        let renamed = rename_indexed(&mut engine, &original, "final.txt").unwrap();
+
+        assert_eq!(renamed, dir.join("final.txt"));
+        assert!(renamed.is_file() && !original.exists());
+        let mut index: Vec<PathBuf> = engine
+            .get_index()
+            .iter()
+            .map(|file| file.path.clone())
+            .collect();
+        index.sort();
+        assert_eq!(index, vec![renamed, other]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,632 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::category::Categories;
+use crate::search_engine::IndexedFile;
+
+/// Filters parsed out of `key:value` tokens in a query and applied to search results.
+///
+/// Tokens are separated by whitespace and removed from the query before the
+/// remaining text is handed to the search engine, e.g. `report category:document`
+/// searches for `report` and keeps only document results.
+///
+/// A token starting with `-` excludes results instead, e.g. `report -draft -old`
+/// finds `report` but drops every file whose name contains `draft` or `old`.
+/// To search for a name that itself starts with a dash, put the token in double
+/// quotes: `"-draft"` searches for `-draft` and the quotes are removed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct QueryFilters {
+    pub(crate) category: Option<String>,
+    pub(crate) created: Option<DateRange>,
+    pub(crate) directory: Option<DirFilter>,
+    /// Lowercase name patterns, see [`pattern_matches`], that drop a result.
+    pub(crate) excluded: Vec<String>,
+    pub(crate) modified: Option<DateRange>,
+    pub(crate) size: Option<SizeRange>,
+}
+
+/// An inclusive range of file sizes in bytes parsed from a `size:` token.
+///
+/// Sizes take an optional `KB`, `MB` or `GB` suffix (powers of 1024, any case):
+///
+/// * `size:<10KB` keeps files smaller than 10 KB.
+/// * `size:>1GB` keeps files larger than 1 GB.
+/// * `size:0` keeps empty files; any other bare size keeps files of exactly that size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SizeRange {
+    pub(crate) min: u64,
+    pub(crate) max: u64,
+}
+
+impl SizeRange {
+    pub(crate) fn parse(value: &str) -> Option<SizeRange> {
+        if let Some(size) = value.strip_prefix('<') {
+            return Some(SizeRange {
+                min: 0,
+                max: parse_size(size)?.checked_sub(1)?,
+            });
+        }
+        if let Some(size) = value.strip_prefix('>') {
+            return Some(SizeRange {
+                min: parse_size(size)?.checked_add(1)?,
+                max: u64::MAX,
+            });
+        }
+        let size = parse_size(value)?;
+        Some(SizeRange {
+            min: size,
+            max: size,
+        })
+    }
+
+    /// Returns whether `size` lies within the range. A missing size never matches.
+    pub(crate) fn contains(&self, size: Option<u64>) -> bool {
+        size.is_some_and(|size| (self.min..=self.max).contains(&size))
+    }
+}
+
+/// Parses a byte count with an optional `KB`, `MB` or `GB` suffix.
+fn parse_size(size: &str) -> Option<u64> {
+    let upper = size.trim().to_ascii_uppercase();
+    let (number, unit) = match upper.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => upper.split_at(index),
+        None => (upper.as_str(), ""),
+    };
+    let multiplier: u64 = match unit {
+        "" | "B" => 1,
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Keeps results by the folders they are in, independent of the name match.
+///
+/// * `parent:2024` looks only at the immediate parent folder's name.
+/// * `dir:2024` looks at the name of every ancestor folder, so it also keeps
+///   `2024/q1/report.txt`. A pattern containing `/` or `\` is instead matched
+///   against the whole parent path, e.g. `dir:projects/2024`.
+///
+/// Patterns are case-insensitive substrings, or globs when they contain `*` or
+/// `?`. A glob must match a whole folder name, but may match anywhere in a path.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct DirFilter {
+    pub(crate) pattern: String,
+    pub(crate) any_ancestor: bool,
+}
+
+impl DirFilter {
+    pub(crate) fn matches(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy().to_lowercase();
+        let mut folders: Vec<&str> = path.split(['\\', '/']).collect();
+        folders.pop();
+        if !self.any_ancestor {
+            return folders
+                .last()
+                .is_some_and(|parent| pattern_matches(&self.pattern, parent));
+        }
+        if self.pattern.contains(['\\', '/']) {
+            let mut pattern = self.pattern.replace('\\', "/");
+            if pattern.contains(['*', '?']) {
+                pattern = format!("*{}*", pattern);
+            }
+            return pattern_matches(&pattern, &folders.join("/"));
+        }
+        folders
+            .iter()
+            .filter(|folder| !folder.is_empty())
+            .any(|folder| pattern_matches(&self.pattern, folder))
+    }
+}
+
+/// Substring match, or a whole-text glob match when `pattern` has `*` or `?`.
+pub(crate) fn pattern_matches(pattern: &str, text: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return text.contains(pattern);
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, t));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A half-open time range `[after, before)` parsed from a `created:` or
+/// `modified:` token.
+///
+/// Dates are written as `YYYY-MM-DD` and interpreted as midnight UTC:
+///
+/// * `>2024-01-01` keeps times on or after the start of that day.
+/// * `<2024-01-01` keeps times strictly before the start of that day.
+/// * `2024-01-01` keeps times within that day.
+/// * `2024-01-01..2024-02-01` is the same as `>2024-01-01` combined with `<2024-02-01`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct DateRange {
+    pub(crate) after: Option<SystemTime>,
+    pub(crate) before: Option<SystemTime>,
+}
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl DateRange {
+    pub(crate) fn parse(value: &str) -> Option<DateRange> {
+        if let Some(date) = value.strip_prefix('>') {
+            return Some(DateRange {
+                after: Some(parse_date(date)?),
+                before: None,
+            });
+        }
+        if let Some(date) = value.strip_prefix('<') {
+            return Some(DateRange {
+                after: None,
+                before: Some(parse_date(date)?),
+            });
+        }
+        if let Some((start, end)) = value.split_once("..") {
+            return Some(DateRange {
+                after: Some(parse_date(start)?),
+                before: Some(parse_date(end)?),
+            });
+        }
+        let day = parse_date(value)?;
+        Some(DateRange {
+            after: Some(day),
+            before: Some(day + DAY),
+        })
+    }
+
+    /// Returns whether `time` lies within the range. A missing time never matches.
+    pub(crate) fn contains(&self, time: Option<SystemTime>) -> bool {
+        let Some(time) = time else {
+            return false;
+        };
+        self.after.is_none_or(|after| time >= after)
+            && self.before.is_none_or(|before| time < before)
+    }
+}
+
+/// Parses `YYYY-MM-DD` into midnight UTC of that day.
+fn parse_date(date: &str) -> Option<SystemTime> {
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    // Days since 1970-01-01 in the proleptic Gregorian calendar.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    let secs = u64::try_from(days).ok()? * DAY.as_secs();
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// The number of days in `month` (1 to 12) of `year` in the Gregorian calendar.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl QueryFilters {
+    /// Splits `query` into the search term and the filters it contains.
+    pub(crate) fn parse(query: &str) -> (String, QueryFilters) {
+        let mut filters = QueryFilters::default();
+        let mut terms = Vec::new();
+        for token in query.split_whitespace() {
+            if let Some(literal) = token
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .filter(|literal| literal.starts_with('-'))
+            {
+                terms.push(literal);
+                continue;
+            }
+            if let Some(excluded) = token.strip_prefix('-').filter(|rest| !rest.is_empty()) {
+                filters.excluded.push(excluded.to_lowercase());
+                continue;
+            }
+            match token.split_once(':') {
+                Some(("category", value)) if !value.is_empty() => {
+                    filters.category = Some(value.to_lowercase());
+                }
+                Some(("created", value)) if DateRange::parse(value).is_some() => {
+                    filters.created = DateRange::parse(value);
+                }
+                Some(("modified", value)) if DateRange::parse(value).is_some() => {
+                    filters.modified = DateRange::parse(value);
+                }
+                Some(("size", value)) if SizeRange::parse(value).is_some() => {
+                    filters.size = SizeRange::parse(value);
+                }
+                Some((key @ ("dir" | "parent"), value)) if !value.is_empty() => {
+                    filters.directory = Some(DirFilter {
+                        pattern: value.to_lowercase(),
+                        any_ancestor: key == "dir",
+                    });
+                }
+                _ => terms.push(token),
+            }
+        }
+        (terms.join(" "), filters)
+    }
+
+    /// Whether `file` satisfies all active filters. The search engine checks this
+    /// for every match before its result limit, so a filter never comes up empty
+    /// just because the first matches happen to fail it.
+    ///
+    /// Size, modification and creation time are the ones stored in the index, so
+    /// entries whose metadata is unknown fail `size:` and `modified:`, and every
+    /// entry fails `created:` unless the index was built with creation times.
+    pub(crate) fn matches(&self, file: &IndexedFile, categories: &Categories) -> bool {
+        let path = &file.path;
+        self.category
+            .as_ref()
+            .is_none_or(|category| categories.matches(category, path))
+            && self
+                .created
+                .is_none_or(|created| created.contains(file.created))
+            && self
+                .modified
+                .is_none_or(|modified| modified.contains(file.modified_time()))
+            && self
+                .directory
+                .as_ref()
+                .is_none_or(|directory| directory.matches(path))
+            && self
+                .size
+                .is_none_or(|size| size.contains(file.known_size()))
+            && !self.is_excluded(path)
+    }
+
+    /// Whether the file name of `path` matches any excluded pattern.
+    fn is_excluded(&self, path: &Path) -> bool {
+        if self.excluded.is_empty() {
+            return false;
+        }
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        let name = name.to_string_lossy().to_lowercase();
+        self.excluded
+            .iter()
+            .any(|pattern| pattern_matches(pattern, &name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    fn results(names: &[&str]) -> Vec<(PathBuf, String)> {
+        names
+            .iter()
+            .map(|name| (PathBuf::from(name), String::from("a")))
+            .collect()
+    }
+
+    /// Drops the results that `filters` rejects.
+    fn retain(
+        filters: &QueryFilters,
+        results: &mut Vec<(PathBuf, String)>,
+        categories: &Categories,
+    ) {
+        results.retain(|(path, _)| filters.matches(&IndexedFile::read(path.clone()), categories));
+    }
+
+    #[test]
+    fn test_parse_category_token() {
+        let (term, filters) = QueryFilters::parse("report category:Image  2024");
+        assert_eq!(term, "report 2024");
+        assert_eq!(filters.category.as_deref(), Some("image"));
+
+        let (term, filters) = QueryFilters::parse("category:");
+        assert_eq!(term, "category:");
+        assert_eq!(filters, QueryFilters::default());
+    }
+
+    #[test]
+    fn test_exclusion_terms() {
+        let categories = Categories::default();
+        let all = results(&[
+            "/docs/report.txt",
+            "/docs/report-DRAFT.txt",
+            "/docs/report-old.txt",
+            "/draft/report-final.txt",
+        ]);
+        let kept = |query: &str| {
+            let (term, filters) = QueryFilters::parse(query);
+            let mut found = all.clone();
+            retain(&filters, &mut found, &categories);
+            let names: Vec<String> = found
+                .into_iter()
+                .map(|(path, _)| path.to_string_lossy().into_owned())
+                .collect();
+            (term, names)
+        };
+
+        let (term, names) = kept("report -draft");
+        assert_eq!(term, "report");
+        assert_eq!(
+            names,
+            [
+                "/docs/report.txt",
+                "/docs/report-old.txt",
+                "/draft/report-final.txt"
+            ]
+        );
+        let (term, names) = kept("-draft report -OLD");
+        assert_eq!(term, "report");
+        assert_eq!(names, ["/docs/report.txt", "/draft/report-final.txt"]);
+        let (_, names) = kept("report -report");
+        assert!(names.is_empty());
+        let (_, names) = kept("report -*-*.txt");
+        assert_eq!(names, ["/docs/report.txt"]);
+    }
+
+    #[test]
+    fn test_quoted_leading_dash_is_a_term() {
+        let (term, filters) = QueryFilters::parse("\"-draft\" notes - -");
+        assert_eq!(term, "-draft notes - -");
+        assert!(filters.excluded.is_empty());
+        let (term, filters) = QueryFilters::parse("\"-\" -x");
+        assert_eq!(term, "-");
+        assert_eq!(filters.excluded, ["x"]);
+    }
+
+    #[test]
+    fn test_category_image_keeps_only_images() {
+        let (_, filters) = QueryFilters::parse("a category:image");
+        let mut found = results(&["a.png", "a.txt", "A.JPEG", "a.rs", "a", "a.gif.zip"]);
+        retain(&filters, &mut found, &Categories::default());
+        let names: Vec<_> = found
+            .iter()
+            .map(|(path, _)| path.to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["a.png", "A.JPEG"]);
+    }
+
+    #[test]
+    fn test_dir_token_matches_any_ancestor() {
+        let (term, filters) = QueryFilters::parse("report dir:2024");
+        assert_eq!(term, "report");
+        let mut found = results(&[
+            "C:\\2024\\report.txt",
+            "C:\\archive\\2024\\q1\\report.txt",
+            "C:\\2023\\report-2024.txt",
+            "/home/me/Budget2024/report.txt",
+        ]);
+        retain(&filters, &mut found, &Categories::default());
+        let kept: Vec<&str> = found.iter().map(|(p, _)| p.to_str().unwrap()).collect();
+        assert_eq!(
+            kept,
+            vec![
+                "C:\\2024\\report.txt",
+                "C:\\archive\\2024\\q1\\report.txt",
+                "/home/me/Budget2024/report.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parent_token_matches_immediate_parent_only() {
+        let (_, filters) = QueryFilters::parse("report parent:2024");
+        let mut found = results(&["C:\\2024\\report.txt", "C:\\archive\\2024\\q1\\report.txt"]);
+        retain(&filters, &mut found, &Categories::default());
+        assert_eq!(found, results(&["C:\\2024\\report.txt"]));
+    }
+
+    #[test]
+    fn test_dir_globs_and_paths() {
+        let glob = DirFilter {
+            pattern: "20?4".to_string(),
+            any_ancestor: true,
+        };
+        assert!(glob.matches(Path::new("/x/2024/a.txt")));
+        assert!(!glob.matches(Path::new("/x/Budget2024/a.txt")));
+        let path = DirFilter {
+            pattern: "projects\\20*".to_string(),
+            any_ancestor: true,
+        };
+        assert!(path.matches(Path::new("C:\\Projects\\2024\\a.txt")));
+        assert!(!path.matches(Path::new("C:\\Projects\\old\\a.txt")));
+        assert!(!path.matches(Path::new("C:\\Projects\\2024")));
+    }
+
+    #[test]
+    fn test_parse_size_tokens() {
+        assert_eq!(parse_size("0"), Some(0));
+        assert_eq!(parse_size("512b"), Some(512));
+        assert_eq!(parse_size("10KB"), Some(10 * 1024));
+        assert_eq!(parse_size("2mb"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_size("1GB"), Some(1 << 30));
+        assert_eq!(parse_size("1TB"), None);
+        assert_eq!(parse_size("KB"), None);
+
+        let (term, filters) = QueryFilters::parse("tmp size:<1KB");
+        assert_eq!(term, "tmp");
+        assert_eq!(filters.size, Some(SizeRange { min: 0, max: 1023 }));
+        assert_eq!(
+            SizeRange::parse(">1MB"),
+            Some(SizeRange {
+                min: (1 << 20) + 1,
+                max: u64::MAX
+            })
+        );
+        assert_eq!(SizeRange::parse("0"), Some(SizeRange { min: 0, max: 0 }));
+        assert_eq!(SizeRange::parse("<0"), None);
+
+        let (term, filters) = QueryFilters::parse("size:big");
+        assert_eq!(term, "size:big");
+        assert_eq!(filters.size, None);
+    }
+
+    #[test]
+    fn test_size_filter_reads_file_sizes() {
+        let dir = std::env::temp_dir().join("search_size_filter_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("empty.tmp"), "").unwrap();
+        fs::write(dir.join("small.tmp"), vec![0u8; 100]).unwrap();
+        fs::write(dir.join("large.tmp"), vec![0u8; 4096]).unwrap();
+        let all: Vec<(PathBuf, String)> = ["empty.tmp", "small.tmp", "large.tmp", "gone.tmp"]
+            .iter()
+            .map(|name| (dir.join(name), String::new()))
+            .collect();
+        let kept = |query: &str| {
+            let (_, filters) = QueryFilters::parse(query);
+            let mut found = all.clone();
+            retain(&filters, &mut found, &Categories::default());
+            found
+                .into_iter()
+                .map(|(path, _)| path.file_name().unwrap().to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kept("size:0"), vec!["empty.tmp"]);
+        assert_eq!(kept("size:<1KB"), vec!["empty.tmp", "small.tmp"]);
+        assert_eq!(kept("size:>1KB"), vec!["large.tmp"]);
+        assert_eq!(kept("size:100"), vec!["small.tmp"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_filters_use_the_stored_metadata() {
+        let categories = Categories::default();
+        let stored = IndexedFile {
+            path: PathBuf::from("/no/such/dir/report.txt"),
+            size: 100,
+            modified: parse_date("2024-03-01").unwrap() + Duration::from_secs(60),
+            created: None,
+        };
+        let (_, filters) = QueryFilters::parse("size:100 modified:2024-03-01");
+        assert!(filters.matches(&stored, &categories));
+        let (_, filters) = QueryFilters::parse("size:>1KB");
+        assert!(!filters.matches(&stored, &categories));
+        // An entry from an index without metadata matches no size or time.
+        let unknown = IndexedFile::from(PathBuf::from("/no/such/dir/report.txt"));
+        let (_, filters) = QueryFilters::parse("size:0");
+        assert!(!filters.matches(&unknown, &categories));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01"), Some(UNIX_EPOCH));
+        assert_eq!(
+            parse_date("2024-03-01"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_251_200))
+        );
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("2024-04-31"), None);
+        assert_eq!(parse_date("2024-02-31"), None);
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("1900-02-29"), None);
+        assert!(parse_date("2024-02-29").is_some());
+        assert!(parse_date("2000-02-29").is_some());
+        assert_eq!(parse_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_created_range() {
+        let (term, filters) = QueryFilters::parse("a created:2024-01-01..2024-02-01");
+        assert_eq!(term, "a");
+        let range = filters.created.unwrap();
+        let jan_1 = parse_date("2024-01-01").unwrap();
+        let feb_1 = parse_date("2024-02-01").unwrap();
+        assert!(range.contains(Some(jan_1)));
+        assert!(range.contains(Some(feb_1 - Duration::from_secs(1))));
+        assert!(!range.contains(Some(feb_1)));
+        assert!(!range.contains(Some(jan_1 - Duration::from_secs(1))));
+        assert!(!range.contains(None));
+
+        let day = DateRange::parse("2024-01-01").unwrap();
+        assert!(day.contains(Some(jan_1 + Duration::from_secs(3600))));
+        assert!(!day.contains(Some(jan_1 + DAY)));
+
+        let (term, filters) = QueryFilters::parse("created:soon");
+        assert_eq!(term, "created:soon");
+        assert!(filters.created.is_none());
+    }
+
+    #[test]
+    fn test_created_filter_uses_the_stored_time() {
+        let categories = Categories::default();
+        let (_, recent) = QueryFilters::parse("created:>2000-01-01");
+        let (_, old) = QueryFilters::parse("created:<2000-01-01");
+        let file = IndexedFile {
+            created: parse_date("2024-03-01"),
+            ..IndexedFile::from(PathBuf::from("/no/such/dir/report.txt"))
+        };
+        assert!(recent.matches(&file, &categories));
+        assert!(!old.matches(&file, &categories));
+        // Without a stored creation time nothing matches.
+        let unknown = IndexedFile::from(PathBuf::from("/no/such/dir/report.txt"));
+        assert!(!recent.matches(&unknown, &categories));
+        assert!(!old.matches(&unknown, &categories));
+    }
+
+    #[test]
+    fn test_modified_filter_boundaries() {
+        let path = std::env::temp_dir().join("search_modified_filter_test.txt");
+        fs::write(&path, "modified").unwrap();
+        let mtime = fs::metadata(&path).unwrap().modified().unwrap();
+        let secs = mtime.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let day = UNIX_EPOCH + Duration::from_secs(secs - secs % DAY.as_secs());
+
+        // `>` includes the first instant of its day, `<` excludes it.
+        let (_, after) = QueryFilters::parse("modified:>1970-01-01");
+        let starts_today = DateRange {
+            after: Some(day),
+            before: None,
+        };
+        let ends_today = DateRange {
+            after: None,
+            before: Some(day),
+        };
+        assert!(starts_today.contains(Some(day)));
+        assert!(!ends_today.contains(Some(day)));
+
+        let categories = Categories::default();
+        let mut found = vec![(path.clone(), String::from("modified"))];
+        retain(&after, &mut found, &categories);
+        assert_eq!(found.len(), 1);
+        let before = QueryFilters {
+            modified: Some(ends_today),
+            ..QueryFilters::default()
+        };
+        retain(&before, &mut found, &categories);
+        assert!(found.is_empty());
+
+        let mut missing = results(&["/no/such/file.txt"]);
+        retain(&after, &mut missing, &categories);
+        assert!(missing.is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use egui::{FontData, FontDefinitions, FontFamily};
+
+/// The font used when no font file is found, one that supports Chinese characters.
+const DEFAULT_FONT: &[u8] = include_bytes!("./font/NotoSerifCJKsc-Regular.otf");
+
+/// The font file read at startup unless `config.toml` names another one.
+pub(crate) const FONT_FILE: &str = "font.otf";
+
+/// The name the UI font is registered under with egui.
+const FONT_NAME: &str = "my_font";
+
+/// Whether `bytes` start like a TrueType or OpenType font or font collection.
+/// egui panics on font data it cannot parse, so anything else is not used.
+fn is_font(bytes: &[u8]) -> bool {
+    [&[0, 1, 0, 0][..], b"OTTO", b"true", b"ttcf"]
+        .iter()
+        .any(|magic| bytes.starts_with(magic))
+}
+
+/// Reads the UI font from `path`, or gives the embedded default when there is no
+/// such file. A file that is not a font is reported and the default used too.
+pub(crate) fn load_font(path: &Path) -> FontData {
+    match std::fs::read(path) {
+        Ok(bytes) if is_font(&bytes) => FontData::from_owned(bytes),
+        Ok(_) => {
+            eprintln!(
+                "{} is not a font file, using the default font",
+                path.display()
+            );
+            FontData::from_static(DEFAULT_FONT)
+        }
+        Err(_) => FontData::from_static(DEFAULT_FONT),
+    }
+}
+
+/// Makes `font` the first choice for proportional text, keeping egui's own fonts
+/// for whatever it does not cover.
+pub(crate) fn setup_custom_fonts(ctx: &egui::Context, font: FontData) {
+    let mut fonts = FontDefinitions::default();
+    fonts.font_data.insert(FONT_NAME.to_owned(), font);
+    fonts
+        .families
+        .entry(FontFamily::Proportional)
+        .or_default()
+        .insert(0, FONT_NAME.to_owned());
+    ctx.set_fonts(fonts);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_font_replaces_the_default() {
+        let dir = std::env::temp_dir().join("search_font_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let font = dir.join("custom.otf");
+
+        assert_eq!(load_font(&font).font, DEFAULT_FONT);
+        let custom = b"OTTO\0\x0b\0\x80".to_vec();
+        std::fs::write(&font, &custom).unwrap();
+        assert_eq!(load_font(&font).font, custom);
+        std::fs::write(&font, "not a font").unwrap();
+        assert_eq!(load_font(&font).font, DEFAULT_FONT);
+        assert!(is_font(DEFAULT_FONT));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fonts_set_once_stay_for_later_frames() {
+        let ctx = egui::Context::default();
+        setup_custom_fonts(&ctx, FontData::from_static(DEFAULT_FONT));
+        for _ in 0..3 {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| ui.label("搜索"));
+            });
+            let cjk =
+                ctx.fonts(|fonts| fonts.has_glyphs(&egui::FontId::proportional(14.0), "搜索"));
+            assert!(cjk);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+/// The rules of one `.gitignore` file, applying to the folder it lives in.
+///
+/// Supports comments, `!` negation, a trailing `/` for folders only, patterns
+/// anchored by a `/`, and the `*`, `?`, `[...]` and `**` wildcards.
+#[derive(Clone, Debug)]
+pub(crate) struct GitIgnore {
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    pattern: Vec<char>,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl GitIgnore {
+    /// Reads `dir/.gitignore`, if there is one.
+    pub(crate) fn load(dir: &Path) -> Option<Self> {
+        let buf = read_to_string(dir.join(".gitignore")).ok()?;
+        Some(Self::parse(dir.to_path_buf(), &buf))
+    }
+
+    pub(crate) fn parse(base: PathBuf, text: &str) -> Self {
+        let rules = text
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim_end();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let (negated, line) = match line.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, line.strip_prefix('\\').unwrap_or(line)),
+                };
+                let (dir_only, line) = match line.strip_suffix('/') {
+                    Some(rest) => (true, rest),
+                    None => (false, line),
+                };
+                let anchored = line.contains('/');
+                let line = line.strip_prefix('/').unwrap_or(line);
+                (!line.is_empty()).then(|| Rule {
+                    pattern: line.chars().collect(),
+                    negated,
+                    dir_only,
+                    anchored,
+                })
+            })
+            .collect();
+        GitIgnore { base, rules }
+    }
+
+    /// `Some(true)` if the last rule matching `path` ignores it, `Some(false)` if it
+    /// re-includes it, `None` if no rule applies.
+    fn decision(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.base).ok()?;
+        let relative: Vec<char> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+            .chars()
+            .collect();
+        let name_start = relative
+            .iter()
+            .rposition(|&c| c == '/')
+            .map_or(0, |slash| slash + 1);
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| is_dir || !rule.dir_only)
+            .find(|rule| {
+                let text = if rule.anchored {
+                    &relative[..]
+                } else {
+                    &relative[name_start..]
+                };
+                glob_matches(&rule.pattern, text)
+            })
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// Whether `path` is ignored by the `.gitignore` files in `stack`, which are
+/// ordered from the outermost folder inwards so deeper files take precedence.
+pub(crate) fn is_ignored(stack: &[GitIgnore], path: &Path, is_dir: bool) -> bool {
+    stack
+        .iter()
+        .rev()
+        .find_map(|gitignore| gitignore.decision(path, is_dir))
+        .unwrap_or(false)
+}
+
+/// Matches a `.gitignore` glob against a `/` separated path. `*`, `?` and classes
+/// never match a `/`; `**` matches any number of folders.
+fn glob_matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            match rest.strip_prefix(&['/']) {
+                Some(rest) => {
+                    glob_matches(rest, text)
+                        || (0..text.len())
+                            .any(|i| text[i] == '/' && glob_matches(rest, &text[i + 1..]))
+                }
+                None => (0..=text.len()).any(|i| glob_matches(rest, &text[i..])),
+            }
+        }
+        Some('*') => {
+            for i in 0..=text.len() {
+                if glob_matches(&pattern[1..], &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            text.first().is_some_and(|&c| c != '/') && glob_matches(&pattern[1..], &text[1..])
+        }
+        Some('[') => match class_matches(&pattern[1..], text.first().copied()) {
+            Some((true, rest)) => glob_matches(rest, &text[1..]),
+            Some((false, _)) => false,
+            None => text.first() == Some(&'[') && glob_matches(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_matches(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_matches(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Matches `c` against the class starting after a `[`, returning whether it matched
+/// and the pattern after the closing `]`, or `None` if the class is unterminated.
+fn class_matches(class: &[char], c: Option<char>) -> Option<(bool, &[char])> {
+    let (negated, class) = match class.first() {
+        Some('!' | '^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    // A `]` right after the opening bracket is part of the class.
+    let end = class
+        .iter()
+        .skip(1)
+        .position(|&c| c == ']')
+        .map(|position| position + 1)?;
+    let Some(c) = c.filter(|&c| c != '/') else {
+        return Some((false, &class[end + 1..]));
+    };
+    let members = &class[..end];
+    let mut found = false;
+    let mut i = 0;
+    while i < members.len() {
+        if i + 2 < members.len() && members[i + 1] == '-' {
+            found |= (members[i]..=members[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= members[i] == c;
+            i += 1;
+        }
+    }
+    Some((found != negated, &class[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        glob_matches(&pattern, &text)
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob("*.log", "debug.log"));
+        assert!(!glob("*.log", "logs/debug.log"));
+        assert!(glob("**/debug.log", "debug.log"));
+        assert!(glob("**/debug.log", "a/b/debug.log"));
+        assert!(glob("logs/**", "logs/a/b.txt"));
+        assert!(glob("a/**/b", "a/x/y/b"));
+        assert!(glob("a/**/b", "a/b"));
+        assert!(glob("file?.[ch]", "file1.c"));
+        assert!(!glob("file?.[!ch]", "file1.c"));
+        assert!(glob("[0-9][0-9]", "42"));
+        assert!(glob("\\#notes", "#notes"));
+    }
+
+    #[test]
+    fn test_rules() {
+        let base = PathBuf::from("/repo");
+        let gitignore = GitIgnore::parse(
+            base.clone(),
+            "# build output\n*.log\n!keep.log\n/target\nbuild/\ndocs/*.tmp\n",
+        );
+        let stack = [gitignore];
+        assert!(is_ignored(&stack, &base.join("a").join("x.log"), false));
+        assert!(!is_ignored(&stack, &base.join("keep.log"), false));
+        assert!(is_ignored(&stack, &base.join("target"), true));
+        assert!(!is_ignored(&stack, &base.join("src").join("target"), true));
+        assert!(is_ignored(&stack, &base.join("src").join("build"), true));
+        assert!(!is_ignored(&stack, &base.join("build"), false));
+        assert!(is_ignored(&stack, &base.join("docs").join("a.tmp"), false));
+        assert!(!is_ignored(
+            &stack,
+            &base.join("src").join("docs").join("a.tmp"),
+            false
+        ));
+        assert!(!is_ignored(&stack, Path::new("/elsewhere/x.log"), false));
+    }
+
+    #[test]
+    fn test_deeper_files_take_precedence() {
+        let base = PathBuf::from("/repo");
+        let stack = [
+            GitIgnore::parse(base.clone(), "*.log\n"),
+            GitIgnore::parse(base.join("logs"), "!important.log\n"),
+        ];
+        assert!(is_ignored(
+            &stack,
+            &base.join("logs").join("debug.log"),
+            false
+        ));
+        assert!(!is_ignored(
+            &stack,
+            &base.join("logs").join("important.log"),
+            false
+        ));
+    }
+}
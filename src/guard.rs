@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use crate::search_engine::IndexedFile;
+
+pub(crate) const MB: u64 = 1024 * 1024;
+
+/// How many files are indexed between two memory checks while traversing.
+pub(crate) const MEMORY_CHECK_INTERVAL: usize = 50_000;
+
+/// Free disk space and memory that indexing must leave untouched.
+///
+/// Set by `min_free_disk_mb` and `min_free_memory_mb` in `config.toml`; a
+/// threshold of `0` disables that guard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResourceLimits {
+    pub(crate) min_free_disk: u64,
+    pub(crate) min_free_memory: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits {
+            min_free_disk: 100 * MB,
+            min_free_memory: 256 * MB,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Parses the `min_free_disk_mb=` and `min_free_memory_mb=` lines of the
+    /// `limits.ini` of earlier versions.
+    pub(crate) fn parse(text: &str) -> Self {
+        let mut limits = ResourceLimits::default();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let Ok(megabytes) = value.trim().parse::<u64>() else {
+                continue;
+            };
+            match key.trim() {
+                "min_free_disk_mb" => limits.min_free_disk = megabytes * MB,
+                "min_free_memory_mb" => limits.min_free_memory = megabytes * MB,
+                _ => {}
+            }
+        }
+        limits
+    }
+}
+
+/// Rough size of the serialized index: each path plus its length prefix, size and
+/// modification time.
+pub(crate) fn estimated_index_size(files: &[IndexedFile]) -> u64 {
+    files
+        .iter()
+        .map(|file| file.path.as_os_str().len() as u64 + 8 + 20)
+        .sum::<u64>()
+        + 8
+}
+
+/// Refuses a write of `required` bytes that would leave less than `min_free` on disk.
+/// An unknown `available` amount lets the write through.
+pub(crate) fn check_disk_space(
+    available: Option<u64>,
+    required: u64,
+    min_free: u64,
+) -> Result<(), String> {
+    match available {
+        Some(available) if available < required.saturating_add(min_free) => Err(format!(
+            "Not saving index: {} MB free on disk, {} MB needed to keep {} MB free",
+            available / MB,
+            required.div_ceil(MB),
+            min_free / MB
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Refuses to keep indexing once free memory drops below `min_free`.
+/// An unknown `available` amount lets indexing continue.
+pub(crate) fn check_memory(available: Option<u64>, min_free: u64) -> Result<(), String> {
+    match available {
+        Some(available) if available < min_free => Err(format!(
+            "Not indexing: only {} MB of memory free, at least {} MB required",
+            available / MB,
+            min_free / MB
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Bytes available to this process on the volume holding `path`.
+#[cfg(unix)]
+pub(crate) fn available_disk_space(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+pub(crate) fn available_disk_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn available_disk_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Physical memory currently available to new allocations, including page cache
+/// the kernel can reclaim.
+#[cfg(target_os = "linux")]
+pub(crate) fn available_memory() -> Option<u64> {
+    std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| parse_mem_available(&meminfo))
+}
+
+/// Reads the `MemAvailable:` line of `/proc/meminfo`, which is given in kB.
+#[cfg(any(target_os = "linux", test))]
+fn parse_mem_available(meminfo: &str) -> Option<u64> {
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))
+        .and_then(|value| value.trim().strip_suffix("kB"))
+        .and_then(|kb| kb.trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+#[cfg(windows)]
+pub(crate) fn available_memory() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+        return None;
+    }
+    Some(status.ullAvailPhys)
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub(crate) fn available_memory() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_limits() {
+        let limits = ResourceLimits::parse("min_free_disk_mb=2048\nmin_free_memory_mb = 0\nbogus");
+        assert_eq!(limits.min_free_disk, 2048 * MB);
+        assert_eq!(limits.min_free_memory, 0);
+        assert_eq!(ResourceLimits::parse(""), ResourceLimits::default());
+    }
+
+    #[test]
+    fn test_check_disk_space() {
+        assert!(check_disk_space(Some(500 * MB), 10 * MB, 100 * MB).is_ok());
+        assert!(check_disk_space(Some(110 * MB), 10 * MB, 100 * MB).is_ok());
+        assert!(check_disk_space(Some(109 * MB), 10 * MB, 100 * MB).is_err());
+        assert!(check_disk_space(Some(0), 1, 0).is_err());
+        assert!(check_disk_space(None, u64::MAX, u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_check_memory() {
+        assert!(check_memory(Some(300 * MB), 256 * MB).is_ok());
+        assert!(check_memory(Some(255 * MB), 256 * MB).is_err());
+        assert!(check_memory(Some(0), 0).is_ok());
+        assert!(check_memory(None, u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_parse_mem_available() {
+        let meminfo = "MemTotal:        6157312 kB\nMemFree:          282624 kB\nMemAvailable:    5288960 kB\n";
+        assert_eq!(parse_mem_available(meminfo), Some(5288960 * 1024));
+        assert_eq!(parse_mem_available("MemTotal: 1 kB"), None);
+    }
+
+    #[test]
+    fn test_estimated_index_size() {
+        assert_eq!(estimated_index_size(&[]), 8);
+        let files = vec![
+            IndexedFile::from(PathBuf::from("abc")),
+            IndexedFile::from(PathBuf::from("de")),
+        ];
+        assert_eq!(estimated_index_size(&files), 8 + 31 + 30);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{fs::read_to_string, io, path::Path};
+
+/// Where submitted queries are kept between runs.
+pub(crate) const HISTORY_FILE: &str = "history.txt";
+
+/// The most queries the history keeps.
+pub(crate) const HISTORY_LIMIT: usize = 100;
+
+/// Recently submitted queries, most recent first, each listed once.
+///
+/// Saved as plain text with one query per line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SearchHistory {
+    queries: Vec<String>,
+}
+
+impl SearchHistory {
+    /// Reads the history from `path`; no file gives an empty history.
+    pub(crate) fn load(path: &Path) -> Self {
+        let mut history = SearchHistory::default();
+        if let Ok(buf) = read_to_string(path) {
+            // Recording oldest first leaves the file's order intact.
+            for query in buf.lines().rev() {
+                history.record(query);
+            }
+        }
+        history
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> io::Result<()> {
+        let mut buf = self.queries.join("\n");
+        buf.push('\n');
+        std::fs::write(path, buf)
+    }
+
+    /// Moves `query` to the front, dropping the oldest queries beyond
+    /// [`HISTORY_LIMIT`]. Blank queries are ignored.
+    pub(crate) fn record(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.queries.retain(|recorded| recorded != query);
+        self.queries.insert(0, query.to_string());
+        self.queries.truncate(HISTORY_LIMIT);
+    }
+
+    pub(crate) fn queries(&self) -> &[String] {
+        &self.queries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_dedups_and_caps() {
+        let mut history = SearchHistory::default();
+        history.record("main");
+        history.record("report");
+        history.record("  main ");
+        history.record("");
+        assert_eq!(history.queries(), ["main", "report"]);
+
+        for number in 0..HISTORY_LIMIT {
+            history.record(&format!("query {}", number));
+        }
+        assert_eq!(history.queries().len(), HISTORY_LIMIT);
+        assert_eq!(history.queries()[0], format!("query {}", HISTORY_LIMIT - 1));
+        assert!(!history.queries().contains(&"report".to_string()));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join("search_history_test.txt");
+        let path = path.as_path();
+        let mut history = SearchHistory::default();
+        history.record("older");
+        history.record("newer");
+        history.save(path).unwrap();
+        assert_eq!(SearchHistory::load(path), history);
+        let _ = std::fs::remove_file(path);
+        assert_eq!(SearchHistory::load(path), SearchHistory::default());
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    path::PathBuf,
+    thread::{self, JoinHandle},
+    time::SystemTime,
+};
+
+use crate::config::Config;
+use crate::json::json_string;
+use crate::search_engine::{Search, SearchEngine};
+
+const DEFAULT_PORT: u16 = 7878;
+
+/// Settings for the local search endpoint, the `[http]` table of `config.toml`
+/// with `port` and `root` keys. The server only ever listens on localhost.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct HttpConfig {
+    pub(crate) port: u16,
+    pub(crate) root: PathBuf,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            port: DEFAULT_PORT,
+            root: PathBuf::from("C:\\"),
+        }
+    }
+}
+
+impl HttpConfig {
+    /// The endpoint settings of `config`, with the defaults for those it lacks.
+    pub(crate) fn from_config(config: &Config) -> Self {
+        let defaults = HttpConfig::default();
+        HttpConfig {
+            port: config.http_port.unwrap_or(defaults.port),
+            root: config.http_root.clone().unwrap_or(defaults.root),
+        }
+    }
+}
+
+/// Serves `GET /search?q=...` on `127.0.0.1:port` from a worker thread, answering
+/// with the matches for the saved index of `root` as JSON.
+pub(crate) fn start(config: HttpConfig) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, config.port))?;
+    let mut engine = Search::new();
+    engine.set_root_dir(config.root);
+    Ok(thread::spawn(move || serve(listener, engine)))
+}
+
+/// Answers requests on `listener` until it fails, reloading the index whenever
+/// the saved index file changes.
+pub(crate) fn serve(listener: TcpListener, mut engine: Search) {
+    let mut loaded: Option<SystemTime> = None;
+    for stream in listener.incoming().flatten() {
+        let modified = fs::metadata(engine.get_index_file())
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified.is_some() && modified != loaded {
+            if let Err(e) = engine.load_index() {
+                eprintln!("{}", e);
+            }
+            loaded = modified;
+        }
+        let _ = handle(stream, &mut engine);
+    }
+}
+
+fn handle(mut stream: TcpStream, engine: &mut Search) -> io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let Some(query) = search_query(&request_line) else {
+        return stream.write_all(
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+    };
+    engine.reset_search_results();
+    if let Err(e) = engine.search(&query) {
+        let body = format!("{{\"error\":{}}}", json_string(&e.to_string()));
+        return write!(
+            stream,
+            "HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+    }
+    let body = results_to_json(engine.get_results());
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+/// Extracts the decoded `q` parameter from a `GET /search?...` request line.
+fn search_query(request_line: &str) -> Option<String> {
+    let mut parts = request_line.split_whitespace();
+    if parts.next()? != "GET" {
+        return None;
+    }
+    let target = parts.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/search" {
+        return None;
+    }
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("q="))
+        .map(percent_decode)
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%' && i + 2 < bytes.len())
+            .then(|| Some(hex_value(bytes[i + 1])? * 16 + hex_value(bytes[i + 2])?))
+            .flatten();
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                i += 2;
+            }
+            (b'+', None) => decoded.push(b' '),
+            (byte, None) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+/// Renders results as `[{"path": ..., "matched": ...}, ...]`.
+pub(crate) fn results_to_json(results: &[(PathBuf, String)]) -> String {
+    let entries: Vec<String> = results
+        .iter()
+        .map(|(path, matched)| {
+            format!(
+                "{{\"path\":{},\"matched\":{}}}",
+                json_string(&path.to_string_lossy()),
+                json_string(matched)
+            )
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_search_query() {
+        assert_eq!(
+            search_query("GET /search?q=my%20report+2024 HTTP/1.1\r\n").as_deref(),
+            Some("my report 2024")
+        );
+        assert_eq!(
+            search_query("GET /search?x=1&q=a%2Bb HTTP/1.1").as_deref(),
+            Some("a+b")
+        );
+        assert_eq!(search_query("GET /other?q=a HTTP/1.1"), None);
+        assert_eq!(search_query("POST /search?q=a HTTP/1.1"), None);
+    }
+
+    #[test]
+    fn test_results_to_json_escapes() {
+        let results = vec![(PathBuf::from("C:\\a \"b\".txt"), "b".to_string())];
+        assert_eq!(
+            results_to_json(&results),
+            r#"[{"path":"C:\\a \"b\".txt","matched":"b"}]"#
+        );
+        assert_eq!(results_to_json(&[]), "[]");
+    }
+
+    #[test]
+    fn test_endpoint_returns_json_results() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut engine = Search::new();
+        engine.set_root_dir(PathBuf::from("search_http_endpoint_test"));
+        engine.replace_index(vec![
+            PathBuf::from("/docs/report.txt").into(),
+            PathBuf::from("/docs/notes.txt").into(),
+        ]);
+        thread::spawn(move || serve(listener, engine));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .write_all(b"GET /search?q=rep HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(head.contains("Content-Type: application/json"));
+        let entries: Vec<&str> = body
+            .strip_prefix("[{")
+            .and_then(|body| body.strip_suffix("}]"))
+            .unwrap()
+            .split("},{")
+            .collect();
+        assert_eq!(
+            entries,
+            vec![r#""path":"/docs/report.txt","matched":"rep""#]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use crate::category::Categories;
+
+/// The file type icons, side by side in one image in [`FileIcon::ALL`] order.
+const ICON_ATLAS: &[u8] = include_bytes!("./icons/file_icons.png");
+
+/// Width and height of one icon in the atlas, in pixels.
+pub(crate) const ICON_SIZE: f32 = 16.0;
+
+/// The icon shown next to a result to indicate its type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FileIcon {
+    Folder,
+    Document,
+    Image,
+    Audio,
+    Video,
+    Archive,
+    Code,
+    Unknown,
+}
+
+impl FileIcon {
+    const ALL: [FileIcon; 8] = [
+        FileIcon::Folder,
+        FileIcon::Document,
+        FileIcon::Image,
+        FileIcon::Audio,
+        FileIcon::Video,
+        FileIcon::Archive,
+        FileIcon::Code,
+        FileIcon::Unknown,
+    ];
+
+    /// The icon for a file, from the built-in category its extension belongs to
+    /// in `categories`. Other extensions and files without one are `Unknown`.
+    pub(crate) fn for_file(path: &Path, categories: &Categories) -> Self {
+        [
+            ("document", FileIcon::Document),
+            ("image", FileIcon::Image),
+            ("audio", FileIcon::Audio),
+            ("video", FileIcon::Video),
+            ("archive", FileIcon::Archive),
+            ("code", FileIcon::Code),
+        ]
+        .into_iter()
+        .find(|(category, _)| categories.matches(category, path))
+        .map_or(FileIcon::Unknown, |(_, icon)| icon)
+    }
+
+    /// Where the icon lies in the atlas, in texture coordinates.
+    pub(crate) fn uv(self) -> egui::Rect {
+        let index = Self::ALL.iter().position(|&icon| icon == self).unwrap_or(0);
+        let width = 1.0 / Self::ALL.len() as f32;
+        egui::Rect::from_min_max(
+            egui::pos2(index as f32 * width, 0.0),
+            egui::pos2((index + 1) as f32 * width, 1.0),
+        )
+    }
+}
+
+/// Uploads the icon atlas as a texture. Called once; the handle is kept for as
+/// long as the icons are shown.
+pub(crate) fn load_icon_atlas(ctx: &egui::Context) -> Option<egui::TextureHandle> {
+    let atlas = image::load_from_memory(ICON_ATLAS).ok()?.to_rgba8();
+    let size = [atlas.width() as usize, atlas.height() as usize];
+    let image = egui::ColorImage::from_rgba_unmultiplied(size, atlas.as_raw());
+    Some(ctx.load_texture("file_icons", image, egui::TextureOptions::NEAREST))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_for_extension() {
+        let categories = Categories::default();
+        let icon = |name: &str| FileIcon::for_file(Path::new(name), &categories);
+        assert_eq!(icon("/docs/report.PDF"), FileIcon::Document);
+        assert_eq!(icon("photo.jpeg"), FileIcon::Image);
+        assert_eq!(icon("song.flac"), FileIcon::Audio);
+        assert_eq!(icon("clip.mkv"), FileIcon::Video);
+        assert_eq!(icon("backup.7z"), FileIcon::Archive);
+        assert_eq!(icon("src/main.rs"), FileIcon::Code);
+        assert_eq!(icon("data.xyz"), FileIcon::Unknown);
+        assert_eq!(icon("Makefile"), FileIcon::Unknown);
+    }
+
+    #[test]
+    fn test_atlas_holds_every_icon() {
+        let atlas = image::load_from_memory(ICON_ATLAS).unwrap();
+        assert_eq!(atlas.height() as f32, ICON_SIZE);
+        assert_eq!(atlas.width() as f32, ICON_SIZE * FileIcon::ALL.len() as f32);
+        assert_eq!(FileIcon::Folder.uv().min.x, 0.0);
+        assert_eq!(FileIcon::Unknown.uv().max.x, 1.0);
+    }
+}
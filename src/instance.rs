@@ -0,0 +1,257 @@
+use std::{
+    ffi::OsString,
+    fs::{hard_link, metadata, read_to_string, remove_file, rename, write},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// The lock file held while a window runs, next to the index files it writes.
+pub(crate) const LOCK_FILE: &str = "search.lock";
+
+/// How long an empty lock file is taken to be held before it counts as stale.
+const EMPTY_LOCK_GRACE: Duration = Duration::from_secs(10);
+
+/// Shows that this process is the only window indexing into the working folder.
+/// The lock file, holding the process id, is removed again when this is dropped,
+/// unless another instance has taken it over by then.
+#[derive(Debug)]
+pub(crate) struct InstanceLock {
+    path: PathBuf,
+    pid: u32,
+}
+
+impl InstanceLock {
+    /// Takes the lock at `path`, or returns `None` while another running instance
+    /// holds it. A lock left behind by a process that is gone, e.g. after a crash,
+    /// is taken over.
+    pub(crate) fn acquire(path: &Path) -> io::Result<Option<InstanceLock>> {
+        InstanceLock::acquire_as(path, std::process::id(), process_running)
+    }
+
+    /// Takes the lock for the process `pid`, where `running` tells whether the
+    /// process holding an existing lock is still alive.
+    ///
+    /// The process id is written to a file of its own first and then linked into
+    /// place, so the lock never exists without its holder. An empty lock file is
+    /// left by a crash or a damaged disk. It is taken to be stale unless it was
+    /// written in the last [`EMPTY_LOCK_GRACE`].
+    fn acquire_as(
+        path: &Path,
+        pid: u32,
+        running: impl Fn(u32) -> bool,
+    ) -> io::Result<Option<InstanceLock>> {
+        let pending = sibling(path, pid, "tmp");
+        write(&pending, pid.to_string())?;
+        let acquired = InstanceLock::link_as(path, &pending, pid, running);
+        let _ = remove_file(&pending);
+        acquired
+    }
+
+    fn link_as(
+        path: &Path,
+        pending: &Path,
+        pid: u32,
+        running: impl Fn(u32) -> bool,
+    ) -> io::Result<Option<InstanceLock>> {
+        // The second attempt follows removing a stale lock.
+        for _ in 0..2 {
+            match hard_link(pending, path) {
+                Ok(()) => {
+                    return Ok(Some(InstanceLock {
+                        path: path.to_path_buf(),
+                        pid,
+                    }))
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let holder = match read_to_string(path) {
+                        Ok(text) => text,
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                        Err(e) => return Err(e),
+                    };
+                    let held = if holder.trim().is_empty() {
+                        written_recently(path)
+                    } else {
+                        let holder_pid = holder.trim().parse::<u32>().ok();
+                        holder_pid.is_some_and(|holder| holder != pid && running(holder))
+                    };
+                    if held {
+                        return Ok(None);
+                    }
+                    if !remove_stale(path, &holder, pid)? {
+                        return Ok(None);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Removes the lock at `path` if it still holds `stale`. It is moved aside
+/// before it is checked, so a lock another instance took in the meantime is put
+/// back rather than deleted. Returns whether the lock is gone.
+fn remove_stale(path: &Path, stale: &str, pid: u32) -> io::Result<bool> {
+    let aside = sibling(path, pid, "stale");
+    match rename(path, &aside) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(e),
+    }
+    let unchanged = read_to_string(&aside).is_ok_and(|text| text == stale);
+    if !unchanged {
+        // Only fails when yet another instance holds the lock by now.
+        let _ = hard_link(&aside, path);
+    }
+    let _ = remove_file(&aside);
+    Ok(unchanged)
+}
+
+/// Whether the file at `path` was modified within the last [`EMPTY_LOCK_GRACE`].
+/// A modification time in the future does not count as recent.
+fn written_recently(path: &Path) -> bool {
+    metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < EMPTY_LOCK_GRACE))
+}
+
+/// A file next to `path` used only by the process `pid`.
+fn sibling(path: &Path, pid: u32, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}.{}", pid, suffix));
+    PathBuf::from(name)
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let ours =
+            read_to_string(&self.path).is_ok_and(|text| text.trim().parse::<u32>() == Ok(self.pid));
+        if ours {
+            let _ = remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn process_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks whether the process exists and may be signalled.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn process_running(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // SAFETY: the handle is checked before use and closed once read.
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return false;
+        }
+        let mut exit_code = 0;
+        let running =
+            GetExitCodeProcess(process, &mut exit_code) != 0 && exit_code == STILL_ACTIVE as u32;
+        CloseHandle(process);
+        running
+    }
+}
+
+/// Without a way to ask, every lock is taken to be stale.
+#[cfg(not(any(unix, windows)))]
+fn process_running(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let path = std::env::temp_dir().join("search_instance_lock_test.lock");
+        let _ = remove_file(&path);
+        let alive = |pid| pid == 42;
+
+        let first = InstanceLock::acquire_as(&path, 42, alive).unwrap().unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), "42");
+        assert!(InstanceLock::acquire_as(&path, 43, alive)
+            .unwrap()
+            .is_none());
+        assert_eq!(read_to_string(&path).unwrap(), "42");
+        drop(first);
+        assert!(!path.exists());
+
+        // Left behind by a crashed process, or unreadable.
+        for stale in ["7", "not a pid"] {
+            std::fs::write(&path, stale).unwrap();
+            let lock = InstanceLock::acquire_as(&path, 43, alive).unwrap();
+            assert!(lock.is_some());
+            assert_eq!(read_to_string(&path).unwrap(), "43");
+        }
+        assert!(!path.exists());
+        assert!(!sibling(&path, 43, "tmp").exists());
+        assert!(!sibling(&path, 43, "stale").exists());
+    }
+
+    #[test]
+    fn test_empty_lock_is_stale_once_old() {
+        let path = std::env::temp_dir().join("search_instance_empty_lock_test.lock");
+        std::fs::write(&path, "").unwrap();
+        assert!(InstanceLock::acquire_as(&path, 43, |_| false)
+            .unwrap()
+            .is_none());
+        assert_eq!(read_to_string(&path).unwrap(), "");
+
+        // Left empty by a crash some time ago.
+        let written = std::time::SystemTime::now() - EMPTY_LOCK_GRACE * 2;
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(written)
+            .unwrap();
+        let lock = InstanceLock::acquire_as(&path, 43, |_| false).unwrap();
+        assert!(lock.is_some());
+        assert_eq!(read_to_string(&path).unwrap(), "43");
+        drop(lock);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_drop_keeps_a_lock_taken_over() {
+        let path = std::env::temp_dir().join("search_instance_taken_lock_test.lock");
+        let _ = remove_file(&path);
+        let lock = InstanceLock::acquire_as(&path, 42, |_| false)
+            .unwrap()
+            .unwrap();
+        // Another instance decided the lock was stale and took it.
+        std::fs::write(&path, "44").unwrap();
+        drop(lock);
+        assert_eq!(read_to_string(&path).unwrap(), "44");
+        remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_stale_removal_puts_back_a_fresh_lock() {
+        let path = std::env::temp_dir().join("search_instance_fresh_lock_test.lock");
+        std::fs::write(&path, "44").unwrap();
+        // The lock was read as held by 7, but 44 has replaced it since.
+        assert!(!remove_stale(&path, "7", 43).unwrap());
+        assert_eq!(read_to_string(&path).unwrap(), "44");
+        assert!(remove_stale(&path, "44", 43).unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_this_process_is_running() {
+        assert!(process_running(std::process::id()));
+    }
+}
@@ -0,0 +1,314 @@
+use std::ffi::OsStr;
+
+pub(crate) fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quotes an OS string, such as a path, for JSON. Windows names that are not valid
+/// Unicode keep their unpaired surrogates as `\uXXXX` escapes, so the name read
+/// back is exactly the original; elsewhere invalid bytes become U+FFFD.
+pub(crate) fn json_os_string(text: &OsStr) -> String {
+    if let Some(text) = text.to_str() {
+        return json_string(text);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        let mut quoted = String::from("\"");
+        for unit in char::decode_utf16(text.encode_wide()) {
+            match unit {
+                Ok(c) => {
+                    let escaped = json_string(c.encode_utf8(&mut [0; 4]));
+                    quoted.push_str(&escaped[1..escaped.len() - 1]);
+                }
+                Err(e) => quoted.push_str(&format!("\\u{:04x}", e.unpaired_surrogate())),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+    #[cfg(not(windows))]
+    json_string(&text.to_string_lossy())
+}
+
+/// A parsed JSON document.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    /// Members in document order.
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// The member `key` of an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The value of a number that is a whole number small enough to be exact.
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match *self {
+            JsonValue::Number(n) if n >= 0.0 && n.fract() == 0.0 && n <= (1u64 << 53) as f64 => {
+                Some(n as u64)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a complete JSON document.
+pub(crate) fn parse_json(text: &str) -> Result<JsonValue, String> {
+    let mut parser = JsonParser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position < parser.chars.len() {
+        return Err(parser.error("Trailing characters"));
+    }
+    Ok(value)
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+    fn error(&self, message: &str) -> String {
+        format!("{} at character {}", message, self.position)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.get(self.position).copied();
+        self.position += 1;
+        c
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error(&format!("Expected `{}`", expected))),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, String> {
+        for expected in word.chars() {
+            if self.next() != Some(expected) {
+                return Err(self.error("Invalid literal"));
+            }
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.position) {
+            Some('n') => self.keyword("null", JsonValue::Null),
+            Some('t') => self.keyword("true", JsonValue::Bool(true)),
+            Some('f') => self.keyword("false", JsonValue::Bool(false)),
+            Some('"') => self.string().map(JsonValue::String),
+            Some('[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.position) == Some(&']') {
+                    self.position += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.next() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(JsonValue::Array(items)),
+                        _ => return Err(self.error("Expected `,` or `]`")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.position += 1;
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.position) == Some(&'}') {
+                    self.position += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let name = self.string()?;
+                    self.expect(':')?;
+                    members.push((name, self.value()?));
+                    self.skip_whitespace();
+                    match self.next() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(JsonValue::Object(members)),
+                        _ => return Err(self.error("Expected `,` or `}`")),
+                    }
+                }
+            }
+            Some(c) if *c == '-' || c.is_ascii_digit() => {
+                let start = self.position;
+                while self
+                    .chars
+                    .get(self.position)
+                    .is_some_and(|c| matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    self.position += 1;
+                }
+                let number: String = self.chars[start..self.position].iter().collect();
+                number
+                    .parse()
+                    .map(JsonValue::Number)
+                    .map_err(|_| self.error("Invalid number"))
+            }
+            _ => Err(self.error("Expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.next() != Some('"') {
+            return Err(self.error("Expected a string"));
+        }
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => match self.next() {
+                    Some('"') => text.push('"'),
+                    Some('\\') => text.push('\\'),
+                    Some('/') => text.push('/'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('n') => text.push('\n'),
+                    Some('r') => text.push('\r'),
+                    Some('t') => text.push('\t'),
+                    Some('u') => {
+                        let unit = self.hex_unit()?;
+                        let c = if (0xD800..0xDC00).contains(&unit)
+                            && self.chars.get(self.position..self.position + 2)
+                                == Some(&['\\', 'u'])
+                        {
+                            self.position += 2;
+                            let low = self.hex_unit()?;
+                            char::decode_utf16([unit, low]).next().and_then(Result::ok)
+                        } else {
+                            char::from_u32(unit as u32)
+                        };
+                        text.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    _ => return Err(self.error("Invalid escape")),
+                },
+                Some(c) => text.push(c),
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+    }
+
+    fn hex_unit(&mut self) -> Result<u16, String> {
+        let digits: String = self
+            .chars
+            .get(self.position..self.position + 4)
+            .ok_or_else(|| self.error("Invalid escape"))?
+            .iter()
+            .collect();
+        self.position += 4;
+        u16::from_str_radix(&digits, 16).map_err(|_| self.error("Invalid escape"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(
+            json_string("C:\\a \"b\"\n\u{1}"),
+            r#""C:\\a \"b\"\n\u0001""#
+        );
+        assert_eq!(json_os_string(OsStr::new("é.txt")), "\"é.txt\"");
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let value = parse_json(
+            r#" {"name": "a \"b\"\n\u00e9\ud83d\ude00", "list": [1, -2.5e1, true, null], "empty": {}} "#,
+        )
+        .unwrap();
+        assert_eq!(
+            value.get("name").and_then(JsonValue::as_str),
+            Some("a \"b\"\né😀")
+        );
+        assert_eq!(
+            value.get("list").and_then(JsonValue::as_array),
+            Some(
+                &[
+                    JsonValue::Number(1.0),
+                    JsonValue::Number(-25.0),
+                    JsonValue::Bool(true),
+                    JsonValue::Null
+                ][..]
+            )
+        );
+        assert_eq!(value.get("empty"), Some(&JsonValue::Object(Vec::new())));
+        assert_eq!(
+            parse_json(&json_string("C:\\x \"y\"\t")),
+            Ok(JsonValue::String("C:\\x \"y\"\t".to_string()))
+        );
+        assert!(parse_json("[1,]").is_err());
+        assert!(parse_json("{\"a\" 1}").is_err());
+        assert!(parse_json("\"open").is_err());
+        assert!(parse_json("[] x").is_err());
+    }
+}
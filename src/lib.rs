@@ -0,0 +1,64 @@
+//! A file name search engine with an `egui` front end.
+//!
+//! The engine indexes the paths under one or more root folders and matches
+//! search keys against the indexed file names. It is used through the
+//! [`SearchEngine`] trait, implemented by [`Search`]:
+//!
+//! ```
+//! use search::{Search, SearchEngine};
+//!
+//! let root = std::env::temp_dir().join("search_lib_doctest");
+//! std::fs::create_dir_all(root.join("src")).unwrap();
+//! std::fs::write(root.join("src").join("main.rs"), "fn main() {}").unwrap();
+//!
+//! let mut engine = Search::new();
+//! engine.set_root_dir(root.clone());
+//! engine.generate_index().unwrap();
+//! engine.search("main").unwrap();
+//!
+//! let found: Vec<_> = engine.iter_results().map(|(path, _)| path).collect();
+//! assert_eq!(found, vec![&root.join("src").join("main.rs")]);
+//! # std::fs::remove_dir_all(&root).unwrap();
+//! ```
+//!
+//! The `search` binary is a thin wrapper around [`app::run`].
+
+pub mod app;
+mod blacklist;
+mod category;
+mod cli;
+mod command;
+mod config;
+mod csv;
+mod debounce;
+mod drives;
+mod error;
+mod file_ops;
+mod filter;
+mod fonts;
+mod gitignore;
+mod guard;
+mod history;
+#[cfg(feature = "http")]
+mod http;
+mod icons;
+mod instance;
+mod json;
+mod message;
+mod preview;
+mod query;
+mod results;
+mod saved;
+mod schedule;
+mod search_engine;
+mod streams;
+mod transfer;
+mod ui_handle;
+mod watch;
+mod worker;
+
+pub use blacklist::Blacklist;
+pub use error::SearchError;
+pub use guard::ResourceLimits;
+pub use search_engine::{Haystack, IndexedFile, ScoredResult, Search, SearchEngine, SearchMode};
+pub use transfer::{read_results, write_results};
@@ -0,0 +1,16 @@
+use std::{path::PathBuf, time::Duration};
+
+/// A request from the UI to the background indexing threads.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum EngineMessage {
+    /// Indexes these roots into a single index now.
+    Reindex(Vec<PathBuf>),
+    /// Sets the time between scheduled reindexing runs.
+    SetInterval(Duration),
+    /// Makes a running index build wait before reading the next folder.
+    PauseIndexing,
+    /// Lets a paused index build continue.
+    ResumeIndexing,
+    /// Stops the background threads, each after the file it is working on.
+    Shutdown,
+}
@@ -0,0 +1,87 @@
+use std::{fs::File, io::Read, path::Path};
+
+/// The most lines a preview shows.
+pub(crate) const PREVIEW_LINES: usize = 200;
+
+/// The most bytes read for a preview, so that selecting a huge file stays quick.
+pub(crate) const PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// What the preview pane shows for a file.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Preview {
+    /// The first lines of a text file.
+    Text(String),
+    /// A folder, a binary file or a file that cannot be read.
+    Unavailable,
+}
+
+/// Reads the start of `path` for the preview pane: at most [`PREVIEW_LINES`] lines
+/// from the first [`PREVIEW_BYTES`] bytes. Files containing NUL bytes or that are
+/// not UTF-8 count as binary.
+pub(crate) fn load_preview(path: &Path) -> Preview {
+    let Ok(file) = File::open(path) else {
+        return Preview::Unavailable;
+    };
+    if !file.metadata().is_ok_and(|metadata| metadata.is_file()) {
+        return Preview::Unavailable;
+    }
+    let mut head = Vec::new();
+    if file.take(PREVIEW_BYTES).read_to_end(&mut head).is_err() || head.contains(&0) {
+        return Preview::Unavailable;
+    }
+    let text = match std::str::from_utf8(&head) {
+        Ok(text) => text,
+        // The cut after `PREVIEW_BYTES` may split the last character.
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()])
+            .expect("prefix up to valid_up_to is UTF-8"),
+        Err(_) => return Preview::Unavailable,
+    };
+    let lines: Vec<&str> = text.lines().take(PREVIEW_LINES).collect();
+    Preview::Text(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_preview() {
+        let dir = std::env::temp_dir().join("search_preview_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let text = dir.join("notes.txt");
+        std::fs::write(&text, "first\nsecond\n").unwrap();
+        assert_eq!(
+            load_preview(&text),
+            Preview::Text("first\nsecond".to_string())
+        );
+
+        let long = dir.join("long.txt");
+        let lines: Vec<String> = (0..PREVIEW_LINES + 50).map(|n| n.to_string()).collect();
+        std::fs::write(&long, lines.join("\n")).unwrap();
+        let Preview::Text(shown) = load_preview(&long) else {
+            panic!("expected a text preview");
+        };
+        assert_eq!(shown.lines().count(), PREVIEW_LINES);
+
+        let huge = dir.join("huge.txt");
+        let mut contents = "a".repeat(PREVIEW_BYTES as usize - 1);
+        contents.push('é');
+        std::fs::write(&huge, contents).unwrap();
+        let Preview::Text(shown) = load_preview(&huge) else {
+            panic!("expected a text preview");
+        };
+        assert_eq!(shown.len(), PREVIEW_BYTES as usize - 1);
+
+        let binary = dir.join("image.png");
+        std::fs::write(&binary, [0x89, b'P', b'N', b'G', 0, 0, 1]).unwrap();
+        assert_eq!(load_preview(&binary), Preview::Unavailable);
+        let latin1 = dir.join("latin1.txt");
+        std::fs::write(&latin1, [b'c', b'a', b'f', 0xE9, b'!']).unwrap();
+        assert_eq!(load_preview(&latin1), Preview::Unavailable);
+        assert_eq!(load_preview(&dir), Preview::Unavailable);
+        assert_eq!(load_preview(&dir.join("missing.txt")), Preview::Unavailable);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,266 @@
+/// A search query combining terms with `AND` and `OR`, e.g. `invoice AND 2024`
+/// or `jpg OR png`.
+///
+/// * The operators are only recognised in upper case, so `and` and `or` are
+///   searched for like any other word.
+/// * `AND` binds tighter than `OR`: `a OR b AND c` is `a OR (b AND c)`.
+/// * Parentheses group, e.g. `(jpg OR png) AND 2024`, and may be nested. They
+///   may be attached to a word, as in `(jpg`.
+/// * Words next to each other without an operator form one term, so
+///   `annual report OR summary` looks for `annual report` or `summary`.
+///
+/// A query without any operator is a single term and is searched exactly as
+/// before, parentheses included. A query that does not parse, such as `a AND`,
+/// `(a OR b` or `a (b OR c)`, is also searched as one plain term.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Query<T = String> {
+    Term(T),
+    /// Matches when every part matches.
+    And(Vec<Query<T>>),
+    /// Matches when any part matches.
+    Or(Vec<Query<T>>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+impl Query {
+    pub(crate) fn parse(text: &str) -> Query {
+        let tokens = tokenize(text);
+        if !tokens
+            .iter()
+            .any(|token| matches!(token, Token::And | Token::Or))
+        {
+            return Query::Term(text.to_string());
+        }
+        let mut parser = Parser { tokens, next: 0 };
+        match parser.or() {
+            Some(query) if parser.next == parser.tokens.len() => query,
+            _ => Query::Term(text.to_string()),
+        }
+    }
+}
+
+impl<T> Query<T> {
+    /// Converts every term with `convert`, e.g. into a compiled pattern, stopping
+    /// at the first error.
+    pub(crate) fn try_map<U, E>(
+        &self,
+        convert: &mut impl FnMut(&T) -> Result<U, E>,
+    ) -> Result<Query<U>, E> {
+        let parts = |parts: &[Query<T>], convert: &mut _| {
+            parts
+                .iter()
+                .map(|part| part.try_map(convert))
+                .collect::<Result<Vec<_>, E>>()
+        };
+        Ok(match self {
+            Query::Term(term) => Query::Term(convert(term)?),
+            Query::And(all) => Query::And(parts(all, convert)?),
+            Query::Or(any) => Query::Or(parts(any, convert)?),
+        })
+    }
+
+    /// Every term of the query, from left to right.
+    pub(crate) fn terms(&self) -> Vec<&T> {
+        match self {
+            Query::Term(term) => vec![term],
+            Query::And(parts) | Query::Or(parts) => parts.iter().flat_map(Query::terms).collect(),
+        }
+    }
+
+    /// Evaluates the query for one candidate, where `find` returns the text a
+    /// term matched. Returns the match of the first term that made the whole
+    /// query match, or `None` when it does not match.
+    pub(crate) fn find<'t>(&self, find: &mut impl FnMut(&T) -> Option<&'t str>) -> Option<&'t str> {
+        match self {
+            Query::Term(term) => find(term),
+            Query::And(all) => {
+                let mut first = None;
+                for part in all {
+                    let found = part.find(find)?;
+                    first.get_or_insert(found);
+                }
+                first
+            }
+            Query::Or(any) => any.iter().find_map(|part| part.find(find)),
+        }
+    }
+}
+
+/// Splits on whitespace, then splits the parentheses off the ends of each word.
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for word in text.split_whitespace() {
+        let trimmed = word.trim_start_matches('(');
+        tokens.extend((0..word.len() - trimmed.len()).map(|_| Token::Open));
+        let inner = trimmed.trim_end_matches(')');
+        match inner {
+            "" => {}
+            "AND" => tokens.push(Token::And),
+            "OR" => tokens.push(Token::Or),
+            word => tokens.push(Token::Word(word)),
+        }
+        tokens.extend((0..trimmed.len() - inner.len()).map(|_| Token::Close));
+    }
+    tokens
+}
+
+/// A recursive descent parser over the tokens, one method per precedence level.
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    next: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.next).copied()
+    }
+
+    fn or(&mut self) -> Option<Query> {
+        let mut any = vec![self.and()?];
+        while self.peek() == Some(Token::Or) {
+            self.next += 1;
+            any.push(self.and()?);
+        }
+        Some(if any.len() == 1 {
+            any.remove(0)
+        } else {
+            Query::Or(any)
+        })
+    }
+
+    fn and(&mut self) -> Option<Query> {
+        let mut all = vec![self.primary()?];
+        while self.peek() == Some(Token::And) {
+            self.next += 1;
+            all.push(self.primary()?);
+        }
+        Some(if all.len() == 1 {
+            all.remove(0)
+        } else {
+            Query::And(all)
+        })
+    }
+
+    fn primary(&mut self) -> Option<Query> {
+        if self.peek() == Some(Token::Open) {
+            self.next += 1;
+            let group = self.or()?;
+            if self.peek() != Some(Token::Close) {
+                return None;
+            }
+            self.next += 1;
+            return Some(group);
+        }
+        let mut words = Vec::new();
+        while let Some(Token::Word(word)) = self.peek() {
+            words.push(word);
+            self.next += 1;
+        }
+        if words.is_empty() {
+            return None;
+        }
+        Some(Query::Term(words.join(" ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(text: &str) -> Query {
+        Query::Term(text.to_string())
+    }
+
+    /// Whether `query` matches `name`, with terms as plain substrings.
+    fn matches(query: &str, name: &str) -> bool {
+        Query::parse(query)
+            .find(&mut |term: &String| name.contains(term.as_str()).then_some(name))
+            .is_some()
+    }
+
+    #[test]
+    fn test_and() {
+        assert_eq!(
+            Query::parse("invoice AND 2024"),
+            Query::And(vec![term("invoice"), term("2024")])
+        );
+        assert!(matches("invoice AND 2024", "invoice-2024.pdf"));
+        assert!(!matches("invoice AND 2024", "invoice-2023.pdf"));
+        assert!(!matches("invoice AND 2024", "receipt-2024.pdf"));
+    }
+
+    #[test]
+    fn test_or() {
+        assert_eq!(
+            Query::parse("jpg OR png OR gif"),
+            Query::Or(vec![term("jpg"), term("png"), term("gif")])
+        );
+        assert!(matches("jpg OR png", "photo.png"));
+        assert!(matches("jpg OR png", "photo.jpg"));
+        assert!(!matches("jpg OR png", "photo.gif"));
+    }
+
+    #[test]
+    fn test_mixed_with_grouping() {
+        assert_eq!(
+            Query::parse("a OR b AND c"),
+            Query::Or(vec![term("a"), Query::And(vec![term("b"), term("c")])])
+        );
+        let grouped = "(jpg OR png) AND holiday 2024";
+        assert_eq!(
+            Query::parse(grouped),
+            Query::And(vec![
+                Query::Or(vec![term("jpg"), term("png")]),
+                term("holiday 2024")
+            ])
+        );
+        assert!(matches(grouped, "holiday 2024 beach.png"));
+        assert!(!matches(grouped, "holiday 2024 notes.txt"));
+        assert!(!matches(grouped, "holiday 2023 beach.jpg"));
+        assert!(matches("(a) OR ((b))", "b"));
+    }
+
+    #[test]
+    fn test_without_operators_or_parse_errors_is_one_term() {
+        for text in [
+            "report final",
+            "file (1)",
+            "invoice and 2024",
+            "a AND",
+            "OR b",
+            "(a OR b",
+            "a (b OR c)",
+            "a AND ()",
+        ] {
+            assert_eq!(Query::parse(text), term(text));
+        }
+    }
+
+    #[test]
+    fn test_find_returns_the_first_match() {
+        let query = Query::parse("b AND a");
+        let found = query.find(&mut |term: &String| {
+            "a-b"
+                .find(term.as_str())
+                .map(|start| &"a-b"[start..start + term.len()])
+        });
+        assert_eq!(found, Some("b"));
+        let lengths: Result<Query<usize>, ()> = query.try_map(&mut |term| Ok(term.len()));
+        assert_eq!(
+            lengths,
+            Ok(Query::And(vec![Query::Term(1), Query::Term(1)]))
+        );
+        assert_eq!(
+            Query::parse("a OR (b AND c)").terms(),
+            ["a", "b", "c"].map(String::from).iter().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Counts results per containing directory, most matches first and ties by path.
+pub(crate) fn count_by_directory(results: &[(PathBuf, String)]) -> Vec<(PathBuf, usize)> {
+    let mut counts: HashMap<&Path, usize> = HashMap::new();
+    for (path, _) in results {
+        *counts.entry(parent_dir(path)).or_default() += 1;
+    }
+    let mut counts: Vec<(PathBuf, usize)> = counts
+        .into_iter()
+        .map(|(dir, count)| (dir.to_path_buf(), count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Which results stay visible when each directory may show at most `cap` of them.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct DirectoryCap {
+    /// One flag per result, `false` for results trimmed by the cap.
+    pub(crate) shown: Vec<bool>,
+    /// Directories that had results trimmed and how many, in order of first appearance.
+    pub(crate) hidden: Vec<(PathBuf, usize)>,
+}
+
+/// Keeps the first `cap` results of every directory in result order. A cap of zero
+/// keeps everything.
+pub(crate) fn cap_per_directory(results: &[(PathBuf, String)], cap: usize) -> DirectoryCap {
+    let mut seen: HashMap<&Path, usize> = HashMap::new();
+    let mut capped = DirectoryCap::default();
+    for (path, _) in results {
+        let dir = parent_dir(path);
+        let count = seen.entry(dir).or_default();
+        *count += 1;
+        let shown = cap == 0 || *count <= cap;
+        capped.shown.push(shown);
+        if !shown {
+            match capped.hidden.iter_mut().find(|(hidden, _)| hidden == dir) {
+                Some((_, hidden)) => *hidden += 1,
+                None => capped.hidden.push((dir.to_path_buf(), 1)),
+            }
+        }
+    }
+    capped
+}
+
+/// Buckets the results at `indices` by containing directory, directories sorted by
+/// path and the results of each in the order of `indices`.
+pub(crate) fn group_by_directory(
+    results: &[(PathBuf, String)],
+    indices: &[usize],
+) -> Vec<(PathBuf, Vec<usize>)> {
+    let mut groups: BTreeMap<&Path, Vec<usize>> = BTreeMap::new();
+    for &index in indices {
+        groups
+            .entry(parent_dir(&results[index].0))
+            .or_default()
+            .push(index);
+    }
+    groups
+        .into_iter()
+        .map(|(dir, indices)| (dir.to_path_buf(), indices))
+        .collect()
+}
+
+/// The drive a result is on for the drive filter: its drive letter such as `D:`
+/// when it has one, and otherwise the longest of `roots` that contains it.
+pub(crate) fn result_drive(path: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let text = path.to_string_lossy();
+    let mut chars = text.chars();
+    if let (Some(letter), Some(':')) = (chars.next(), chars.next()) {
+        if letter.is_ascii_alphabetic() {
+            return Some(PathBuf::from(format!("{}:", letter.to_ascii_uppercase())));
+        }
+    }
+    roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.as_os_str().len())
+        .cloned()
+}
+
+/// The drives the results are on, sorted, see [`result_drive`].
+pub(crate) fn result_drives(results: &[(PathBuf, String)], roots: &[PathBuf]) -> Vec<PathBuf> {
+    let drives: BTreeSet<PathBuf> = results
+        .iter()
+        .filter_map(|(path, _)| result_drive(path, roots))
+        .collect();
+    drives.into_iter().collect()
+}
+
+/// Returns the directory that contains `path`, or an empty path for bare names.
+pub(crate) fn parent_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+/// Drops results whose full path differs from an earlier one only by letter case,
+/// keeping the first occurrence.
+pub(crate) fn dedup_case_insensitive(results: &mut Vec<(PathBuf, String)>) {
+    let mut seen = HashSet::new();
+    results.retain(|(path, _)| seen.insert(path.to_string_lossy().to_lowercase()));
+}
+
+/// Splits `text` into consecutive parts, each marked with whether it lies in one
+/// of `ranges`. The ranges must be sorted and not overlap; ranges that are out of
+/// bounds or not on character boundaries are skipped.
+pub(crate) fn highlight_segments<'a>(
+    text: &'a str,
+    ranges: &[Range<usize>],
+) -> Vec<(&'a str, bool)> {
+    let mut segments = Vec::new();
+    let mut end = 0;
+    for range in ranges {
+        if range.start < end || text.get(range.clone()).is_none() {
+            continue;
+        }
+        if range.start > end {
+            segments.push((&text[end..range.start], false));
+        }
+        segments.push((&text[range.clone()], true));
+        end = range.end;
+    }
+    if end < text.len() {
+        segments.push((&text[end..], false));
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_segments() {
+        assert_eq!(
+            highlight_segments("report_re.txt", &[0..2, 7..9]),
+            [
+                ("re", true),
+                ("port_", false),
+                ("re", true),
+                (".txt", false)
+            ]
+        );
+        assert_eq!(highlight_segments("abc", &[]), [("abc", false)]);
+        assert_eq!(
+            highlight_segments("abc", &[Range { start: 0, end: 3 }]),
+            [("abc", true)]
+        );
+        // Out of bounds, overlapping and mid-character ranges are ignored.
+        assert_eq!(
+            highlight_segments("äb", &[1..2, 0..2, 1..3, 2..9]),
+            [("ä", true), ("b", false)]
+        );
+    }
+
+    #[test]
+    fn test_dedup_case_insensitive() {
+        let mut results: Vec<(PathBuf, String)> = [
+            "C:\\Docs\\Report.txt",
+            "C:\\docs\\report.TXT",
+            "C:\\Docs\\other.txt",
+            "c:\\DOCS\\REPORT.txt",
+        ]
+        .iter()
+        .map(|p| (PathBuf::from(p), String::new()))
+        .collect();
+        dedup_case_insensitive(&mut results);
+        let paths: Vec<PathBuf> = results.into_iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("C:\\Docs\\Report.txt"),
+                PathBuf::from("C:\\Docs\\other.txt")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_directory() {
+        let results: Vec<(PathBuf, String)> =
+            ["/b/x.txt", "/a/y.txt", "/b/a.txt", "/a/sub/x.txt", "x.txt"]
+                .iter()
+                .map(|path| (PathBuf::from(path), String::from("x")))
+                .collect();
+        assert_eq!(
+            group_by_directory(&results, &[0, 1, 2, 3, 4]),
+            vec![
+                (PathBuf::from(""), vec![4]),
+                (PathBuf::from("/a"), vec![1]),
+                (PathBuf::from("/a/sub"), vec![3]),
+                (PathBuf::from("/b"), vec![0, 2]),
+            ]
+        );
+        assert_eq!(
+            group_by_directory(&results, &[2, 0, 3]),
+            vec![
+                (PathBuf::from("/a/sub"), vec![3]),
+                (PathBuf::from("/b"), vec![2, 0]),
+            ]
+        );
+        assert!(group_by_directory(&results, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_result_drive() {
+        let roots = [
+            PathBuf::from("/mnt/data"),
+            PathBuf::from("/mnt/data/archive"),
+        ];
+        let drive = |path: &str| result_drive(Path::new(path), &roots);
+        assert_eq!(drive("D:\\Data\\a.txt"), Some(PathBuf::from("D:")));
+        assert_eq!(drive("d:/a.txt"), Some(PathBuf::from("D:")));
+        assert_eq!(drive("/mnt/data/a.txt"), Some(PathBuf::from("/mnt/data")));
+        assert_eq!(
+            drive("/mnt/data/archive/a.txt"),
+            Some(PathBuf::from("/mnt/data/archive"))
+        );
+        assert_eq!(drive("/mnt/database/a.txt"), None);
+
+        let results: Vec<(PathBuf, String)> = [
+            "E:\\x.txt",
+            "/mnt/data/x.txt",
+            "c:\\x.txt",
+            "C:\\y.txt",
+            "/tmp/x.txt",
+        ]
+        .iter()
+        .map(|path| (PathBuf::from(path), String::from("x")))
+        .collect();
+        assert_eq!(
+            result_drives(&results, &roots),
+            ["/mnt/data", "C:", "E:"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn test_count_by_directory() {
+        let results: Vec<(PathBuf, String)> = [
+            "/a/x.txt",
+            "/b/x.txt",
+            "/a/y.txt",
+            "/a/sub/x.txt",
+            "/b/z.txt",
+            "/c/x.txt",
+            "x.txt",
+        ]
+        .iter()
+        .map(|path| (PathBuf::from(path), String::from("x")))
+        .collect();
+        assert_eq!(
+            count_by_directory(&results),
+            vec![
+                (PathBuf::from("/a"), 2),
+                (PathBuf::from("/b"), 2),
+                (PathBuf::new(), 1),
+                (PathBuf::from("/a/sub"), 1),
+                (PathBuf::from("/c"), 1),
+            ]
+        );
+        assert!(count_by_directory(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_cap_per_directory() {
+        let results: Vec<(PathBuf, String)> = [
+            "/a/1.txt", "/a/2.txt", "/b/1.txt", "/a/3.txt", "/a/4.txt", "/b/2.txt", "/b/3.txt",
+            "/c/1.txt",
+        ]
+        .iter()
+        .map(|path| (PathBuf::from(path), String::from("txt")))
+        .collect();
+
+        let capped = cap_per_directory(&results, 2);
+        let shown: Vec<_> = results
+            .iter()
+            .zip(&capped.shown)
+            .filter(|(_, shown)| **shown)
+            .map(|((path, _), _)| path.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            shown,
+            ["/a/1.txt", "/a/2.txt", "/b/1.txt", "/b/2.txt", "/c/1.txt"]
+        );
+        assert_eq!(
+            capped.hidden,
+            vec![(PathBuf::from("/a"), 2), (PathBuf::from("/b"), 1)]
+        );
+
+        let uncapped = cap_per_directory(&results, 0);
+        assert!(uncapped.shown.iter().all(|shown| *shown));
+        assert!(uncapped.hidden.is_empty());
+    }
+}
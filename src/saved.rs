@@ -0,0 +1,163 @@
+use std::{fs::read_to_string, io};
+
+use crate::json::{json_string, parse_json, JsonValue};
+
+/// Where saved searches are kept between runs.
+pub(crate) const SAVED_SEARCHES_FILE: &str = "saved.json";
+
+/// The settings next to the search box that a saved search restores. Filters
+/// typed into the query, such as `size:>1mb`, are part of the query itself.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SearchOptions {
+    pub(crate) extensions: String,
+    pub(crate) category: Option<String>,
+}
+
+/// A query saved under a name so it can be run again.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SavedSearch {
+    pub(crate) name: String,
+    pub(crate) query: String,
+    pub(crate) options: SearchOptions,
+}
+
+impl SavedSearch {
+    fn to_json(&self) -> String {
+        let category = match &self.options.category {
+            Some(category) => json_string(category),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"name\":{},\"query\":{},\"options\":{{\"extensions\":{},\"category\":{}}}}}",
+            json_string(&self.name),
+            json_string(&self.query),
+            json_string(&self.options.extensions),
+            category
+        )
+    }
+
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        let options = value.get("options");
+        let option = |key| options.and_then(|options| options.get(key));
+        Some(SavedSearch {
+            name: value.get("name")?.as_str()?.to_string(),
+            query: value.get("query")?.as_str()?.to_string(),
+            options: SearchOptions {
+                extensions: option("extensions")
+                    .and_then(JsonValue::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                category: option("category")
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string),
+            },
+        })
+    }
+}
+
+/// The saved searches, in the order they were added. Names are unique.
+///
+/// Saved as a JSON array of `{"name", "query", "options"}` objects.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SavedSearches {
+    searches: Vec<SavedSearch>,
+}
+
+impl SavedSearches {
+    /// Reads the saved searches from `path`. No file gives none; a file that is
+    /// not valid JSON is an error, so it is not overwritten unnoticed.
+    pub(crate) fn load(path: &str) -> Result<Self, String> {
+        let Ok(buf) = read_to_string(path) else {
+            return Ok(SavedSearches::default());
+        };
+        let value = parse_json(&buf).map_err(|e| format!("Invalid {}: {}", path, e))?;
+        let searches = value
+            .as_array()
+            .ok_or_else(|| format!("Invalid {}: expected an array", path))?
+            .iter()
+            .filter_map(SavedSearch::from_json)
+            .collect();
+        Ok(SavedSearches { searches })
+    }
+
+    pub(crate) fn save(&self, path: &str) -> io::Result<()> {
+        let entries: Vec<String> = self
+            .searches
+            .iter()
+            .map(|search| format!("  {}", search.to_json()))
+            .collect();
+        std::fs::write(path, format!("[\n{}\n]\n", entries.join(",\n")))
+    }
+
+    /// Adds `search`, replacing a saved search with the same name.
+    pub(crate) fn add(&mut self, search: SavedSearch) {
+        match self
+            .searches
+            .iter_mut()
+            .find(|saved| saved.name == search.name)
+        {
+            Some(saved) => *saved = search,
+            None => self.searches.push(search),
+        }
+    }
+
+    /// Removes the saved search called `name`, returning whether there was one.
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        let len = self.searches.len();
+        self.searches.retain(|saved| saved.name != name);
+        self.searches.len() != len
+    }
+
+    pub(crate) fn list(&self) -> &[SavedSearch] {
+        &self.searches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(name: &str, query: &str, category: Option<&str>) -> SavedSearch {
+        SavedSearch {
+            name: name.to_string(),
+            query: query.to_string(),
+            options: SearchOptions {
+                extensions: "rs, toml".to_string(),
+                category: category.map(str::to_string),
+            },
+        }
+    }
+
+    #[test]
+    fn test_add_replaces_and_remove() {
+        let mut searches = SavedSearches::default();
+        searches.add(saved("logs", "\\.log$", None));
+        searches.add(saved("big", "size:>1mb", None));
+        searches.add(saved("logs", "error", None));
+        assert_eq!(searches.list().len(), 2);
+        assert_eq!(searches.list()[0].query, "error");
+        assert!(searches.remove("logs"));
+        assert!(!searches.remove("logs"));
+        assert_eq!(searches.list(), [saved("big", "size:>1mb", None)]);
+    }
+
+    #[test]
+    fn test_save_and_reload() {
+        let path = std::env::temp_dir().join("search_saved_test.json");
+        let path = path.to_str().unwrap();
+        let mut searches = SavedSearches::default();
+        searches.add(saved(
+            "C:\\ \"logs\"",
+            "\\.log$ modified:<7d",
+            Some("document"),
+        ));
+        searches.add(saved("plain", "main", None));
+        searches.save(path).unwrap();
+        assert_eq!(SavedSearches::load(path), Ok(searches));
+
+        std::fs::write(path, "[{\"name\": ").unwrap();
+        assert!(SavedSearches::load(path).is_err());
+        let _ = std::fs::remove_file(path);
+        assert_eq!(SavedSearches::load(path), Ok(SavedSearches::default()));
+    }
+}
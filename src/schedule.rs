@@ -0,0 +1,199 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// Tracks when each indexed root is next due for a reindex.
+///
+/// Roots may carry their own interval; roots without one follow the global
+/// update interval that the update thread adapts at runtime.
+pub(crate) struct UpdateSchedule {
+    roots: Vec<ScheduledRoot>,
+    uses_defaults: bool,
+}
+
+struct ScheduledRoot {
+    root: PathBuf,
+    interval: Option<Duration>,
+    last_run: Instant,
+}
+
+impl UpdateSchedule {
+    pub(crate) fn new(roots: Vec<(PathBuf, Option<Duration>)>, now: Instant) -> Self {
+        UpdateSchedule {
+            uses_defaults: false,
+            roots: roots
+                .into_iter()
+                .map(|(root, interval)| ScheduledRoot {
+                    root,
+                    interval,
+                    last_run: now,
+                })
+                .collect(),
+        }
+    }
+
+    /// Schedules the configured `roots`, where a root without an interval uses
+    /// the global one. Falls back to `default_roots` when none are configured.
+    pub(crate) fn configured(
+        roots: Vec<(PathBuf, Option<Duration>)>,
+        default_roots: Vec<PathBuf>,
+        now: Instant,
+    ) -> Self {
+        if roots.is_empty() {
+            return UpdateSchedule {
+                uses_defaults: true,
+                ..UpdateSchedule::new(
+                    default_roots.into_iter().map(|root| (root, None)).collect(),
+                    now,
+                )
+            };
+        }
+        UpdateSchedule::new(roots, now)
+    }
+
+    fn due_at(&self, root: &ScheduledRoot, default_interval: Duration) -> Instant {
+        root.last_run + root.interval.unwrap_or(default_interval)
+    }
+
+    /// Returns the roots whose interval has elapsed at `now`, in configured order.
+    pub(crate) fn due(&self, now: Instant, default_interval: Duration) -> Vec<PathBuf> {
+        self.roots
+            .iter()
+            .filter(|root| self.due_at(root, default_interval) <= now)
+            .map(|root| root.root.clone())
+            .collect()
+    }
+
+    /// Returns the earliest instant at which any root becomes due.
+    pub(crate) fn next_due(&self, default_interval: Duration) -> Option<Instant> {
+        self.roots
+            .iter()
+            .map(|root| self.due_at(root, default_interval))
+            .min()
+    }
+
+    /// Whether the roots are the defaults passed to `configured` rather than
+    /// configured ones.
+    pub(crate) fn uses_defaults(&self) -> bool {
+        self.uses_defaults
+    }
+
+    /// Schedules `root` on the global interval, first due one interval after
+    /// `now`. Does nothing if it is already scheduled.
+    pub(crate) fn add(&mut self, root: PathBuf, now: Instant) {
+        if !self.contains(&root) {
+            self.roots.push(ScheduledRoot {
+                root,
+                interval: None,
+                last_run: now,
+            });
+        }
+    }
+
+    pub(crate) fn contains(&self, root: &PathBuf) -> bool {
+        self.roots.iter().any(|entry| entry.root == *root)
+    }
+
+    pub(crate) fn mark_updated(&mut self, root: &PathBuf, now: Instant) {
+        if let Some(entry) = self.roots.iter_mut().find(|entry| entry.root == *root) {
+            entry.last_run = now;
+        }
+    }
+}
+
+/// Parses the `roots.ini` of earlier versions, one `root=seconds` per line. A
+/// root without `=seconds` uses the global interval.
+pub(crate) fn parse_roots(buf: &str) -> Vec<(PathBuf, Option<Duration>)> {
+    buf.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.rsplit_once('=') {
+            Some((root, secs)) => (
+                PathBuf::from(root.trim()),
+                secs.trim().parse().ok().map(Duration::from_secs),
+            ),
+            None => (PathBuf::from(line), None),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_roots() {
+        let roots = parse_roots("# comment\nC:\\Projects=60\n\nD:\\\nE:\\Share = abc\n");
+        assert_eq!(
+            roots,
+            vec![
+                (PathBuf::from("C:\\Projects"), Some(Duration::from_secs(60))),
+                (PathBuf::from("D:\\"), None),
+                (PathBuf::from("E:\\Share"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_due_roots() {
+        let start = Instant::now();
+        let fast = PathBuf::from("fast");
+        let slow = PathBuf::from("slow");
+        let global = PathBuf::from("global");
+        let mut schedule = UpdateSchedule::new(
+            vec![
+                (fast.clone(), Some(Duration::from_secs(60))),
+                (slow.clone(), Some(Duration::from_secs(3600))),
+                (global.clone(), None),
+            ],
+            start,
+        );
+        let default_interval = Duration::from_secs(600);
+
+        assert!(schedule.due(start, default_interval).is_empty());
+        assert_eq!(
+            schedule.next_due(default_interval),
+            Some(start + Duration::from_secs(60))
+        );
+
+        let t = start + Duration::from_secs(60);
+        assert_eq!(schedule.due(t, default_interval), vec![fast.clone()]);
+        schedule.mark_updated(&fast, t);
+        assert!(schedule.due(t, default_interval).is_empty());
+
+        let t = start + Duration::from_secs(600);
+        assert_eq!(
+            schedule.due(t, default_interval),
+            vec![fast.clone(), global]
+        );
+        assert_eq!(
+            schedule.due(t, Duration::from_secs(900)),
+            vec![fast.clone()]
+        );
+
+        let t = start + Duration::from_secs(3600);
+        assert!(schedule.due(t, default_interval).contains(&slow));
+    }
+
+    #[test]
+    fn test_add_root_and_defaults() {
+        let start = Instant::now();
+        let defaults = vec![PathBuf::from("C:\\")];
+        let mut schedule = UpdateSchedule::configured(Vec::new(), defaults, start);
+        assert!(schedule.uses_defaults());
+
+        let usb = PathBuf::from("E:\\");
+        let t = start + Duration::from_secs(300);
+        schedule.add(usb.clone(), t);
+        schedule.add(usb.clone(), start);
+        assert!(schedule.contains(&usb));
+        let interval = Duration::from_secs(600);
+        assert_eq!(
+            schedule.due(start + interval, interval),
+            [PathBuf::from("C:\\")]
+        );
+        assert_eq!(schedule.due(t + interval, interval).len(), 2);
+        assert!(!UpdateSchedule::new(Vec::new(), start).uses_defaults());
+    }
+}
@@ -1,10 +1,180 @@
 use std::{
-    fs::{read_dir, File},
-    io::{BufReader, BufWriter},
-    path::PathBuf,
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap, HashMap, HashSet},
+    fs::{read_dir, read_to_string, DirEntry, File, Metadata},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    slice::Iter,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::Sender,
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use regex::Regex;
+use bincode::Options;
+use flate2::{bufread::GzDecoder, write::GzEncoder, Compression};
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::blacklist::Blacklist;
+use crate::category::Categories;
+use crate::csv::write_results_csv;
+use crate::error::SearchError;
+use crate::filter::{pattern_matches, QueryFilters};
+use crate::gitignore::{is_ignored, GitIgnore};
+use crate::guard::{
+    available_disk_space, available_memory, check_disk_space, check_memory, estimated_index_size,
+    ResourceLimits, MEMORY_CHECK_INTERVAL,
+};
+use crate::json::{json_os_string, parse_json, JsonValue};
+use crate::query::Query;
+use crate::results::result_drive;
+use crate::streams::{alternate_streams, is_stream_entry, stream_entry};
+use crate::worker::CancelToken;
+
+/// Number of indexed files after which a partial index is first published while
+/// indexing in memory mode.
+pub(crate) const PARTIAL_INDEX_START: usize = 50_000;
+
+/// How often indexing reports its progress to the UI.
+pub(crate) const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often paused indexing checks whether it may continue.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How far an indexing run has got, sent while it traverses the roots.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct IndexProgress {
+    /// Files indexed so far in this run. Never decreases between reports.
+    pub(crate) files: usize,
+    /// The folder being read, or the first root once the run has finished.
+    pub(crate) current_dir: PathBuf,
+}
+
+/// A whole index for `root` handed from the indexing threads to the UI. Partial
+/// snapshots are consistent prefixes of the finished index.
+pub(crate) struct IndexSnapshot {
+    pub(crate) roots: Vec<PathBuf>,
+    pub(crate) files: Vec<IndexedFile>,
+    pub(crate) complete: bool,
+}
+
+/// A file in the index, with the size and modification time it had when it was
+/// indexed.
+///
+/// Entries loaded from an index saved before the metadata was stored have a size
+/// of 0 and a modification time of [`UNIX_EPOCH`] until the next full reindex.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub path: PathBuf,
+    /// Size in bytes.
+    pub size: u64,
+    pub modified: SystemTime,
+    /// When the file was created, stored only while creation times are indexed
+    /// (see `set_index_created_time`) and where the platform reports one.
+    pub created: Option<SystemTime>,
+}
+
+/// An entry of a version 3 index, saved before creation times could be stored.
+#[derive(Deserialize)]
+struct IndexedFileV3 {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+impl From<IndexedFileV3> for IndexedFile {
+    fn from(file: IndexedFileV3) -> Self {
+        IndexedFile {
+            path: file.path,
+            size: file.size,
+            modified: file.modified,
+            created: None,
+        }
+    }
+}
+
+impl IndexedFile {
+    /// The entry for `path` with its current metadata, which is left unknown when
+    /// it cannot be read. The creation time is not stored.
+    pub fn read(path: PathBuf) -> Self {
+        IndexedFile::read_with(path, false)
+    }
+
+    /// Like `read`, also storing the creation time if `created` is set.
+    pub(crate) fn read_with(path: PathBuf, created: bool) -> Self {
+        match path.metadata() {
+            Ok(metadata) => IndexedFile::with_metadata(path, &metadata, created),
+            Err(_) => IndexedFile::from(path),
+        }
+    }
+
+    fn with_metadata(path: PathBuf, metadata: &Metadata, created: bool) -> Self {
+        // Times before 1970 cannot be serialized, so they count as unknown.
+        let since_epoch =
+            |time: io::Result<SystemTime>| time.ok().filter(|time| *time >= UNIX_EPOCH);
+        IndexedFile {
+            path,
+            size: metadata.len(),
+            modified: since_epoch(metadata.modified()).unwrap_or(UNIX_EPOCH),
+            created: if created {
+                since_epoch(metadata.created())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl IndexedFile {
+    /// The modification time, or `None` when it is unknown.
+    pub(crate) fn modified_time(&self) -> Option<SystemTime> {
+        (self.modified != UNIX_EPOCH).then_some(self.modified)
+    }
+
+    /// The size in bytes, or `None` when the metadata is unknown.
+    pub(crate) fn known_size(&self) -> Option<u64> {
+        self.modified_time().map(|_| self.size)
+    }
+}
+
+impl From<PathBuf> for IndexedFile {
+    /// An entry whose size and modification time are unknown.
+    fn from(path: PathBuf) -> Self {
+        IndexedFile {
+            path,
+            size: 0,
+            modified: UNIX_EPOCH,
+            created: None,
+        }
+    }
+}
+
+/// Starts every index file, followed by the format version as a little-endian `u32`
+/// and the gzip compressed bincode payload.
+///
+/// Older files are still read: version 1 has no version number, so the gzip stream
+/// follows the magic directly, and files without the magic are plain bincode.
+/// Versions before 3 hold only the paths, without the size and modification time,
+/// and version 3 has no creation times.
+const INDEX_MAGIC: &[u8; 4] = b"SIDX";
+
+/// The index format version written by [`SearchEngine::save_index`].
+pub(crate) const INDEX_VERSION: u32 = 4;
+
+const GZIP_MAGIC: &[u8; 2] = &[0x1f, 0x8b];
+
+/// Maximum number of indexed files inspected by [`SearchEngine::fallback_search`].
+const FALLBACK_SCAN_LIMIT: usize = 200_000;
+
+/// Files larger than this are skipped by [`SearchEngine::search_contents`] unless
+/// another cap is set with `set_content_size_limit`.
+pub(crate) const DEFAULT_CONTENT_SIZE_LIMIT: u64 = 1024 * 1024;
+
 #[derive(Clone)]
 /// Represents a search operation within a directory structure.
 ///
@@ -14,17 +184,76 @@ use regex::Regex;
 ///
 /// # Fields
 ///
-/// * `indexed_files` - A vector of `PathBuf` representing the files that have been indexed.
+/// * `indexed_files` - The files that have been indexed with their size and modification time,
+///   shared with the copies made by `search_view` and copied on write.
+/// * `dir_times` - Modification time of every folder traversed by the last indexing run,
+///   used by `update_index` to find the folders that changed since.
 /// * `search_results` - A vector of tuples where each tuple contains a `PathBuf` and a `String`,
 ///   representing the file path and the search result respectively.
+/// * `match_ranges` - Where the search matched in the file name of each result, see
+///   `name_ranges`.
 /// * `root_dir` - A `PathBuf` representing the root directory where the search is performed.
+/// * `extra_roots` - Further directories indexed together with `root_dir` into the same index.
 /// * `search_results_limit` - A `usize` representing the maximum number of search results to be returned.
-pub(crate) struct Search {
-    indexed_files: Vec<PathBuf>,
+/// * `results_truncated` - Whether a search stopped at `search_results_limit` with more
+///   matches left.
+/// * `search_scope` - Directories that results must lie within; empty means the whole index.
+/// * `ignore_separators` - Whether names are compared with `compact_match` instead of as a regex.
+/// * `search_mode` - How the search key is turned into a pattern.
+/// * `resource_limits` - Free disk and memory that indexing must leave untouched.
+/// * `blacklist` - Files that are neither indexed nor returned by searches.
+/// * `index_streams` - Whether NTFS alternate data streams are indexed as `file:stream` entries.
+/// * `index_created` - Whether indexing stores the creation time of each file.
+/// * `haystack` - Which text of each indexed path a search is matched against.
+/// * `ignore_case` - Whether `search` matches regardless of letter case.
+/// * `content_size_limit` - Largest file in bytes that `search_contents` reads.
+/// * `extensions` - Lowercase extensions that results must have; empty allows any file.
+/// * `exclude_dirs` - Folders that indexing does not descend into, see `is_excluded_dir`.
+/// * `respect_gitignore` - Whether indexing skips what `.gitignore` files ignore.
+/// * `skip_hidden` - Whether indexing skips hidden and system entries, see `is_hidden`.
+/// * `rank_results` - Whether searches sort their results best match first, see `score`.
+/// * `whole_word` - Whether `search` only accepts matches that are whole words, see
+///   `find_whole_word`.
+/// * `cancel` - Stops searches early once a newer search replaces them.
+/// * `max_depth` - How many folder levels below each root indexing descends into;
+///   `None` has no limit.
+/// * `progress` - Where indexing reports its progress, and at most how often.
+/// * `paused` - Set while indexing should wait before reading the next folder.
+/// * `stop` - Once set, indexing gives up after the file it is reading.
+pub struct Search {
+    indexed_files: Arc<Vec<IndexedFile>>,
+    dir_times: HashMap<PathBuf, SystemTime>,
     search_results: Vec<(PathBuf, String)>,
+    match_ranges: HashMap<PathBuf, Vec<Range<usize>>>,
     root_dir: PathBuf,
+    extra_roots: Vec<PathBuf>,
 
     search_results_limit: usize,
+    results_truncated: bool,
+    search_scope: Vec<PathBuf>,
+    ignore_separators: bool,
+    search_mode: SearchMode,
+    resource_limits: ResourceLimits,
+    blacklist: Blacklist,
+    index_streams: bool,
+    index_created: bool,
+    haystack: Haystack,
+    ignore_case: bool,
+    content_size_limit: u64,
+    extensions: Vec<String>,
+    exclude_dirs: Vec<PathBuf>,
+    respect_gitignore: bool,
+    skip_hidden: bool,
+    rank_results: bool,
+    whole_word: bool,
+    filters: QueryFilters,
+    categories: Categories,
+    drive: Option<PathBuf>,
+    cancel: Option<CancelToken>,
+    max_depth: Option<usize>,
+    progress: Option<(Sender<IndexProgress>, Duration)>,
+    paused: Option<Arc<AtomicBool>>,
+    stop: Option<Arc<AtomicBool>>,
 }
 #[allow(dead_code)]
 /// A trait that defines the behavior of a search engine.
@@ -34,227 +263,3939 @@ pub(crate) struct Search {
 /// - `new() -> Self`:
 ///   Creates a new instance of the search engine.
 ///
-/// - `generate_index(&mut self)`:
-///   Generates the search index.
+/// - `generate_index(&mut self) -> Result<(), SearchError>`:
+///   Generates the search index, traversing the folders of each root in
+///   parallel, and stops with an error when free memory falls below the
+///   configured minimum.
+///
+/// - `generate_index_with_progress(&mut self, first_snapshot, on_partial) -> Result<(), SearchError>`:
+///   Like `generate_index`, but hands the files indexed so far to `on_partial` once
+///   `first_snapshot` files are found and again each time that count doubles, so
+///   copying the snapshots costs at most twice the final index size.
 ///
-/// - `save_index(&self)`:
-///   Saves the current search index to a persistent storage.
+/// - `update_index(&mut self) -> Result<bool, SearchError>`:
+///   Brings an index built by this engine up to date by re-reading only the
+///   folders whose modification time changed, dropping files and folders that
+///   are gone, and returns whether any folder changed. Falls back to
+///   `generate_index` when the folder times are unknown, e.g. after `load_index`.
 ///
-/// - `load_index(&mut self)`:
-///   Loads the search index from persistent storage.
+/// - `save_index(&self) -> Result<(), SearchError>`:
+///   Saves the current search index to a persistent storage, gzip compressed,
+///   refusing when the write would leave less than the configured minimum free
+///   disk space.
+///   An empty index is never written, so it cannot replace a saved one.
+///
+/// - `load_index(&mut self) -> Result<(), SearchError>`:
+///   Loads the search index from persistent storage, reading every earlier
+///   format too, so saving it again migrates the file. A missing file loads an
+///   empty index; an unknown version or unreadable data also leaves the index
+///   empty and is returned as an error.
 ///
 /// - `len(&self) -> usize`:
 ///   Returns the number of items in the search index.
 ///
-/// - `get_index(&self) -> &Vec<PathBuf>`:
+/// - `count_files(&self) -> usize`:
+///   Returns the number of files in the search index, leaving out the entries
+///   for alternate data streams.
+///
+/// - `is_empty(&self) -> bool`:
+///   Returns whether the search index is empty.
+///
+/// - `get_index(&self) -> &Vec<IndexedFile>`:
 ///   Returns a reference to the search index.
 ///
+/// - `replace_index(&mut self, files: Vec<IndexedFile>)`:
+///   Replaces the search index with an index built elsewhere.
+///
+/// - `take_index(&mut self) -> Vec<IndexedFile>`:
+///   Moves the search index out, leaving it empty.
+///
+/// - `merge_index(&mut self, files: Vec<IndexedFile>)`:
+///   Adds an index built elsewhere, e.g. for another root, to the search index.
+///   A file indexed in both keeps its place but takes the size and modification
+///   time from `files`, and a file listed twice in `files` is added once.
+///
+/// - `insert_path(&mut self, path: PathBuf)`:
+///   Adds a single file with its current metadata to the search index unless it
+///   is already present.
+///
+/// - `remove_path(&mut self, path: &Path) -> bool`:
+///   Removes a single file from the search index and the current results,
+///   returning whether it was indexed.
+///
 /// - `set_root_dir(&mut self, root_dir: PathBuf)`:
-///   Sets the root directory for the search engine.
+///   Sets the root directory for the search engine, dropping any roots added
+///   with `add_root_dir`.
+///
+/// - `add_root_dir(&mut self, root_dir: PathBuf)`:
+///   Adds another directory to index and search together with the root directory.
+///
+/// - `get_root_dirs(&self) -> Vec<PathBuf>`:
+///   Returns the root directory followed by the added ones.
+///
+/// - `get_index_file(&self) -> String`:
+///   Returns the name of the file the index of the current roots is saved to.
 ///
 /// - `get_root_dir(&self) -> &PathBuf`:
 ///   Returns a reference to the root directory of the search engine.
 ///
-/// - `search(&mut self, key: &str)`:
-///   Performs a search with the given key.
+/// - `search(&mut self, key: &str) -> Result<(), SearchError>`:
+///   Performs a search with the given key, failing without results when the key
+///   is not a valid pattern for the current [`SearchMode`]. Terms may be combined
+///   with `AND` and `OR`, e.g. `invoice AND 2024` or `(jpg OR png) AND beach`.
+///
+/// - `search_substring(&mut self, keyword: &str) -> Result<(), SearchError>`:
+///   Searches for `keyword` literally anywhere in the text, whatever the
+///   configured [`SearchMode`].
+///
+/// - `search_contents(&self, keyword: &str) -> Vec<(PathBuf, usize)>`:
+///   Reads each indexed text file and returns every line containing `keyword`
+///   as the file and its 1-based line number. Files over the content size limit
+///   or that are not valid UTF-8 are skipped.
+///
+/// - `fallback_search(&mut self, key: &str)`:
+///   Scans up to a bounded number of indexed files for names containing `key`
+///   literally, ignoring case. Used when `search` finds nothing.
+///
+/// - `complete(&self, prefix: &str, limit: usize) -> Vec<String>`:
+///   Returns up to `limit` distinct file names in the index that start with
+///   `prefix`, in alphabetical order. Letter case is ignored unless `search`
+///   matches case. No match, or an empty `prefix`, gives no names.
 ///
 /// - `get_results(&self) -> &Vec<(PathBuf, String)>`:
 ///   Returns a reference to the search results.
 ///
+/// - `iter_results(&self) -> Iter<'_, (PathBuf, String)>`:
+///   Iterates over the search results by reference.
+///
+/// - `take_results(&mut self) -> Vec<(PathBuf, String)>`:
+///   Moves the search results out without copying them, leaving none behind.
+///
+/// - `match_ranges(&self, path: &Path) -> &[Range<usize>]`:
+///   Returns the byte ranges of every match in the file name of the result
+///   `path`, sorted and not overlapping, for highlighting. A match that lies only
+///   in the folders of a full path search has none.
+///
+/// - `take_match_ranges(&mut self) -> HashMap<PathBuf, Vec<Range<usize>>>`:
+///   Moves the match ranges of all results out, keyed by result path.
+///
+/// - `scored_results(&self) -> Vec<ScoredResult>`:
+///   Returns the search results with the relevance `score` of each.
+///
+/// - `results_to_json(&self) -> String`:
+///   Renders the search results as a JSON array of objects with `path` and
+///   `name`, plus `size` in bytes and `modified` in seconds since the Unix epoch
+///   as stored in the index, when known.
+///
+/// - `export_index_json(&self, path: &Path) -> Result<(), SearchError>`:
+///   Writes the search index to `path` as JSON, see `index_to_json`. An empty
+///   index is [`SearchError::IndexNotLoaded`].
+///
+/// - `import_index_json(&mut self, path: &Path) -> Result<(), SearchError>`:
+///   Replaces the search index with one read from a JSON file written by
+///   `export_index_json`, possibly edited since. A missing file is
+///   [`SearchError::NotFound`]; on any error the index is kept.
+///
+/// - `export_results_csv(&self, out: &Path) -> io::Result<()>`:
+///   Writes the search results to the file `out` as CSV with the columns
+///   `path`, `name`, `size` and `modified`, see `write_results_csv`.
+///
 /// - `reset_search_results(&mut self)`:
-///   Resets the search results.
+///   Resets the search results and the truncation flag.
 ///
 /// - `set_search_results_limit(&mut self, limit: usize)`:
-///   Sets a limit on the number of search results.
+///   Sets a limit on the number of search results. Unranked searches stop
+///   collecting once it is reached, so a query that matches everything stays
+///   cheap; ranked searches weigh every match and keep the best ones.
+///
+/// - `get_search_results_limit(&self) -> usize`:
+///   Returns the limit on the number of search results.
+///
+/// - `results_truncated(&self) -> bool`:
+///   Returns whether a search since the last reset stopped at the limit while
+///   more files matched.
+///
+/// - `set_rank_results(&mut self, rank: bool)`:
+///   Makes `search` and `fallback_search` sort their results by `score`, best
+///   first (the default). Without it results stay in index order.
+///
+/// - `set_whole_word(&mut self, whole_word: bool)`:
+///   Makes `search` accept a match only when it is a whole word, so `test` finds
+///   `test.txt` but not `contest.txt`. Off by default; separator-insensitive
+///   matching ignores it.
+///
+/// - `set_search_scope(&mut self, scope: Vec<PathBuf>)`:
+///   Restricts searches to indexed files under any of the given directories
+///   without touching the index itself. An empty list searches everything.
+///
+/// - `set_ignore_separators(&mut self, ignore: bool)`:
+///   Switches `search` between regex matching and separator-insensitive
+///   matching (see `compact_match`).
+///
+/// - `set_search_mode(&mut self, mode: SearchMode)`:
+///   Chooses how `search` interprets its key.
+///
+/// - `set_ignore_case(&mut self, ignore_case: bool)`:
+///   Makes `search` match regardless of letter case (the default). Returned
+///   paths always keep their original case.
+///
+/// - `set_haystack(&mut self, haystack: Haystack)`:
+///   Chooses what text of each indexed path `search` and `fallback_search`
+///   match against, whatever the matching mode.
+///
+/// - `set_content_size_limit(&mut self, bytes: u64)`:
+///   Sets the largest file `search_contents` reads.
+///
+/// - `set_extension_filter(&mut self, extensions: Vec<String>)`:
+///   Restricts searches to files with one of the given extensions, compared
+///   ignoring case and any leading `.`. Files without an extension are left out
+///   while the list is non-empty; an empty list allows every file.
+///
+/// - `set_exclude_dirs(&mut self, exclude_dirs: Vec<PathBuf>)`:
+///   Sets the folders, by name, glob or full path, that indexing skips entirely.
+///
+/// - `set_respect_gitignore(&mut self, enabled: bool)`:
+///   Skips files and folders ignored by the `.gitignore` files found while
+///   indexing, deeper files and later rules taking precedence. Off by default.
+///
+/// - `set_skip_hidden(&mut self, skip: bool)`:
+///   Leaves hidden and system files and folders out of the index. On by default.
+///
+/// - `set_max_depth(&mut self, max_depth: Option<usize>)`:
+///   Limits indexing to folders at most `max_depth` levels below a root, so `0`
+///   indexes only the files directly in each root. `None` (the default) indexes
+///   every level.
+///
+/// - `set_resource_limits(&mut self, limits: ResourceLimits)`:
+///   Sets the free disk and memory thresholds checked while indexing.
+///
+/// - `set_blacklist(&mut self, blacklist: Blacklist)`:
+///   Sets the files that indexing skips and searches leave out.
+///
+/// - `set_index_created_time(&mut self, enabled: bool)`:
+///   Also stores the creation time of each file while indexing, for `created:`
+///   filters. Off by default; files on platforms or file systems that do not
+///   record creation times have none either way.
+///
+/// - `set_index_streams(&mut self, enabled: bool)`:
+///   Also indexes the alternate data streams of each file. Only has an effect
+///   on Windows builds with the `ads` feature.
 ///
 /// - `clear_index_files(&mut self)`:
 ///   Clears the index files.
 pub trait SearchEngine {
     fn new() -> Self;
-    fn generate_index(&mut self);
-    fn save_index(&self);
-    fn load_index(&mut self);
+    fn generate_index(&mut self) -> Result<(), SearchError>;
+    fn generate_index_with_progress(
+        &mut self,
+        first_snapshot: usize,
+        on_partial: &mut dyn FnMut(&[IndexedFile]),
+    ) -> Result<(), SearchError>;
+    fn update_index(&mut self) -> Result<bool, SearchError>;
+    fn save_index(&self) -> Result<(), SearchError>;
+    fn load_index(&mut self) -> Result<(), SearchError>;
     fn len(&self) -> usize;
-    fn get_index(&self) -> &Vec<PathBuf>;
+    fn count_files(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    fn get_index(&self) -> &Vec<IndexedFile>;
+    fn replace_index(&mut self, files: Vec<IndexedFile>);
+    fn take_index(&mut self) -> Vec<IndexedFile>;
+    fn merge_index(&mut self, files: Vec<IndexedFile>);
+    fn insert_path(&mut self, path: PathBuf);
+    fn remove_path(&mut self, path: &Path) -> bool;
     fn set_root_dir(&mut self, root_dir: PathBuf);
+    fn add_root_dir(&mut self, root_dir: PathBuf);
+    fn get_root_dirs(&self) -> Vec<PathBuf>;
+    fn get_index_file(&self) -> String;
     fn get_root_dir(&self) -> &PathBuf;
-    fn search(&mut self, key: &str);
+    fn search(&mut self, key: &str) -> Result<(), SearchError>;
+    fn search_substring(&mut self, keyword: &str) -> Result<(), SearchError>;
+    fn search_contents(&self, keyword: &str) -> Vec<(PathBuf, usize)>;
+    fn fallback_search(&mut self, key: &str);
+    fn complete(&self, prefix: &str, limit: usize) -> Vec<String>;
     fn get_results(&self) -> &Vec<(PathBuf, String)>;
+    fn iter_results(&self) -> Iter<'_, (PathBuf, String)>;
+    fn take_results(&mut self) -> Vec<(PathBuf, String)>;
+    fn match_ranges(&self, path: &Path) -> &[Range<usize>];
+    fn take_match_ranges(&mut self) -> HashMap<PathBuf, Vec<Range<usize>>>;
+    fn scored_results(&self) -> Vec<ScoredResult>;
+    fn results_to_json(&self) -> String;
+    fn export_results_csv(&self, out: &Path) -> io::Result<()>;
+    fn export_index_json(&self, path: &Path) -> Result<(), SearchError>;
+    fn import_index_json(&mut self, path: &Path) -> Result<(), SearchError>;
     fn reset_search_results(&mut self);
     fn set_search_results_limit(&mut self, limit: usize);
+    fn get_search_results_limit(&self) -> usize;
+    fn results_truncated(&self) -> bool;
+    fn set_rank_results(&mut self, rank: bool);
+    fn set_whole_word(&mut self, whole_word: bool);
+    fn set_search_scope(&mut self, scope: Vec<PathBuf>);
+    fn set_ignore_separators(&mut self, ignore: bool);
+    fn set_search_mode(&mut self, mode: SearchMode);
+    fn set_haystack(&mut self, haystack: Haystack);
+    fn set_ignore_case(&mut self, ignore_case: bool);
+    fn set_content_size_limit(&mut self, bytes: u64);
+    fn set_extension_filter(&mut self, extensions: Vec<String>);
+    fn set_exclude_dirs(&mut self, exclude_dirs: Vec<PathBuf>);
+    fn set_respect_gitignore(&mut self, enabled: bool);
+    fn set_skip_hidden(&mut self, skip: bool);
+    fn set_max_depth(&mut self, max_depth: Option<usize>);
+    fn set_resource_limits(&mut self, limits: ResourceLimits);
+    fn set_blacklist(&mut self, blacklist: Blacklist);
+    fn set_index_created_time(&mut self, enabled: bool);
+    fn set_index_streams(&mut self, enabled: bool);
     fn clear_index_files(&mut self);
 }
 
+impl Search {
+    fn walk(&self) -> Walk<'_> {
+        Walk {
+            blacklist: &self.blacklist,
+            exclude_dirs: &self.exclude_dirs,
+            index_streams: self.index_streams,
+            index_created: self.index_created,
+            respect_gitignore: self.respect_gitignore,
+            skip_hidden: self.skip_hidden,
+            roots: self.get_root_dirs(),
+            max_depth: self.max_depth,
+            dir_times: Mutex::new(HashMap::new()),
+            progress: self.progress.as_ref(),
+            paused: self.paused.as_deref(),
+            stop: self.stop.as_deref(),
+            files_seen: AtomicUsize::new(0),
+            last_progress: Mutex::new(None),
+        }
+    }
+
+    /// A copy for searching on another thread. It shares the index instead of
+    /// copying it and starts without results or the folder times `update_index`
+    /// needs, so it is only meant for searching.
+    pub(crate) fn search_view(&self) -> Search {
+        Search {
+            indexed_files: Arc::clone(&self.indexed_files),
+            dir_times: HashMap::new(),
+            search_results: Vec::new(),
+            match_ranges: HashMap::new(),
+            root_dir: self.root_dir.clone(),
+            extra_roots: self.extra_roots.clone(),
+            search_results_limit: self.search_results_limit,
+            results_truncated: false,
+            search_scope: self.search_scope.clone(),
+            ignore_separators: self.ignore_separators,
+            search_mode: self.search_mode,
+            resource_limits: self.resource_limits,
+            blacklist: self.blacklist.clone(),
+            index_streams: self.index_streams,
+            index_created: self.index_created,
+            haystack: self.haystack,
+            ignore_case: self.ignore_case,
+            content_size_limit: self.content_size_limit,
+            extensions: self.extensions.clone(),
+            exclude_dirs: self.exclude_dirs.clone(),
+            respect_gitignore: self.respect_gitignore,
+            skip_hidden: self.skip_hidden,
+            rank_results: self.rank_results,
+            whole_word: self.whole_word,
+            filters: self.filters.clone(),
+            categories: self.categories.clone(),
+            drive: self.drive.clone(),
+            cancel: self.cancel.clone(),
+            max_depth: self.max_depth,
+            progress: None,
+            paused: None,
+            stop: None,
+        }
+    }
+
+    /// Makes searches stop early, keeping the results found so far, once `token`
+    /// is cancelled. `None` lets every search run to the end.
+    pub(crate) fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        self.cancel = token;
+    }
+
+    /// Makes indexing send an [`IndexProgress`] to `sender` when it enters a
+    /// folder at least `interval` after the previous report, and once more when
+    /// it finishes. `None` stops the reports.
+    pub(crate) fn set_progress_sender(
+        &mut self,
+        sender: Option<Sender<IndexProgress>>,
+        interval: Duration,
+    ) {
+        self.progress = sender.map(|sender| (sender, interval));
+    }
+
+    /// Makes indexing wait before each folder for as long as `paused` is set,
+    /// keeping everything indexed so far. `None` never pauses.
+    pub(crate) fn set_pause_flag(&mut self, paused: Option<Arc<AtomicBool>>) {
+        self.paused = paused;
+    }
+
+    /// Makes indexing give up with [`SearchError::Stopped`] once `stop` is set, even
+    /// while paused. `None` never stops.
+    pub(crate) fn set_stop_flag(&mut self, stop: Option<Arc<AtomicBool>>) {
+        self.stop = stop;
+    }
+
+    /// Makes searches keep only files passing `filters`, with `categories` giving
+    /// the extensions of each category, before the result limit is applied.
+    pub(crate) fn set_query_filters(&mut self, filters: QueryFilters, categories: Categories) {
+        self.filters = filters;
+        self.categories = categories;
+    }
+
+    /// Makes searches keep only files on `drive`, see [`result_drive`]. `None`
+    /// keeps files on every drive.
+    pub(crate) fn set_drive_filter(&mut self, drive: Option<PathBuf>) {
+        self.drive = drive;
+    }
+
+    /// Whether `file` may be a result: in the search scope, with an allowed
+    /// extension, not blacklisted, and passing the query and drive filters.
+    fn accepts(&self, file: &IndexedFile, roots: &[PathBuf]) -> bool {
+        let path = &file.path;
+        in_scope(&self.search_scope, path)
+            && has_extension(&self.extensions, path)
+            && !self.blacklist.blocks(path)
+            && self.filters.matches(file, &self.categories)
+            && self
+                .drive
+                .as_ref()
+                .is_none_or(|drive| result_drive(path, roots).as_ref() == Some(drive))
+    }
+
+    /// The index entries of `results`, in the same order, so that their size and
+    /// modification time come from the index instead of the file system. A result
+    /// that is not indexed, e.g. one removed since, has unknown metadata.
+    pub(crate) fn result_files(&self, results: &[(PathBuf, String)]) -> Vec<IndexedFile> {
+        let positions: HashMap<&Path, usize> = results
+            .iter()
+            .enumerate()
+            .map(|(position, (path, _))| (path.as_path(), position))
+            .collect();
+        let mut files: Vec<IndexedFile> = results
+            .iter()
+            .map(|(path, _)| IndexedFile::from(path.clone()))
+            .collect();
+        for file in self.indexed_files.iter() {
+            if let Some(&position) = positions.get(file.path.as_path()) {
+                files[position] = file.clone();
+            }
+        }
+        files
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// The index for modifying, copied first if a `search_view` still shares it.
+    fn index_mut(&mut self) -> &mut Vec<IndexedFile> {
+        Arc::make_mut(&mut self.indexed_files)
+    }
+
+    /// Searches the index for `query`, whose terms are matched like a plain key
+    /// in the current search mode. See [`Query`] for how terms combine.
+    pub(crate) fn search_query(&mut self, query: &Query) -> Result<(), SearchError> {
+        if self.ignore_separators {
+            let query = query.try_map(&mut |key: &String| {
+                Ok::<_, regex::Error>(compact(key).into_iter().map(|(c, _)| c).collect())
+            })?;
+            let mut matches = Matches::new(self.remaining_results(), self.rank_results);
+            let roots = self.get_root_dirs();
+            for entry in self.indexed_files.iter() {
+                if self.is_cancelled() {
+                    break;
+                }
+                if !self.accepts(entry, &roots) {
+                    continue;
+                }
+                let file = &entry.path;
+                let Some(text) = self.haystack.text(file) else {
+                    continue;
+                };
+                if let Some(matched) = query.find(&mut |key: &Vec<char>| compact_match(text, key)) {
+                    let ranges = || {
+                        let ranges = query
+                            .terms()
+                            .into_iter()
+                            .flat_map(|key| compact_ranges(text, key));
+                        name_ranges(file, text, ranges)
+                    };
+                    if !matches.push(file, matched, ranges) {
+                        break;
+                    }
+                }
+            }
+            self.add_matches(matches);
+            return Ok(());
+        }
+        let query =
+            query.try_map(&mut |key: &String| self.search_mode.pattern(key, self.ignore_case))?;
+        let haystack = self.search_mode.haystack(self.haystack);
+        let mut matches = Matches::new(self.remaining_results(), self.rank_results);
+        let roots = self.get_root_dirs();
+        for entry in self.indexed_files.iter() {
+            if self.is_cancelled() {
+                break;
+            }
+            if !self.accepts(entry, &roots) {
+                continue;
+            }
+            let file = &entry.path;
+            let Some(text) = haystack.text(file) else {
+                continue;
+            };
+            let found = query.find(&mut |regex: &Regex| {
+                let found = if self.whole_word {
+                    find_whole_word(regex, text)
+                } else {
+                    regex.find(text)
+                };
+                found.map(|re| re.as_str())
+            });
+            if let Some(matched) = found {
+                let ranges = || {
+                    let ranges = query
+                        .terms()
+                        .into_iter()
+                        .flat_map(|regex| regex_ranges(regex, text, self.whole_word));
+                    name_ranges(file, text, ranges)
+                };
+                if !matches.push(file, matched, ranges) {
+                    break;
+                }
+            }
+        }
+        self.add_matches(matches);
+        Ok(())
+    }
+
+    /// How many more results fit under `search_results_limit` next to those
+    /// already found.
+    fn remaining_results(&self) -> usize {
+        self.search_results_limit
+            .saturating_sub(self.search_results.len())
+    }
+
+    /// Adds the matches of one scan to the results, then ranks them all if
+    /// ranking is on.
+    fn add_matches(&mut self, matches: Matches) {
+        self.results_truncated |= matches.truncated;
+        for Reverse(found) in matches.found.into_sorted_vec() {
+            self.match_ranges.insert(found.path.clone(), found.ranges);
+            self.search_results.push((found.path, found.matched));
+        }
+        self.rank_results();
+    }
+
+    /// Sorts the results best match first if ranking is on. The sort is stable, so
+    /// equally good matches keep their index order.
+    fn rank_results(&mut self) {
+        if self.rank_results {
+            self.search_results
+                .sort_by_cached_key(|(path, matched)| Reverse(score(path, matched)));
+        }
+    }
+}
+
 impl SearchEngine for Search {
-    fn generate_index(&mut self) {
+    fn generate_index(&mut self) -> Result<(), SearchError> {
         // clear before new index added to indexed_files
-        self.indexed_files.clear();
+        self.indexed_files = Arc::default();
+        self.dir_times.clear();
 
-        fn traverse_index(current_path: &PathBuf, indexed: &mut Vec<PathBuf>) {
-            if current_path.metadata().is_err() {
-                return;
+        let min_free_memory = self.resource_limits.min_free_memory;
+        let check = move |indexed: &[IndexedFile]| {
+            if indexed.len().is_multiple_of(MEMORY_CHECK_INTERVAL) {
+                check_memory(available_memory(), min_free_memory)
+                    .map_err(SearchError::ResourceLimit)?;
             }
-
-            if let Ok(entries) = read_dir(current_path) {
-                for entry in entries.flatten() {
-                    {
-                        if entry.path().is_dir() {
-                            traverse_index(&entry.path(), indexed);
-                        } else if entry.path().is_file() {
-                            indexed.push(entry.path());
-                        }
+            Ok(())
+        };
+        let walk = self.walk();
+        let visited = Mutex::new(HashSet::new());
+        let mut indexed = Vec::new();
+        let result = check_memory(available_memory(), min_free_memory)
+            .map_err(SearchError::ResourceLimit)
+            .and_then(|_| {
+                for root in outermost_roots(&self.get_root_dirs()) {
+                    let mut gitignores = Vec::new();
+                    if walk.enter(&root, &mut gitignores, &visited).is_none() {
+                        continue;
+                    }
+                    let subdirs =
+                        walk.index_entries(&root, &mut indexed, &gitignores, &mut { check })?;
+                    // Each top-level folder is traversed on its own thread; collecting keeps
+                    // their order, so the result equals a sequential traversal.
+                    let parts: Vec<Result<Vec<IndexedFile>, SearchError>> = subdirs
+                        .par_iter()
+                        .map(|subdir| {
+                            let mut local = Vec::new();
+                            walk.traverse(
+                                subdir,
+                                &mut local,
+                                &mut gitignores.clone(),
+                                &visited,
+                                &mut { check },
+                            )?;
+                            Ok(local)
+                        })
+                        .collect();
+                    for part in parts {
+                        indexed.extend(part?);
                     }
                 }
+                Ok(())
+            });
+        walk.report_progress(self.get_root_dir(), true);
+        let dir_times = walk.into_dir_times();
+        if result.is_ok() {
+            self.indexed_files = Arc::new(indexed);
+            self.dir_times = dir_times;
+        }
+        result
+    }
+
+    fn generate_index_with_progress(
+        &mut self,
+        first_snapshot: usize,
+        on_partial: &mut dyn FnMut(&[IndexedFile]),
+    ) -> Result<(), SearchError> {
+        // clear before new index added to indexed_files
+        self.indexed_files = Arc::default();
+        self.dir_times.clear();
+
+        let min_free_memory = self.resource_limits.min_free_memory;
+        let mut next_snapshot = first_snapshot.max(1);
+        let mut after_push = |indexed: &[IndexedFile]| {
+            if indexed.len().is_multiple_of(MEMORY_CHECK_INTERVAL) {
+                check_memory(available_memory(), min_free_memory)
+                    .map_err(SearchError::ResourceLimit)?;
             }
+            if indexed.len() >= next_snapshot {
+                on_partial(indexed);
+                next_snapshot = next_snapshot.saturating_mul(2);
+            }
+            Ok(())
+        };
+        let walk = self.walk();
+        let visited = Mutex::new(HashSet::new());
+        let mut indexed = Vec::new();
+        let result = check_memory(available_memory(), min_free_memory)
+            .map_err(SearchError::ResourceLimit)
+            .and_then(|_| {
+                for root in outermost_roots(&self.get_root_dirs()) {
+                    walk.traverse(
+                        &root,
+                        &mut indexed,
+                        &mut Vec::new(),
+                        &visited,
+                        &mut after_push,
+                    )?;
+                }
+                Ok(())
+            });
+        walk.report_progress(self.get_root_dir(), true);
+        let dir_times = walk.into_dir_times();
+        if result.is_ok() {
+            self.indexed_files = Arc::new(indexed);
+            self.dir_times = dir_times;
+        }
+        result
+    }
+
+    fn update_index(&mut self) -> Result<bool, SearchError> {
+        if self.dir_times.is_empty() {
+            return self.generate_index().map(|_| true);
         }
+        let mut indexed = std::mem::take(self.index_mut());
+        let mut dir_times = std::mem::take(&mut self.dir_times);
+        let mut changed: Vec<PathBuf> = dir_times
+            .iter()
+            .filter(|(dir, time)| modified_time(dir).as_ref() != Some(*time))
+            .map(|(dir, _)| dir.clone())
+            .collect();
+        changed.sort();
+        let any_changed = !changed.is_empty();
 
-        traverse_index(&self.root_dir, &mut self.indexed_files);
+        let roots = self.get_root_dirs();
+        let walk = self.walk();
+        let visited = Mutex::new(HashSet::new());
+        let mut no_check = |_: &[IndexedFile]| Ok(());
+        let mut result = Ok(());
+        for dir in changed {
+            if !dir_times.contains_key(&dir) {
+                // Removed together with a changed folder above it.
+                continue;
+            }
+            if !dir.is_dir() {
+                indexed.retain(|file| !file.path.starts_with(&dir));
+                dir_times.retain(|known, _| !known.starts_with(&dir));
+                continue;
+            }
+            indexed.retain(|file| file.path.parent() != Some(dir.as_path()));
+            let gitignores = walk.gitignores_for(&roots, &dir);
+            result = walk
+                .index_entries(&dir, &mut indexed, &gitignores, &mut no_check)
+                .and_then(|subdirs| {
+                    let gone: Vec<PathBuf> = dir_times
+                        .keys()
+                        .filter(|known| {
+                            known.parent() == Some(dir.as_path()) && !subdirs.contains(known)
+                        })
+                        .cloned()
+                        .collect();
+                    for subdir in gone {
+                        indexed.retain(|file| !file.path.starts_with(&subdir));
+                        dir_times.retain(|known, _| !known.starts_with(&subdir));
+                    }
+                    for subdir in subdirs {
+                        if !dir_times.contains_key(&subdir) {
+                            walk.traverse(
+                                &subdir,
+                                &mut indexed,
+                                &mut gitignores.clone(),
+                                &visited,
+                                &mut no_check,
+                            )?;
+                        }
+                    }
+                    Ok(())
+                });
+            if result.is_err() {
+                break;
+            }
+            if let Some(time) = modified_time(&dir) {
+                dir_times.insert(dir, time);
+            }
+        }
+        dir_times.extend(walk.into_dir_times());
+        self.indexed_files = Arc::new(indexed);
+        self.dir_times = dir_times;
+        result.map(|_| any_changed)
     }
 
     fn new() -> Self {
         Search {
-            indexed_files: Vec::new(),
+            indexed_files: Arc::default(),
+            dir_times: HashMap::new(),
             root_dir: PathBuf::from("C:\\"),
+            extra_roots: Vec::new(),
             search_results: Vec::new(),
+            match_ranges: HashMap::new(),
             search_results_limit: 200,
+            results_truncated: false,
+            search_scope: Vec::new(),
+            ignore_separators: false,
+            search_mode: SearchMode::default(),
+            resource_limits: ResourceLimits::default(),
+            blacklist: Blacklist::default(),
+            index_streams: false,
+            index_created: false,
+            haystack: Haystack::default(),
+            ignore_case: true,
+            content_size_limit: DEFAULT_CONTENT_SIZE_LIMIT,
+            extensions: Vec::new(),
+            exclude_dirs: Vec::new(),
+            respect_gitignore: false,
+            skip_hidden: true,
+            rank_results: true,
+            whole_word: false,
+            filters: QueryFilters::default(),
+            categories: Categories::default(),
+            drive: None,
+            cancel: None,
+            max_depth: None,
+            progress: None,
+            paused: None,
+            stop: None,
         }
     }
 
-    fn save_index(&self) {
+    fn save_index(&self) -> Result<(), SearchError> {
         if self.indexed_files.is_empty() {
-            return;
+            return Ok(());
         }
-        let file = File::create(format!(
-            "index {}",
-            self.root_dir
-                .to_str()
-                .unwrap_or_default()
-                .replace("\\", "")
-                .replace(":", "")
-        ))
-        .expect("Fail to create file");
-
-        let writer = BufWriter::new(file);
-        if let Err(e) = bincode::serialize_into(writer, &self.indexed_files) {
-            eprintln!("Failed to serialize index: {}", e);
-        }
-    }
-
-    fn load_index(&mut self) {
-        let file = match File::open(format!(
-            "index {}",
-            self.root_dir
-                .to_str()
-                .unwrap_or_default()
-                .replace("\\", "")
-                .replace(":", "")
-        )) {
+        let file_name = self.get_index_file();
+        let directory = Path::new(&file_name)
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        check_disk_space(
+            available_disk_space(directory),
+            estimated_index_size(&self.indexed_files),
+            self.resource_limits.min_free_disk,
+        )
+        .map_err(SearchError::ResourceLimit)?;
+        write_atomically(Path::new(&file_name), |writer| {
+            writer.write_all(INDEX_MAGIC)?;
+            writer.write_all(&INDEX_VERSION.to_le_bytes())?;
+            let mut encoder = GzEncoder::new(writer, Compression::fast());
+            bincode::serialize_into(&mut encoder, &*self.indexed_files)
+                .map_err(io::Error::other)?;
+            encoder.finish()?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    fn load_index(&mut self) -> Result<(), SearchError> {
+        self.dir_times.clear();
+        let file = match File::open(self.get_index_file()) {
             Ok(x) => x,
             Err(_) => {
-                self.indexed_files = Vec::new();
-                return;
+                self.indexed_files = Arc::default();
+                return Ok(());
             }
         };
-        let reader = BufReader::new(file);
-        self.indexed_files = bincode::deserialize_from(reader).unwrap_or_default();
+        let file_len = file.metadata().map_or(0, |metadata| metadata.len());
+        match read_index(BufReader::new(file), file_len) {
+            Ok(files) => {
+                self.indexed_files = Arc::new(files);
+                Ok(())
+            }
+            Err(e) => {
+                self.indexed_files = Arc::default();
+                Err(SearchError::InvalidIndex(e))
+            }
+        }
     }
 
-    fn get_index(&self) -> &Vec<PathBuf> {
+    fn get_index(&self) -> &Vec<IndexedFile> {
         &self.indexed_files
     }
 
+    fn replace_index(&mut self, files: Vec<IndexedFile>) {
+        self.indexed_files = Arc::new(files);
+        self.dir_times.clear();
+    }
+
+    fn take_index(&mut self) -> Vec<IndexedFile> {
+        self.dir_times.clear();
+        std::mem::take(self.index_mut())
+    }
+
+    fn merge_index(&mut self, files: Vec<IndexedFile>) {
+        let index = self.index_mut();
+        let mut positions: HashMap<PathBuf, usize> = index
+            .iter()
+            .enumerate()
+            .map(|(position, file)| (file.path.clone(), position))
+            .collect();
+        for file in files {
+            match positions.get(&file.path) {
+                Some(&position) => index[position] = file,
+                None => {
+                    positions.insert(file.path.clone(), index.len());
+                    index.push(file);
+                }
+            }
+        }
+    }
+
+    fn insert_path(&mut self, path: PathBuf) {
+        if !self.indexed_files.iter().any(|file| file.path == path) {
+            let file = IndexedFile::read_with(path, self.index_created);
+            self.index_mut().push(file);
+        }
+    }
+
+    fn remove_path(&mut self, path: &Path) -> bool {
+        self.search_results.retain(|(file, _)| file != path);
+        self.match_ranges.remove(path);
+        if !self.indexed_files.iter().any(|file| file.path == path) {
+            return false;
+        }
+        self.index_mut().retain(|file| file.path != path);
+        true
+    }
+
     fn set_root_dir(&mut self, root_dir: PathBuf) {
-        self.root_dir = root_dir;
+        self.root_dir = normalize_root(root_dir);
+        self.extra_roots.clear();
+    }
+
+    fn add_root_dir(&mut self, root_dir: PathBuf) {
+        let root_dir = normalize_root(root_dir);
+        if root_dir != self.root_dir && !self.extra_roots.contains(&root_dir) {
+            self.extra_roots.push(root_dir);
+        }
+    }
+
+    fn get_root_dirs(&self) -> Vec<PathBuf> {
+        let mut roots = vec![self.root_dir.clone()];
+        roots.extend(self.extra_roots.iter().cloned());
+        roots
+    }
+
+    fn get_index_file(&self) -> String {
+        if self.extra_roots.is_empty() {
+            return index_file_name(&self.root_dir);
+        }
+        let names: Vec<String> = self
+            .get_root_dirs()
+            .iter()
+            .map(|root| index_file_name(root)["index ".len()..].to_string())
+            .collect();
+        format!("index {}", names.join(" + "))
     }
 
     fn get_root_dir(&self) -> &PathBuf {
         &self.root_dir
     }
 
-    fn search(&mut self, key: &str) {
-        let regex = Regex::new(key).unwrap_or(Regex::new("None").unwrap());
-        let mut searched = 0usize;
-        for file in self.indexed_files.iter() {
-            if searched >= self.search_results_limit {
+    fn search(&mut self, key: &str) -> Result<(), SearchError> {
+        self.search_query(&Query::parse(key))
+    }
+
+    fn search_substring(&mut self, keyword: &str) -> Result<(), SearchError> {
+        let mode = std::mem::replace(&mut self.search_mode, SearchMode::Contains);
+        let result = self.search(keyword);
+        self.search_mode = mode;
+        result
+    }
+
+    fn search_contents(&self, keyword: &str) -> Vec<(PathBuf, usize)> {
+        let mut matches = Vec::new();
+        if keyword.is_empty() {
+            return matches;
+        }
+        let Ok(regex) = SearchMode::Contains.pattern(keyword, self.ignore_case) else {
+            return matches;
+        };
+        let roots = self.get_root_dirs();
+        for entry in self.indexed_files.iter() {
+            if matches.len() >= self.search_results_limit || self.is_cancelled() {
+                break;
+            }
+            if !self.accepts(entry, &roots) {
+                continue;
+            }
+            let file = &entry.path;
+            let small_enough = file.metadata().is_ok_and(|metadata| {
+                metadata.is_file() && metadata.len() <= self.content_size_limit
+            });
+            if !small_enough {
+                continue;
+            }
+            // Binary and otherwise non UTF-8 files fail to read and are skipped.
+            let Ok(contents) = read_to_string(file) else {
+                continue;
+            };
+            for (number, line) in contents.lines().enumerate() {
+                if regex.is_match(line) {
+                    matches.push((file.clone(), number + 1));
+                }
+            }
+        }
+        matches
+    }
+
+    fn fallback_search(&mut self, key: &str) {
+        if key.is_empty() {
+            return;
+        }
+        let Ok(regex) = RegexBuilder::new(&regex::escape(key))
+            .case_insensitive(true)
+            .build()
+        else {
+            return;
+        };
+        let mut matches = Matches::new(self.remaining_results(), self.rank_results);
+        let roots = self.get_root_dirs();
+        for entry in self.indexed_files.iter().take(FALLBACK_SCAN_LIMIT) {
+            if self.is_cancelled() {
                 break;
             }
-            let file_name = file.file_name().unwrap().to_str().unwrap();
-            if regex.is_match(file.file_name().unwrap().to_str().unwrap()) {
-                if let Some(re) = regex.find(file_name) {
-                    self.search_results
-                        .push((file.clone(), re.as_str().to_string()));
-                    searched += 1;
+            if !self.accepts(entry, &roots) {
+                continue;
+            }
+            let file = &entry.path;
+            let Some(text) = self.haystack.text(file) else {
+                continue;
+            };
+            if let Some(re) = regex.find(text) {
+                let ranges = || name_ranges(file, text, regex_ranges(&regex, text, false));
+                if !matches.push(file, re.as_str(), ranges) {
+                    break;
                 }
             }
         }
+        self.add_matches(matches);
+    }
+
+    fn complete(&self, prefix: &str, limit: usize) -> Vec<String> {
+        if prefix.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+        let folded = prefix.to_lowercase();
+        let starts_with_prefix = |name: &str| {
+            if !self.ignore_case {
+                return name.starts_with(prefix);
+            }
+            // Lowercasing can change the length, so compare as many characters.
+            let head: String = name.chars().take(prefix.chars().count()).collect();
+            head.to_lowercase() == folded
+        };
+        // The `limit` first names so far, sorted by their lowercase form.
+        let mut names = BTreeSet::new();
+        for file in self.indexed_files.iter() {
+            if self.is_cancelled() {
+                break;
+            }
+            if is_stream_entry(&file.path) || self.blacklist.blocks(&file.path) {
+                continue;
+            }
+            let Some(name) = file.path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if !starts_with_prefix(name) {
+                continue;
+            }
+            let key = (name.to_lowercase(), name);
+            if names.len() == limit && names.last().is_some_and(|last| key >= *last) {
+                continue;
+            }
+            names.insert(key);
+            if names.len() > limit {
+                names.pop_last();
+            }
+        }
+        names
+            .into_iter()
+            .map(|(_, name)| name.to_string())
+            .collect()
     }
+
     fn get_results(&self) -> &Vec<(PathBuf, String)> {
         &self.search_results
     }
 
-    fn reset_search_results(&mut self) {
-        self.search_results.clear();
+    fn iter_results(&self) -> Iter<'_, (PathBuf, String)> {
+        self.search_results.iter()
     }
 
-    fn set_search_results_limit(&mut self, limit: usize) {
-        self.search_results_limit = limit;
+    fn take_results(&mut self) -> Vec<(PathBuf, String)> {
+        std::mem::take(&mut self.search_results)
     }
 
-    fn clear_index_files(&mut self) {
-        self.indexed_files = Vec::new()
+    fn match_ranges(&self, path: &Path) -> &[Range<usize>] {
+        self.match_ranges.get(path).map_or(&[], Vec::as_slice)
     }
 
-    fn len(&self) -> usize {
-        self.indexed_files.len()
+    fn take_match_ranges(&mut self) -> HashMap<PathBuf, Vec<Range<usize>>> {
+        std::mem::take(&mut self.match_ranges)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn scored_results(&self) -> Vec<ScoredResult> {
+        self.search_results
+            .iter()
+            .map(|(path, matched)| ScoredResult {
+                path: path.clone(),
+                score: score(path, matched),
+            })
+            .collect()
+    }
 
-    #[test]
-    fn test_new() {
-        let search = Search::new();
-        assert!(search.indexed_files.is_empty());
-        assert_eq!(search.root_dir, PathBuf::from("C:\\"));
+    fn results_to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .result_files(&self.search_results)
+            .iter()
+            .map(|file| {
+                let path = &file.path;
+                let name = path.file_name().unwrap_or(path.as_os_str());
+                let mut entry = format!(
+                    "{{\"path\":{},\"name\":{}",
+                    json_os_string(path.as_os_str()),
+                    json_os_string(name)
+                );
+                if let Some(modified) = file.modified_time() {
+                    let modified = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+                    entry.push_str(&format!(
+                        ",\"size\":{},\"modified\":{}",
+                        file.size,
+                        modified.as_secs()
+                    ));
+                }
+                entry.push('}');
+                entry
+            })
+            .collect();
+        format!("[{}]", entries.join(","))
     }
 
-    #[test]
-    fn test_set_root_dir() {
-        let mut search = Search::new();
-        let new_root = PathBuf::from("D:\\");
-        search.set_root_dir(new_root.clone());
-        assert_eq!(search.root_dir, new_root);
+    fn export_results_csv(&self, out: &Path) -> io::Result<()> {
+        write_results_csv(&self.result_files(&self.search_results), out)
     }
 
-    #[test]
-    fn test_generate_index() {
-        let mut search = Search::new();
+    fn export_index_json(&self, path: &Path) -> Result<(), SearchError> {
+        if self.indexed_files.is_empty() {
+            return Err(SearchError::IndexNotLoaded);
+        }
+        std::fs::write(path, index_to_json(&self.indexed_files))?;
+        Ok(())
+    }
+
+    fn import_index_json(&mut self, path: &Path) -> Result<(), SearchError> {
+        let text = read_to_string(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => SearchError::NotFound(path.to_path_buf()),
+            _ => SearchError::Io(e),
+        })?;
+        let files = index_from_json(&text).map_err(SearchError::InvalidIndex)?;
+        self.replace_index(files);
+        Ok(())
+    }
+
+    fn reset_search_results(&mut self) {
+        self.search_results.clear();
+        self.match_ranges.clear();
+        self.results_truncated = false;
+    }
+
+    fn set_search_results_limit(&mut self, limit: usize) {
+        self.search_results_limit = limit;
+    }
+
+    fn get_search_results_limit(&self) -> usize {
+        self.search_results_limit
+    }
+
+    fn results_truncated(&self) -> bool {
+        self.results_truncated
+    }
+
+    fn set_rank_results(&mut self, rank: bool) {
+        self.rank_results = rank;
+    }
+
+    fn set_whole_word(&mut self, whole_word: bool) {
+        self.whole_word = whole_word;
+    }
+
+    fn set_search_scope(&mut self, scope: Vec<PathBuf>) {
+        self.search_scope = scope;
+    }
+
+    fn set_ignore_separators(&mut self, ignore: bool) {
+        self.ignore_separators = ignore;
+    }
+
+    fn set_search_mode(&mut self, mode: SearchMode) {
+        self.search_mode = mode;
+    }
+
+    fn set_haystack(&mut self, haystack: Haystack) {
+        self.haystack = haystack;
+    }
+
+    fn set_ignore_case(&mut self, ignore_case: bool) {
+        self.ignore_case = ignore_case;
+    }
+
+    fn set_content_size_limit(&mut self, bytes: u64) {
+        self.content_size_limit = bytes;
+    }
+
+    fn set_extension_filter(&mut self, extensions: Vec<String>) {
+        self.extensions = extensions
+            .iter()
+            .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+            .filter(|extension| !extension.is_empty())
+            .collect();
+    }
+
+    fn set_exclude_dirs(&mut self, exclude_dirs: Vec<PathBuf>) {
+        self.exclude_dirs = exclude_dirs;
+    }
+
+    fn set_respect_gitignore(&mut self, enabled: bool) {
+        self.respect_gitignore = enabled;
+    }
+
+    fn set_skip_hidden(&mut self, skip: bool) {
+        self.skip_hidden = skip;
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    fn set_resource_limits(&mut self, limits: ResourceLimits) {
+        self.resource_limits = limits;
+    }
+
+    fn set_blacklist(&mut self, blacklist: Blacklist) {
+        self.blacklist = blacklist;
+    }
+
+    fn set_index_created_time(&mut self, enabled: bool) {
+        self.index_created = enabled;
+    }
+
+    fn set_index_streams(&mut self, enabled: bool) {
+        self.index_streams = enabled;
+    }
+
+    fn clear_index_files(&mut self) {
+        self.indexed_files = Arc::default();
+        self.dir_times.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.indexed_files.len()
+    }
+
+    fn count_files(&self) -> usize {
+        self.indexed_files
+            .iter()
+            .filter(|file| !is_stream_entry(&file.path))
+            .count()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.indexed_files.is_empty()
+    }
+}
+
+/// Collapses repeated separators and drops trailing ones so equivalent spellings of a
+/// directory map to the same path.
+///
+/// On Windows, and elsewhere for paths starting with a drive letter or a UNC
+/// prefix (`\\server`), paths use `\` throughout and both slashes separate;
+/// anything else uses `/`, and a backslash is part of a name as Unix allows. A
+/// UNC prefix keeps its two leading separators, a bare drive (`C:`) becomes its
+/// root (`C:\`), and roots keep their final separator.
+pub(crate) fn normalize_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let has_drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    let windows = cfg!(windows) || has_drive || path.starts_with("\\\\");
+    let separator = if windows { '\\' } else { '/' };
+    let is_separator = |c: char| c == '/' || (windows && c == '\\');
+
+    let mut normalized = String::with_capacity(path.len());
+    if windows && path.starts_with(['\\', '/']) && path[1..].starts_with(['\\', '/']) {
+        normalized.push_str("\\\\");
+    }
+    for part in path.split(is_separator).filter(|part| !part.is_empty()) {
+        if !normalized.is_empty() && !normalized.ends_with(separator) {
+            normalized.push(separator);
+        }
+        if normalized.is_empty() && !has_drive && path.starts_with(is_separator) {
+            normalized.push(separator);
+        }
+        normalized.push_str(part);
+    }
+    if normalized.is_empty() && path.starts_with(is_separator) {
+        normalized.push(separator);
+    }
+    if has_drive && normalized.len() == 2 {
+        normalized.push(separator);
+    }
+    normalized
+}
+
+/// A search result with its relevance; a higher `score` is a better match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScoredResult {
+    pub path: PathBuf,
+    pub score: u32,
+}
+
+/// Rates how well `matched`, the text a search found in `path`, matches the file.
+///
+/// A match of the whole file name, with or without its extension, ranks highest,
+/// then a match at the start of the name, then one elsewhere in the name, then one
+/// only in the folders. Within each of these an earlier match and then a shorter
+/// name rank higher, so `main` puts `main.rs` before `domain_rules.rs`.
+pub(crate) fn score(path: &Path, matched: &str) -> u32 {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let matched = matched.to_lowercase();
+    let position = name
+        .find(&matched)
+        .map(|start| name[..start].chars().count());
+    let tier = match position {
+        _ if matched.is_empty() => 0,
+        _ if name == matched || stem == matched => 3,
+        Some(0) => 2,
+        Some(_) => 1,
+        None => 0,
+    };
+    let position = position.unwrap_or(999).min(999) as u32;
+    let length = name.chars().count().min(999) as u32;
+    tier * 1_000_000 + (999 - position) * 1_000 + (999 - length)
+}
+
+/// A match found while scanning the index, ordered by its `score` and then by
+/// the order it was found in, earlier first.
+struct Candidate {
+    score: u32,
+    order: usize,
+    path: PathBuf,
+    matched: String,
+    ranges: Vec<Range<usize>>,
+}
+
+impl Candidate {
+    fn key(&self) -> (u32, Reverse<usize>) {
+        (self.score, Reverse(self.order))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// The matches of one scan over the index, at most `limit` of them.
+///
+/// Without ranking these are the first matches, and the scan can stop at the
+/// next one. With ranking every match is weighed and the `limit` best by
+/// [`score`] are kept, so a good match late in the index is not cut off.
+struct Matches {
+    limit: usize,
+    rank: bool,
+    /// The kept matches, the worst on top.
+    found: BinaryHeap<Reverse<Candidate>>,
+    seen: usize,
+    truncated: bool,
+}
+
+impl Matches {
+    fn new(limit: usize, rank: bool) -> Self {
+        Matches {
+            limit,
+            rank,
+            found: BinaryHeap::new(),
+            seen: 0,
+            truncated: false,
+        }
+    }
+
+    /// Offers a match of `path`, computing its highlight `ranges` only if it is
+    /// kept. Returns whether the scan should go on.
+    fn push(
+        &mut self,
+        path: &Path,
+        matched: &str,
+        ranges: impl FnOnce() -> Vec<Range<usize>>,
+    ) -> bool {
+        let order = self.seen;
+        self.seen += 1;
+        if self.found.len() >= self.limit {
+            self.truncated = true;
+            if !self.rank {
+                return false;
+            }
+        }
+        let score = if self.rank { score(path, matched) } else { 0 };
+        if self.found.len() >= self.limit {
+            let better = self
+                .found
+                .peek()
+                .is_some_and(|Reverse(worst)| (score, Reverse(order)) > worst.key());
+            if !better {
+                return true;
+            }
+            self.found.pop();
+        }
+        self.found.push(Reverse(Candidate {
+            score,
+            order,
+            path: path.to_path_buf(),
+            matched: matched.to_string(),
+            ranges: ranges(),
+        }));
+        true
+    }
+}
+
+/// How a search key is interpreted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// The key is always a regular expression.
+    #[default]
+    Regex,
+    /// The key is a regular expression only if `looks_like_regex` says so and it
+    /// compiles; otherwise it is matched literally.
+    Auto,
+    /// The key is matched literally at the start of the text only.
+    Prefix,
+    /// The key is matched literally anywhere in the text.
+    Contains,
+    /// The key is a glob matched against the whole file name, whatever the
+    /// [`Haystack`]: `*` matches any run of characters and `?` a single one.
+    Glob,
+}
+
+/// Characters that make [`SearchMode::Auto`] treat a key as a regular expression.
+///
+/// `.` is deliberately absent because it is far more common in file names than as a
+/// wildcard, so `main.rs` stays a literal search.
+pub(crate) const REGEX_TRIGGERS: &[char] = &[
+    '^', '$', '*', '+', '?', '(', ')', '[', ']', '{', '}', '|', '\\',
+];
+
+/// Returns whether `key` contains any of the [`REGEX_TRIGGERS`].
+pub(crate) fn looks_like_regex(key: &str) -> bool {
+    key.contains(REGEX_TRIGGERS)
+}
+
+impl SearchMode {
+    /// Compiles `key` for this mode. `ignore_case` uses Unicode case folding, so
+    /// `ärger` also matches `ÄRGER`.
+    pub(crate) fn pattern(self, key: &str, ignore_case: bool) -> Result<Regex, regex::Error> {
+        let build = |key: &str| RegexBuilder::new(key).case_insensitive(ignore_case).build();
+        match self {
+            SearchMode::Regex => build(key),
+            SearchMode::Auto => match build(key) {
+                Ok(regex) if looks_like_regex(key) => Ok(regex),
+                _ => build(&regex::escape(key)),
+            },
+            SearchMode::Prefix => build(&format!("^{}", regex::escape(key))),
+            SearchMode::Contains => build(&regex::escape(key)),
+            SearchMode::Glob => build(&glob_to_regex(key)),
+        }
+    }
+
+    /// The text `search` matches in this mode when set to match `haystack`.
+    pub(crate) fn haystack(self, haystack: Haystack) -> Haystack {
+        match self {
+            SearchMode::Glob => Haystack::NameWithExtension,
+            _ => haystack,
+        }
+    }
+}
+
+/// The first match of `regex` in `text` that is a whole word.
+///
+/// A word is a run of letters and digits (`char::is_alphanumeric`), so a match
+/// must not have one directly before or after it. Everything else, including `_`,
+/// `-`, `.` and spaces, separates words: `test` is a whole word in `test_data.csv`
+/// and `my-test.txt` but not in `testing.txt` or `test2.txt`. This differs from the
+/// regex `\b`, which counts `_` as part of a word.
+pub(crate) fn find_whole_word<'t>(regex: &Regex, text: &'t str) -> Option<regex::Match<'t>> {
+    find_whole_word_at(regex, text, 0)
+}
+
+/// Like [`find_whole_word`], but only finds matches starting at `start` or later.
+fn find_whole_word_at<'t>(
+    regex: &Regex,
+    text: &'t str,
+    mut start: usize,
+) -> Option<regex::Match<'t>> {
+    let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    while start <= text.len() {
+        let found = regex.find_at(text, start)?;
+        if !is_word(text[..found.start()].chars().next_back())
+            && !is_word(text[found.end()..].chars().next())
+        {
+            return Some(found);
+        }
+        // Retry from the next character, since a later start may be delimited.
+        start = found.start()
+            + text[found.start()..]
+                .chars()
+                .next()
+                .map_or(1, char::len_utf8);
+    }
+    None
+}
+
+/// The byte ranges of every non-empty match of `regex` in `text`, or only of the
+/// whole word matches.
+fn regex_ranges(regex: &Regex, text: &str, whole_word: bool) -> Vec<Range<usize>> {
+    if !whole_word {
+        return regex
+            .find_iter(text)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect();
+    }
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while let Some(found) = find_whole_word_at(regex, text, start) {
+        if found.is_empty() {
+            start = found.end() + text[found.end()..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+        ranges.push(found.range());
+        start = found.end();
+    }
+    ranges
+}
+
+/// Turns `ranges` within `text`, the haystack of `path`, into ranges within the
+/// file name of `path`: sorted, merged where they overlap, and without the parts
+/// that lie in its folders. `text` is either a suffix of the path ending with
+/// the file name, or the start of the file name.
+fn name_ranges(
+    path: &Path,
+    text: &str,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+) -> Vec<Range<usize>> {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    let offset = if text.ends_with(name) {
+        text.len() - name.len()
+    } else {
+        0
+    };
+    let mut ranges: Vec<Range<usize>> = ranges
+        .into_iter()
+        .filter(|range| range.end > offset)
+        .map(|range| range.start.max(offset) - offset..(range.end - offset).min(name.len()))
+        .filter(|range| !range.is_empty())
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Translates a glob into an anchored regular expression.
+fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
+
+/// Which text of an indexed path the search key is matched against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Haystack {
+    /// The file name without its extension: `report` for `C:\docs\report.txt`.
+    Name,
+    /// The file name with its extension: `report.txt`.
+    #[default]
+    NameWithExtension,
+    /// The whole path: `C:\docs\report.txt`.
+    FullPath,
+}
+
+impl Haystack {
+    /// The text of `path` to match, or `None` when it has none or is not valid UTF-8.
+    pub(crate) fn text(self, path: &Path) -> Option<&str> {
+        match self {
+            Haystack::Name => path.file_stem(),
+            Haystack::NameWithExtension => path.file_name(),
+            Haystack::FullPath => Some(path.as_os_str()),
+        }
+        .and_then(|text| text.to_str())
+    }
+}
+
+/// Whether freshly built indexes are written to disk or kept in memory by the UI.
+///
+/// In memory mode the background threads hand each new index to the UI instead of
+/// saving it, and the UI snapshots its index to disk on exit and at a long interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum IndexMode {
+    #[default]
+    Disk,
+    Memory,
+}
+
+impl IndexMode {
+    /// Parses `memory` or `disk`, in any case.
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("memory") {
+            Some(IndexMode::Memory)
+        } else if name.eq_ignore_ascii_case("disk") {
+            Some(IndexMode::Disk)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            IndexMode::Disk => "disk",
+            IndexMode::Memory => "memory",
+        }
+    }
+}
+
+/// One step of `Walk::traverse`: index a folder, or leave one whose contents are
+/// all indexed, dropping the `.gitignore` entering it pushed.
+enum Step {
+    Enter(PathBuf),
+    Leave { pushed: bool },
+}
+
+/// What the index traversal leaves out or adds besides plain files.
+struct Walk<'a> {
+    blacklist: &'a Blacklist,
+    exclude_dirs: &'a [PathBuf],
+    index_streams: bool,
+    index_created: bool,
+    respect_gitignore: bool,
+    skip_hidden: bool,
+    roots: Vec<PathBuf>,
+    max_depth: Option<usize>,
+    dir_times: Mutex<HashMap<PathBuf, SystemTime>>,
+    progress: Option<&'a (Sender<IndexProgress>, Duration)>,
+    paused: Option<&'a AtomicBool>,
+    stop: Option<&'a AtomicBool>,
+    files_seen: AtomicUsize,
+    last_progress: Mutex<Option<Instant>>,
+}
+
+impl Walk<'_> {
+    /// Whether `dir` is no deeper below its root than `max_depth` allows.
+    fn within_depth(&self, dir: &Path) -> bool {
+        let Some(max_depth) = self.max_depth else {
+            return true;
+        };
+        self.roots
+            .iter()
+            .filter_map(|root| dir.strip_prefix(root).ok())
+            .map(|relative| relative.components().count())
+            .min()
+            .is_none_or(|depth| depth <= max_depth)
+    }
+
+    /// Whether indexing should give up.
+    fn stopped(&self) -> bool {
+        self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    /// Sleeps for as long as indexing is paused and not stopped.
+    fn wait_while_paused(&self) {
+        if let Some(paused) = self.paused {
+            while paused.load(Ordering::Relaxed) && !self.stopped() {
+                std::thread::sleep(PAUSE_POLL_INTERVAL);
+            }
+        }
+    }
+
+    /// Reports the files indexed so far while in `dir`, unless the previous report
+    /// was less than the progress interval ago. `finished` always reports.
+    fn report_progress(&self, dir: &Path, finished: bool) {
+        let Some((sender, interval)) = self.progress else {
+            return;
+        };
+        // Counting under the lock keeps reports from different threads in order.
+        let mut last = self.last_progress.lock().unwrap();
+        let now = Instant::now();
+        if !finished && last.is_some_and(|at| now.duration_since(at) < *interval) {
+            return;
+        }
+        *last = Some(now);
+        let _ = sender.send(IndexProgress {
+            files: self.files_seen.load(Ordering::Relaxed),
+            current_dir: dir.to_path_buf(),
+        });
+    }
+
+    /// The modification times of the folders entered so far.
+    fn into_dir_times(self) -> HashMap<PathBuf, SystemTime> {
+        self.dir_times.into_inner().unwrap()
+    }
+
+    /// The `.gitignore` files that apply inside `dir`, from its root inwards.
+    fn gitignores_for(&self, roots: &[PathBuf], dir: &Path) -> Vec<GitIgnore> {
+        if !self.respect_gitignore {
+            return Vec::new();
+        }
+        let Some(root) = roots.iter().find(|root| dir.starts_with(root)) else {
+            return Vec::new();
+        };
+        let mut ancestors: Vec<&Path> = dir
+            .ancestors()
+            .take_while(|ancestor| ancestor.starts_with(root))
+            .collect();
+        ancestors.reverse();
+        ancestors.into_iter().filter_map(GitIgnore::load).collect()
+    }
+
+    /// Indexes everything below `dir`, depth first.
+    ///
+    /// The folders still to visit are kept on a heap allocated stack rather than
+    /// the call stack, so however deeply folders nest the traversal cannot
+    /// overflow. Subfolders are pushed in reverse so they are visited in the
+    /// order `index_entries` returns them.
+    fn traverse(
+        &self,
+        dir: &Path,
+        indexed: &mut Vec<IndexedFile>,
+        gitignores: &mut Vec<GitIgnore>,
+        visited: &Mutex<HashSet<PathBuf>>,
+        after_push: &mut dyn FnMut(&[IndexedFile]) -> Result<(), SearchError>,
+    ) -> Result<(), SearchError> {
+        let mut stack = vec![Step::Enter(dir.to_path_buf())];
+        while let Some(step) = stack.pop() {
+            let dir = match step {
+                Step::Enter(dir) => dir,
+                Step::Leave { pushed } => {
+                    if pushed {
+                        gitignores.pop();
+                    }
+                    continue;
+                }
+            };
+            let Some(pushed) = self.enter(&dir, gitignores, visited) else {
+                continue;
+            };
+            stack.push(Step::Leave { pushed });
+            match self.index_entries(&dir, indexed, gitignores, after_push) {
+                Ok(subdirs) => stack.extend(subdirs.into_iter().rev().map(Step::Enter)),
+                Err(e) => {
+                    // Leave every folder still entered, as returning would have.
+                    for step in stack {
+                        if let Step::Leave { pushed: true } = step {
+                            gitignores.pop();
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks `dir` as visited and pushes its `.gitignore`, returning whether one was
+    /// pushed. Returns `None` when `dir` is unreadable or was already reached
+    /// through a symlink or junction.
+    fn enter(
+        &self,
+        dir: &Path,
+        gitignores: &mut Vec<GitIgnore>,
+        visited: &Mutex<HashSet<PathBuf>>,
+    ) -> Option<bool> {
+        self.wait_while_paused();
+        let canonical = dir.canonicalize().ok()?;
+        if !visited.lock().unwrap().insert(canonical) {
+            return None;
+        }
+        self.report_progress(dir, false);
+        if let Some(time) = modified_time(dir) {
+            self.dir_times
+                .lock()
+                .unwrap()
+                .insert(dir.to_path_buf(), time);
+        }
+        let gitignore = self
+            .respect_gitignore
+            .then(|| GitIgnore::load(dir))
+            .flatten();
+        let pushed = gitignore.is_some();
+        gitignores.extend(gitignore);
+        Some(pushed)
+    }
+
+    /// Indexes the files directly inside `dir` and returns the folders to descend into.
+    fn index_entries(
+        &self,
+        dir: &Path,
+        indexed: &mut Vec<IndexedFile>,
+        gitignores: &[GitIgnore],
+        after_push: &mut dyn FnMut(&[IndexedFile]) -> Result<(), SearchError>,
+    ) -> Result<Vec<PathBuf>, SearchError> {
+        let mut subdirs = Vec::new();
+        let Ok(entries) = read_dir(dir) else {
+            return Ok(subdirs);
+        };
+        for entry in entries.flatten() {
+            if self.stopped() {
+                return Err(SearchError::Stopped);
+            }
+            let path = entry.path();
+            if self.blacklist.blocks(&path) || (self.skip_hidden && is_hidden(&entry)) {
+                continue;
+            }
+            let Ok(metadata) = path.metadata() else {
+                continue;
+            };
+            let is_dir = metadata.is_dir();
+            if is_ignored(gitignores, &path, is_dir) {
+                continue;
+            }
+            if is_dir {
+                if !is_excluded_dir(self.exclude_dirs, &path) && self.within_depth(&path) {
+                    subdirs.push(path);
+                }
+            } else if metadata.is_file() {
+                let streams = if self.index_streams {
+                    alternate_streams(&path)
+                } else {
+                    Vec::new()
+                };
+                for stream in streams {
+                    indexed.push(IndexedFile::read_with(
+                        stream_entry(&path, &stream),
+                        self.index_created,
+                    ));
+                    after_push(indexed)?;
+                }
+                indexed.push(IndexedFile::with_metadata(
+                    path,
+                    &metadata,
+                    self.index_created,
+                ));
+                self.files_seen.fetch_add(1, Ordering::Relaxed);
+                after_push(indexed)?;
+            }
+        }
+        Ok(subdirs)
+    }
+}
+
+/// Writes `path` through `write`, first into a temporary file next to it that
+/// then replaces `path` in one rename. A crash or a failing `write` leaves the
+/// previous file as it was.
+fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+    let written = File::create(&temp_path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+    });
+    match written.and_then(|_| std::fs::rename(&temp_path, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+/// Reads an index file of `file_len` bytes in any known format version.
+///
+/// A corrupt or truncated file is an error rather than a panic. Reads are bounded by
+/// what the file can hold, so a garbled length cannot trigger a huge allocation.
+fn read_index(mut reader: impl BufRead, file_len: u64) -> Result<Vec<IndexedFile>, String> {
+    let invalid = |e: &dyn std::fmt::Display| format!("Failed to read index: {}", e);
+    // The encoding of `bincode::deserialize_from`, limited to `limit` bytes.
+    let options = |limit: u64| {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(limit)
+    };
+    if !reader
+        .fill_buf()
+        .map_err(|e| invalid(&e))?
+        .starts_with(INDEX_MAGIC)
+    {
+        return options(file_len)
+            .deserialize_from::<_, Vec<PathBuf>>(reader)
+            .map(paths_only)
+            .map_err(|e| invalid(&e));
+    }
+    reader.consume(INDEX_MAGIC.len());
+    let version = if reader
+        .fill_buf()
+        .map_err(|e| invalid(&e))?
+        .starts_with(GZIP_MAGIC)
+    {
+        1
+    } else {
+        let mut version = [0; 4];
+        reader.read_exact(&mut version).map_err(|e| invalid(&e))?;
+        u32::from_le_bytes(version)
+    };
+    match version {
+        // Deflate expands data at most 1032 times.
+        1 | 2 => options(file_len.saturating_mul(1032))
+            .deserialize_from::<_, Vec<PathBuf>>(GzDecoder::new(reader))
+            .map(paths_only)
+            .map_err(|e| invalid(&e)),
+        3 => options(file_len.saturating_mul(1032))
+            .deserialize_from::<_, Vec<IndexedFileV3>>(GzDecoder::new(reader))
+            .map(|files| files.into_iter().map(IndexedFile::from).collect())
+            .map_err(|e| invalid(&e)),
+        INDEX_VERSION => options(file_len.saturating_mul(1032))
+            .deserialize_from(GzDecoder::new(reader))
+            .map_err(|e| invalid(&e)),
+        version => Err(format!("Unsupported index version {}", version)),
+    }
+}
+
+/// Renders `files` as a JSON object whose `files` array holds one object per
+/// entry, one per line, with the `path`, the `size` in bytes and the modification
+/// time split into whole seconds since the Unix epoch, `modified`, and the
+/// nanoseconds beyond them, `modified_nanos`. A stored creation time is written
+/// the same way as `created` and `created_nanos`.
+fn index_to_json(files: &[IndexedFile]) -> String {
+    let since_epoch = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let entries: Vec<String> = files
+        .iter()
+        .map(|file| {
+            let modified = since_epoch(file.modified);
+            let created = file
+                .created
+                .map(|created| {
+                    let created = since_epoch(created);
+                    format!(
+                        ",\"created\":{},\"created_nanos\":{}",
+                        created.as_secs(),
+                        created.subsec_nanos()
+                    )
+                })
+                .unwrap_or_default();
+            format!(
+                "{{\"path\":{},\"size\":{},\"modified\":{},\"modified_nanos\":{}{}}}",
+                json_os_string(file.path.as_os_str()),
+                file.size,
+                modified.as_secs(),
+                modified.subsec_nanos(),
+                created
+            )
+        })
+        .collect();
+    format!("{{\"files\":[\n{}\n]}}\n", entries.join(",\n"))
+}
+
+/// Reads an index written by `index_to_json`. Entries without a size or
+/// modification time get unknown metadata, so a list of paths typed by hand also
+/// loads.
+fn index_from_json(text: &str) -> Result<Vec<IndexedFile>, String> {
+    let invalid = |e: &str| format!("Failed to import index: {}", e);
+    let document = parse_json(text).map_err(|e| invalid(&e))?;
+    let entries = document
+        .get("files")
+        .and_then(JsonValue::as_array)
+        .ok_or_else(|| invalid("no \"files\" array"))?;
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let path = entry
+                .get("path")
+                .and_then(JsonValue::as_str)
+                .ok_or_else(|| invalid(&format!("entry {} has no path", index + 1)))?;
+            let field = |key: &str| entry.get(key).and_then(JsonValue::as_u64);
+            let number = |key: &str| field(key).unwrap_or(0);
+            let time = |secs: u64, nanos_key: &str| {
+                UNIX_EPOCH + Duration::new(secs, number(nanos_key).min(999_999_999) as u32)
+            };
+            Ok(IndexedFile {
+                path: PathBuf::from(path),
+                size: number("size"),
+                modified: time(number("modified"), "modified_nanos"),
+                created: field("created").map(|secs| time(secs, "created_nanos")),
+            })
+        })
+        .collect()
+}
+
+/// The entries of an index saved without metadata.
+fn paths_only(paths: Vec<PathBuf>) -> Vec<IndexedFile> {
+    paths.into_iter().map(IndexedFile::from).collect()
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    path.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// The roots that are not inside an earlier one, which their traversal already covers.
+fn outermost_roots(roots: &[PathBuf]) -> Vec<PathBuf> {
+    roots
+        .iter()
+        .enumerate()
+        .filter(|(i, root)| !roots[..*i].iter().any(|earlier| root.starts_with(earlier)))
+        .map(|(_, root)| root.clone())
+        .collect()
+}
+
+/// Folders skipped when `config.toml` sets no `exclude`.
+pub(crate) const DEFAULT_EXCLUDED_DIRS: &[&str] =
+    &["node_modules", "$Recycle.Bin", "System Volume Information"];
+
+/// [`DEFAULT_EXCLUDED_DIRS`] as paths.
+pub(crate) fn default_exclude_dirs() -> Vec<PathBuf> {
+    DEFAULT_EXCLUDED_DIRS.iter().map(PathBuf::from).collect()
+}
+
+/// Whether `dir` matches one of `exclude_dirs`, ignoring case.
+///
+/// An entry containing a separator must equal the whole path of `dir`; any other
+/// entry is compared with the folder name and may use `*` and `?` wildcards.
+pub(crate) fn is_excluded_dir(exclude_dirs: &[PathBuf], dir: &Path) -> bool {
+    if exclude_dirs.is_empty() {
+        return false;
+    }
+    let path = dir.to_string_lossy().to_lowercase();
+    let name = path.rsplit(['\\', '/']).next().unwrap_or_default();
+    exclude_dirs.iter().any(|entry| {
+        let entry = entry.to_string_lossy().to_lowercase();
+        if entry.contains(['\\', '/']) {
+            normalize_path(&entry) == normalize_path(&path)
+        } else if entry.contains(['*', '?']) {
+            pattern_matches(&entry, name)
+        } else {
+            entry == name
+        }
+    })
+}
+
+/// File holding the seconds between automatic index updates, used when
+/// `config.toml` sets no `update_interval`.
+pub(crate) const UPDATE_TIME_FILE: &str = "updateTime.ini";
+
+/// Whether `entry` has the hidden or system attribute, such as `desktop.ini` or
+/// `pagefile.sys`.
+#[cfg(windows)]
+pub(crate) fn is_hidden(entry: &DirEntry) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    use windows_sys::Win32::Storage::FileSystem::{FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_SYSTEM};
+
+    entry.metadata().is_ok_and(|metadata| {
+        metadata.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0
+    })
+}
+
+/// Whether `entry` is a dotfile or dot folder, the convention for hidden entries
+/// outside Windows.
+#[cfg(not(windows))]
+pub(crate) fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().as_encoded_bytes().starts_with(b".")
+}
+
+fn normalize_root(root_dir: PathBuf) -> PathBuf {
+    match root_dir.to_str() {
+        Some(root) => PathBuf::from(normalize_path(root)),
+        None => root_dir,
+    }
+}
+
+/// Name of the file the index for `root` is saved to, in the working directory.
+///
+/// Path separators of every platform, drive colons, `%` and `+` are
+/// percent-encoded, as are bytes that are not valid UTF-8, so every root gets a
+/// name of its own: `C:\Users` and `/home` are saved as `index C%3A%5CUsers` and
+/// `index %2Fhome`. A `+` is encoded because it joins the names of several roots,
+/// see `get_index_file`.
+pub(crate) fn index_file_name(root: &Path) -> String {
+    let mut name = String::from("index ");
+    for chunk in root.as_os_str().as_encoded_bytes().utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' | '/' | ':' | '%' | '+' => name.push_str(&format!("%{:02X}", c as u32)),
+                c => name.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    name
+}
+
+fn is_name_separator(c: char) -> bool {
+    matches!(c, '.' | '_' | '-') || c.is_whitespace()
+}
+
+/// Lowercases `text` and drops name separators, pairing every remaining char with
+/// the byte range of the original char it came from.
+fn compact(text: &str) -> Vec<(char, (usize, usize))> {
+    text.char_indices()
+        .filter(|(_, c)| !is_name_separator(*c))
+        .flat_map(|(i, c)| {
+            let span = (i, i + c.len_utf8());
+            c.to_lowercase().map(move |lower| (lower, span))
+        })
+        .collect()
+}
+
+/// The byte ranges of every non-overlapping [`compact_match`] of `key` in
+/// `file_name`.
+fn compact_ranges(file_name: &str, key: &[char]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if key.is_empty() {
+        return ranges;
+    }
+    let name = compact(file_name);
+    let mut start = 0;
+    while start + key.len() <= name.len() {
+        let window = &name[start..start + key.len()];
+        if window.iter().map(|(c, _)| c).eq(key.iter()) {
+            ranges.push(window[0].1 .0..window[key.len() - 1].1 .1);
+            start += key.len();
+        } else {
+            start += 1;
+        }
+    }
+    ranges
+}
+
+/// Matches a file name against a query while ignoring separators and case.
+///
+/// Both sides are compacted: `.`, `_`, `-` and whitespace are removed and letters are
+/// lowercased, which also erases camelCase word boundaries. `key` is the compacted
+/// query; the name matches when it occurs anywhere in the compacted name. Returns the
+/// matched part of the original name, separators included, so `mainrs` matches
+/// `main.rs` and `myfile` matches `my_file.txt` and `MyFile.txt`.
+pub(crate) fn compact_match<'a>(file_name: &'a str, key: &[char]) -> Option<&'a str> {
+    if key.is_empty() {
+        return None;
+    }
+    let name = compact(file_name);
+    let start = name
+        .windows(key.len())
+        .position(|window| window.iter().map(|(c, _)| c).eq(key.iter()))?;
+    let (begin, _) = name[start].1;
+    let (_, end) = name[start + key.len() - 1].1;
+    Some(&file_name[begin..end])
+}
+
+fn in_scope(scope: &[PathBuf], file: &Path) -> bool {
+    scope.is_empty() || scope.iter().any(|dir| file.starts_with(dir))
+}
+
+/// Whether `file` has one of the lowercase `extensions`, or any file when there are none.
+fn has_extension(extensions: &[String], file: &Path) -> bool {
+    extensions.is_empty()
+        || file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                extensions
+                    .iter()
+                    .any(|allowed| extension.eq_ignore_ascii_case(allowed))
+            })
+}
+
+/// Parses a list of extensions such as `rs,toml` or `.rs .toml`.
+pub(crate) fn parse_extension_list(text: &str) -> Vec<String> {
+    text.split([',', ' ', ';'])
+        .map(|extension| extension.trim().trim_start_matches('.'))
+        .filter(|extension| !extension.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses a pasted list of directories, one per line or separated by `;`.
+/// Surrounding quotes are stripped and each entry is normalized.
+pub(crate) fn parse_path_list(text: &str) -> Vec<PathBuf> {
+    text.split(['\n', ';'])
+        .map(|entry| entry.trim().trim_matches('"').trim())
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| PathBuf::from(normalize_path(entry)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Index entries for `paths`, without metadata.
+    fn entries<P: Into<PathBuf>>(paths: impl IntoIterator<Item = P>) -> Vec<IndexedFile> {
+        paths
+            .into_iter()
+            .map(|path| IndexedFile::from(path.into()))
+            .collect()
+    }
+
+    /// The paths in the index of `search`, in index order.
+    fn index_paths(search: &Search) -> Vec<PathBuf> {
+        search
+            .get_index()
+            .iter()
+            .map(|file| file.path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_new() {
+        let search = Search::new();
+        assert!(search.indexed_files.is_empty());
+        assert_eq!(search.root_dir, PathBuf::from("C:\\"));
+    }
+
+    #[test]
+    fn test_set_root_dir() {
+        let mut search = Search::new();
+        let new_root = PathBuf::from("D:\\");
+        search.set_root_dir(new_root.clone());
+        assert_eq!(search.root_dir, new_root);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("C:\\"), "C:\\");
+        assert_eq!(normalize_path("C:"), "C:\\");
+        assert_eq!(normalize_path("C:\\\\"), "C:\\");
+        assert_eq!(normalize_path("C:/Users//me/"), "C:\\Users\\me");
+        assert_eq!(normalize_path("\\\\server\\\\share\\"), "\\\\server\\share");
+        assert_eq!(
+            normalize_path("\\\\server/share//dir"),
+            "\\\\server\\share\\dir"
+        );
+        assert_eq!(
+            normalize_path("D:\\a\\\\b\\c\\\\d\\e.txt"),
+            "D:\\a\\b\\c\\d\\e.txt"
+        );
+        assert_eq!(normalize_path("."), ".");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_normalize_unix_path() {
+        assert_eq!(normalize_path("//server/share/dir"), "/server/share/dir");
+        assert_eq!(normalize_path("/home//me/projects/"), "/home/me/projects");
+        assert_eq!(normalize_path("//"), "/");
+        assert_eq!(normalize_path("relative//dir/"), "relative/dir");
+        // A backslash is part of the name, not a separator.
+        assert_eq!(normalize_path("/tmp/a\\b/"), "/tmp/a\\b");
+        assert_eq!(normalize_path("back\\\\slash"), "back\\\\slash");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_normalize_windows_path() {
+        assert_eq!(
+            normalize_path("//server/share/dir"),
+            "\\\\server\\share\\dir"
+        );
+        assert_eq!(normalize_path("\\Users//me/"), "\\Users\\me");
+        assert_eq!(normalize_path("relative//dir/"), "relative\\dir");
+    }
+
+    #[test]
+    fn test_set_root_dir_normalizes() {
+        let mut search = Search::new();
+        search.set_root_dir(PathBuf::from("E:"));
+        assert_eq!(search.get_root_dir(), &PathBuf::from("E:\\"));
+    }
+
+    #[test]
+    fn test_generate_index() {
+        let mut search = Search::new();
         search.set_root_dir(PathBuf::from("."));
-        search.generate_index();
+        search.generate_index().unwrap();
         assert!(!search.indexed_files.is_empty());
     }
 
     #[test]
-    fn test_save_and_load_index() {
+    fn test_deeply_nested_folders_do_not_overflow_the_stack() {
+        const DEPTH: usize = 400;
+        let root = std::env::temp_dir().join("search_deep_test");
+        let _ = std::fs::remove_dir_all(&root);
+        let mut deepest = root.clone();
+        for _ in 0..DEPTH {
+            deepest.push("d");
+        }
+        std::fs::create_dir_all(&deepest).unwrap();
+        std::fs::write(deepest.join("bottom.txt"), "").unwrap();
+        for (dir, file) in [("a", "f.txt"), ("a/sub", "g.txt"), ("b", "h.txt")] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join(file), "").unwrap();
+        }
+
+        // A stack this small would not hold one frame per folder level.
+        let indexed = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn({
+                let root = root.clone();
+                move || {
+                    let mut search = Search::new();
+                    search.set_root_dir(root);
+                    search
+                        .generate_index_with_progress(usize::MAX, &mut |_| {})
+                        .unwrap();
+                    index_paths(&search)
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(indexed.contains(&deepest.join("bottom.txt")));
+        assert_eq!(indexed.len(), 4);
+        // Each folder is finished before its siblings, files before subfolders.
+        let position = |file: &str| indexed.iter().position(|path| path.ends_with(file));
+        let (f, g, h) = (
+            position("a/f.txt"),
+            position("a/sub/g.txt"),
+            position("b/h.txt"),
+        );
+        assert_eq!(g, f.map(|f| f + 1));
+        assert!(h < f || h > g);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_progress_counts_increase() {
+        let root = std::env::temp_dir().join("search_progress_test");
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["a", "a/inner", "b", "c"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            for i in 0..5 {
+                std::fs::write(root.join(dir).join(format!("{}.txt", i)), "").unwrap();
+            }
+        }
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut search = Search::new();
+        search.set_root_dir(root.clone());
+        search.set_progress_sender(Some(sender), Duration::ZERO);
+        search.generate_index().unwrap();
+
+        let reports: Vec<IndexProgress> = receiver.try_iter().collect();
+        // One report per folder entered and a final one.
+        assert_eq!(reports.len(), 6);
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].files <= pair[1].files));
+        let last = reports.last().unwrap();
+        assert_eq!(last.files, 20);
+        assert_eq!(last.current_dir, root);
+        assert!(reports[1..5]
+            .iter()
+            .all(|report| report.current_dir.starts_with(&root) && report.current_dir != root));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_paused_indexing_waits_for_resume() {
+        const DEPTH: usize = 200;
+        let root = std::env::temp_dir().join("search_pause_test");
+        let _ = std::fs::remove_dir_all(&root);
+        let mut dir = root.clone();
+        for level in 0..DEPTH {
+            dir.push(format!("level{}", level));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("file.txt"), "").unwrap();
+        }
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let paused = Arc::new(AtomicBool::new(false));
+        let mut search = Search::new();
+        search.set_root_dir(root.clone());
+        search.set_progress_sender(Some(sender), Duration::ZERO);
+        search.set_pause_flag(Some(paused.clone()));
+        let indexing = std::thread::spawn(move || {
+            search.generate_index().unwrap();
+            search
+        });
+
+        // Pause as soon as the first folder is entered, then check that no
+        // further folder is entered until indexing resumes.
+        receiver.recv().unwrap();
+        paused.store(true, Ordering::Relaxed);
+        std::thread::sleep(PAUSE_POLL_INTERVAL * 2);
+        let before_pause = receiver.try_iter().count() + 1;
+        std::thread::sleep(PAUSE_POLL_INTERVAL * 2);
+        let while_paused = receiver.try_iter().count();
+        let finished_while_paused = indexing.is_finished();
+        paused.store(false, Ordering::Relaxed);
+        let search = indexing.join().unwrap();
+        assert!(before_pause < DEPTH);
+        assert_eq!(while_paused, 0);
+        assert!(!finished_while_paused);
+
+        assert_eq!(search.get_index().len(), DEPTH);
+        assert_eq!(receiver.try_iter().last().unwrap().files, DEPTH);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_stop_flag_ends_indexing_without_a_partial_index() {
+        const DEPTH: usize = 200;
+        let root = std::env::temp_dir().join("search_stop_test");
+        let _ = std::fs::remove_dir_all(&root);
+        let mut dir = root.clone();
+        for level in 0..DEPTH {
+            dir.push(format!("level{}", level));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("file.txt"), "").unwrap();
+        }
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let paused = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut search = Search::new();
+        search.set_root_dir(root.clone());
+        search.set_progress_sender(Some(sender), Duration::ZERO);
+        search.set_pause_flag(Some(paused.clone()));
+        search.set_stop_flag(Some(stop.clone()));
+        let indexing = std::thread::spawn(move || (search.generate_index(), search));
+
+        // Stop midway through the folders, while paused so that it is not done yet.
+        receiver.recv().unwrap();
+        paused.store(true, Ordering::Relaxed);
+        stop.store(true, Ordering::Relaxed);
+        let (result, search) = indexing.join().unwrap();
+        assert!(matches!(result, Err(SearchError::Stopped)));
+        assert!(receiver.try_iter().count() < DEPTH);
+        assert!(search.get_index().is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let root = std::env::temp_dir().join("search_max_depth_test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("a").join("b")).unwrap();
+        for file in ["top.txt", "a/one.txt", "a/b/two.txt"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        let mut search = Search::new();
+        search.set_root_dir(root.clone());
+        let names = |search: &Search| -> Vec<String> {
+            let mut names: Vec<String> = search
+                .get_index()
+                .iter()
+                .map(|file| {
+                    file.path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            names.sort();
+            names
+        };
+        for (max_depth, expected) in [
+            (Some(0), vec!["top.txt"]),
+            (Some(1), vec!["one.txt", "top.txt"]),
+            (None, vec!["one.txt", "top.txt", "two.txt"]),
+        ] {
+            search.set_max_depth(max_depth);
+            search.generate_index().unwrap();
+            assert_eq!(names(&search), expected);
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_query_partial_index_mid_build() {
+        let dir = std::env::temp_dir().join("search_partial_index_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        for i in 0..10 {
+            std::fs::write(dir.join(format!("file{}.rs", i)), "").unwrap();
+            std::fs::write(dir.join("nested").join(format!("inner{}.rs", i)), "").unwrap();
+        }
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        let mut snapshots: Vec<Vec<IndexedFile>> = Vec::new();
+        search
+            .generate_index_with_progress(2, &mut |files| {
+                let mut reader = Search::new();
+                reader.replace_index(files.to_vec());
+                reader.search("\\.rs$").unwrap();
+                assert_eq!(reader.get_results().len(), files.len());
+                assert!(reader
+                    .iter_results()
+                    .all(|(path, _)| files.iter().any(|file| &file.path == path)));
+                snapshots.push(files.to_vec());
+            })
+            .unwrap();
+        let sizes: Vec<usize> = snapshots.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![2, 4, 8, 16]);
+        for snapshot in &snapshots {
+            assert_eq!(snapshot[..], search.get_index()[..snapshot.len()]);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_and_load_index() {
+        let mut search = Search::new();
+        search.set_root_dir(PathBuf::from("."));
+        search.generate_index().unwrap();
+        search.save_index().unwrap();
+
+        let mut new_search = Search::new();
+        new_search.set_root_dir(PathBuf::from("."));
+        new_search.load_index().unwrap();
+        assert_eq!(search.indexed_files, new_search.indexed_files);
+    }
+
+    #[test]
+    fn test_index_records_size_and_modified_time() {
+        let dir = std::env::temp_dir().join("search_metadata_index_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("data.bin");
+        std::fs::write(&file, [0; 1234]).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 500);
+        File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.generate_index().unwrap();
+        let indexed = IndexedFile {
+            path: file.clone(),
+            size: 1234,
+            modified,
+            created: None,
+        };
+        assert_eq!(*search.get_index(), vec![indexed.clone()]);
+        search.save_index().unwrap();
+
+        // The metadata is what was indexed, not what the file holds now.
+        std::fs::write(&file, "changed").unwrap();
+        let mut reloaded = Search::new();
+        reloaded.set_root_dir(dir.clone());
+        reloaded.load_index().unwrap();
+        assert_eq!(*reloaded.get_index(), vec![indexed]);
+        std::fs::remove_file(search.get_index_file()).unwrap();
+
+        // Creation times are only stored when asked for.
+        search.set_index_created_time(true);
+        search.generate_index().unwrap();
+        let created = std::fs::metadata(&file).unwrap().created().ok();
+        assert_eq!(search.get_index()[0].created, created);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_index_json_round_trip() {
+        let dir = std::env::temp_dir().join("search_json_index_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs").join("say \"hi\".txt"), "hi").unwrap();
+        std::fs::write(dir.join("docs").join("notes.md"), "").unwrap();
+        std::fs::write(dir.join("readme.txt"), "read me").unwrap();
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.set_index_created_time(true);
+        search.generate_index().unwrap();
+        let json = std::env::temp_dir().join("search_json_index_test.json");
+        search.export_index_json(&json).unwrap();
+
+        let mut imported = Search::new();
+        imported.import_index_json(&json).unwrap();
+        assert_eq!(imported.get_index(), search.get_index());
+        for key in ["txt", "hi", "no"] {
+            search.reset_search_results();
+            imported.reset_search_results();
+            search.search(key).unwrap();
+            imported.search(key).unwrap();
+            assert_eq!(imported.get_results(), search.get_results());
+        }
+
+        // Hand-written indexes may leave the metadata out; broken ones change nothing.
+        std::fs::write(&json, r#"{"files": [{"path": "/a/b.txt"}]}"#).unwrap();
+        imported.import_index_json(&json).unwrap();
+        assert_eq!(*imported.get_index(), entries(["/a/b.txt"]));
+        for broken in [r#"{"files": [{"size": 1}]}"#, "[]", "{"] {
+            std::fs::write(&json, broken).unwrap();
+            assert!(imported.import_index_json(&json).is_err());
+            assert_eq!(imported.len(), 1);
+        }
+        std::fs::remove_file(&json).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_index_errors() {
+        let json = std::env::temp_dir().join("search_index_errors_test.json");
+        let _ = std::fs::remove_file(&json);
+        let mut search = Search::new();
+        assert!(matches!(
+            search.export_index_json(&json),
+            Err(SearchError::IndexNotLoaded)
+        ));
+        assert!(!json.exists());
+        assert!(matches!(
+            search.import_index_json(&json),
+            Err(SearchError::NotFound(path)) if path == json
+        ));
+
+        std::fs::write(&json, "{").unwrap();
+        let error = search.import_index_json(&json).unwrap_err();
+        assert!(matches!(error, SearchError::InvalidIndex(_)));
+        assert!(error.to_string().starts_with("Failed to import index"));
+        std::fs::remove_file(&json).unwrap();
+    }
+
+    #[test]
+    fn test_saving_empty_index_keeps_existing_file() {
+        let root = PathBuf::from("search_empty_save_test");
+        let mut search = Search::new();
+        search.set_root_dir(root.clone());
+        search.replace_index(entries(vec![root.join("kept.txt")]));
+        search.save_index().unwrap();
+        let index_file = index_file_name(search.get_root_dir());
+        let saved = std::fs::read(&index_file).unwrap();
+
+        search.clear_index_files();
+        search.save_index().unwrap();
+        assert_eq!(std::fs::read(&index_file).unwrap(), saved);
+
+        let mut reloaded = Search::new();
+        reloaded.set_root_dir(root.clone());
+        reloaded.load_index().unwrap();
+        assert_eq!(index_paths(&reloaded), vec![root.join("kept.txt")]);
+        std::fs::remove_file(index_file).unwrap();
+    }
+
+    #[test]
+    fn test_index_file_name_is_portable() {
+        assert_eq!(index_file_name(Path::new("C:\\")), "index C%3A%5C");
+        assert_eq!(
+            index_file_name(Path::new("C:\\Users\\me")),
+            "index C%3A%5CUsers%5Cme"
+        );
+        assert_eq!(index_file_name(Path::new("/home/me")), "index %2Fhome%2Fme");
+        assert_eq!(index_file_name(Path::new("/ñandú")), "index %2Fñandú");
+    }
+
+    #[test]
+    fn test_index_file_names_do_not_collide() {
+        let roots = [
+            "/a/b", "/ab", "a/b", "a\\b", "a:b", "ab", "a%2Fb", "a+b", "a%b", "a + b",
+        ];
+        let mut names: Vec<String> = roots
+            .iter()
+            .map(|root| index_file_name(Path::new(root)))
+            .collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), roots.len());
+
+        // Several roots joined with ` + ` cannot pass for one root.
+        let mut joined = Search::new();
+        joined.set_root_dir(PathBuf::from("a"));
+        joined.add_root_dir(PathBuf::from("b"));
+        assert_ne!(joined.get_index_file(), index_file_name(Path::new("a + b")));
+    }
+
+    #[test]
+    fn test_unusual_root_names_round_trip() {
+        for root in ["/ search space", "/9 search digit", "/ñandú search"] {
+            let root = PathBuf::from(root);
+            let file = root.join("file.txt");
+            let mut search = Search::new();
+            search.set_root_dir(root.clone());
+            search.replace_index(vec![IndexedFile::from(file.clone())]);
+            search.save_index().unwrap();
+
+            let mut reloaded = Search::new();
+            reloaded.set_root_dir(root.clone());
+            reloaded.load_index().unwrap();
+            assert_eq!(index_paths(&reloaded), [file]);
+            std::fs::remove_file(index_file_name(&root)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_index_absolute_tree_round_trip() {
+        let dir = std::env::temp_dir().join("search_portable_index_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("a").join("b")).unwrap();
+        std::fs::write(dir.join("top.txt"), "").unwrap();
+        std::fs::write(dir.join("a").join("b").join("deep.txt"), "").unwrap();
+
         let mut search = Search::new();
-        search.set_root_dir(PathBuf::from("."));
-        search.generate_index();
-        search.save_index();
+        search.set_root_dir(dir.clone());
+        search.generate_index().unwrap();
+        let mut indexed = index_paths(&search);
+        indexed.sort();
+        assert_eq!(
+            indexed,
+            vec![
+                dir.join("a").join("b").join("deep.txt"),
+                dir.join("top.txt")
+            ]
+        );
+        for file in &indexed {
+            assert!(file.parent().unwrap().starts_with(&dir));
+        }
+        search.search("deep").unwrap();
+        assert_eq!(search.get_results()[0].1, "deep");
 
-        let mut new_search = Search::new();
-        new_search.set_root_dir(PathBuf::from("."));
-        new_search.load_index();
-        assert_eq!(search.indexed_files, new_search.indexed_files);
+        search.save_index().unwrap();
+        let mut reloaded = Search::new();
+        reloaded.set_root_dir(dir.clone());
+        reloaded.load_index().unwrap();
+        assert_eq!(reloaded.get_index(), search.get_index());
+        std::fs::remove_file(index_file_name(search.get_root_dir())).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_multiple_roots_share_one_index() {
+        let dir = std::env::temp_dir().join("search_multiple_roots_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        for root in ["home", "projects"] {
+            std::fs::create_dir_all(dir.join(root)).unwrap();
+            std::fs::write(dir.join(root).join(format!("{}.txt", root)), "").unwrap();
+        }
+        let mut search = Search::new();
+        search.set_root_dir(dir.join("home"));
+        search.add_root_dir(dir.join("missing"));
+        search.add_root_dir(dir.join("projects"));
+        search.add_root_dir(dir.join("home"));
+        assert_eq!(search.get_root_dirs().len(), 3);
+        search.generate_index().unwrap();
+        assert_eq!(
+            index_paths(&search),
+            vec![
+                dir.join("home").join("home.txt"),
+                dir.join("projects").join("projects.txt")
+            ]
+        );
+        assert_ne!(search.get_index_file(), index_file_name(&dir.join("home")));
+
+        search.set_root_dir(dir.join("projects"));
+        assert_eq!(search.get_root_dirs(), vec![dir.join("projects")]);
+        assert_eq!(
+            search.get_index_file(),
+            index_file_name(&dir.join("projects"))
+        );
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     #[test]
     fn test_get_index() {
         let mut search = Search::new();
         search.set_root_dir(PathBuf::from("."));
-        search.generate_index();
+        search.generate_index().unwrap();
         let index = search.get_index();
-        assert_eq!(index, &search.indexed_files);
+        assert_eq!(index, &*search.indexed_files);
+    }
+
+    #[test]
+    fn test_search_scope() {
+        let scope = parse_path_list("\"/work/a\"\n\n  /work/c//  ;/missing\r\n");
+        assert_eq!(
+            scope,
+            vec![
+                PathBuf::from("/work/a"),
+                PathBuf::from("/work/c"),
+                PathBuf::from("/missing")
+            ]
+        );
+
+        let mut search = Search::new();
+        search.indexed_files = Arc::new(entries(
+            [
+                "/work/a/x.txt",
+                "/work/ab/x.txt",
+                "/work/b/x.txt",
+                "/work/c/d/x.txt",
+            ]
+            .map(PathBuf::from),
+        ));
+        search.set_search_scope(scope);
+        search.search("x").unwrap();
+        let found: Vec<_> = search
+            .get_results()
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                PathBuf::from("/work/a/x.txt"),
+                PathBuf::from("/work/c/d/x.txt")
+            ]
+        );
+
+        search.reset_search_results();
+        search.fallback_search("X");
+        assert_eq!(search.get_results().len(), 2);
+
+        search.reset_search_results();
+        search.set_search_scope(Vec::new());
+        search.search("x").unwrap();
+        assert_eq!(search.get_results().len(), 4);
+    }
+
+    #[test]
+    fn test_search_reports_invalid_patterns() {
+        let mut search = Search::new();
+        search.insert_path(PathBuf::from("/a/report[1].txt"));
+        search.insert_path(PathBuf::from("/a/notes.txt"));
+        assert!(search.search("rep.*\\[1\\]").is_ok());
+        assert_eq!(search.get_results().len(), 1);
+
+        search.reset_search_results();
+        let err = search.search("report[").unwrap_err();
+        assert!(matches!(
+            err,
+            SearchError::InvalidPattern(regex::Error::Syntax(_))
+        ));
+        assert!(search.get_results().is_empty());
+    }
+
+    #[test]
+    fn test_compact_match() {
+        let key = |query: &str| {
+            compact(query)
+                .into_iter()
+                .map(|(c, _)| c)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(compact_match("main.rs", &key("mainrs")), Some("main.rs"));
+        assert_eq!(
+            compact_match("my_file.txt", &key("myfile")),
+            Some("my_file")
+        );
+        assert_eq!(compact_match("MyFile.txt", &key("myfile")), Some("MyFile"));
+        assert_eq!(
+            compact_match("old-My File.txt", &key("my-file")),
+            Some("My File")
+        );
+        assert_eq!(compact_match("ÄPFEL.md", &key("äpfelmd")), Some("ÄPFEL.md"));
+        assert_eq!(compact_match("main.rs", &key("mains")), None);
+        assert_eq!(compact_match("main.rs", &key("._")), None);
+    }
+
+    #[test]
+    fn test_search_ignoring_separators() {
+        let mut search = Search::new();
+        search.indexed_files = Arc::new(entries(["src/main.rs", "my_file.txt", "domain.rs"]));
+        search.search("mainrs").unwrap();
+        assert!(search.get_results().is_empty());
+
+        search.set_ignore_separators(true);
+        search.search("mainrs").unwrap();
+        assert_eq!(
+            search.get_results(),
+            &vec![
+                (PathBuf::from("src/main.rs"), "main.rs".to_string()),
+                (PathBuf::from("domain.rs"), "main.rs".to_string()),
+            ]
+        );
+        search.reset_search_results();
+        search.search("myfile").unwrap();
+        assert_eq!(search.get_results()[0].1, "my_file");
+    }
+
+    #[test]
+    fn test_take_results_moves_without_cloning() {
+        let mut search = Search::new();
+        search.indexed_files = Arc::new(entries((0..1000).map(|i| format!("file{}.txt", i))));
+        search.set_search_results_limit(usize::MAX);
+        search.search("file").unwrap();
+        assert_eq!(search.iter_results().count(), 1000);
+        assert!(search
+            .iter_results()
+            .zip(search.get_index())
+            .all(|((path, _), indexed)| *path == indexed.path));
+
+        let buffer = search.get_results().as_ptr();
+        let taken = search.take_results();
+        assert_eq!(taken.as_ptr(), buffer);
+        assert_eq!(taken.len(), 1000);
+        assert_eq!(search.iter_results().count(), 0);
+    }
+
+    #[test]
+    fn test_auto_mode_routing() {
+        assert!(!looks_like_regex("main.rs"));
+        assert!(!looks_like_regex("my report 2024"));
+        assert!(looks_like_regex("^main"));
+        assert!(looks_like_regex("a|b"));
+        assert!(looks_like_regex("c++"));
+
+        let auto = |key: &str| SearchMode::Auto.pattern(key, false).unwrap();
+        assert!(auto("main.rs").is_match("main.rs"));
+        assert!(!auto("main.rs").is_match("mainXrs"));
+        assert!(auto("^main").is_match("main.rs"));
+        assert!(!auto("^main").is_match("domain.rs"));
+        assert!(auto("\\d{4}").is_match("report 2024.pdf"));
+        // Regex-looking keys that do not compile fall back to literal matching.
+        assert!(auto("a(b").is_match("a(b).txt"));
+        assert!(auto("c++").is_match("c++ primer.pdf"));
+        assert!(SearchMode::Regex.pattern("a(b", false).is_err());
+    }
+
+    #[test]
+    fn test_haystack_applies_to_every_matching_mode() {
+        let mut search = Search::new();
+        for file in [
+            "/projects/docs/readme.md",
+            "/tmp/docs.txt",
+            "/a/b/notes.docs",
+        ] {
+            search.insert_path(PathBuf::from(file));
+        }
+        let run = |search: &mut Search, fallback: bool| {
+            search.reset_search_results();
+            if fallback {
+                search.fallback_search("DOCS");
+            } else {
+                search.search("docs").unwrap();
+            }
+            search.iter_results().count()
+        };
+        for (haystack, expected) in [
+            (Haystack::Name, 1),
+            (Haystack::NameWithExtension, 2),
+            (Haystack::FullPath, 3),
+        ] {
+            search.set_haystack(haystack);
+            for mode in [SearchMode::Regex, SearchMode::Auto] {
+                search.set_search_mode(mode);
+                assert_eq!(
+                    run(&mut search, false),
+                    expected,
+                    "{:?} {:?}",
+                    haystack,
+                    mode
+                );
+            }
+            search.set_ignore_separators(true);
+            assert_eq!(run(&mut search, false), expected, "{:?} compact", haystack);
+            search.set_ignore_separators(false);
+            assert_eq!(run(&mut search, true), expected, "{:?} fallback", haystack);
+        }
+    }
+
+    #[test]
+    fn test_case_insensitive_search_keeps_original_case() {
+        let mut search = Search::new();
+        search.insert_path(PathBuf::from("/pics/Photo.JPG"));
+        search.insert_path(PathBuf::from("/docs/ÄRGER.txt"));
+        search.search("photo").unwrap();
+        assert_eq!(
+            search.get_results(),
+            &vec![(PathBuf::from("/pics/Photo.JPG"), "Photo".to_string())]
+        );
+        search.reset_search_results();
+        search.search("ärger").unwrap();
+        assert_eq!(search.get_results()[0].0, PathBuf::from("/docs/ÄRGER.txt"));
+
+        search.set_ignore_case(false);
+        search.reset_search_results();
+        search.search("photo").unwrap();
+        assert!(search.get_results().is_empty());
+    }
+
+    #[test]
+    fn test_prefix_and_substring_modes() {
+        let mut search = Search::new();
+        search.insert_path(PathBuf::from("/docs/report_2023.pdf"));
+        search.insert_path(PathBuf::from("/docs/annual_report_2023.pdf"));
+        search.insert_path(PathBuf::from("/docs/re.port.txt"));
+        let names = |search: &Search| -> Vec<String> {
+            search
+                .iter_results()
+                .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        search.set_search_mode(SearchMode::Prefix);
+        search.search("report").unwrap();
+        assert_eq!(names(&search), ["report_2023.pdf"]);
+
+        search.reset_search_results();
+        search.search_substring("report").unwrap();
+        assert_eq!(
+            names(&search),
+            ["report_2023.pdf", "annual_report_2023.pdf"]
+        );
+
+        search.reset_search_results();
+        search.set_search_mode(SearchMode::Contains);
+        search.search("re.port").unwrap();
+        assert_eq!(names(&search), ["re.port.txt"]);
+    }
+
+    #[test]
+    fn test_match_ranges_cover_every_occurrence() {
+        let mut search = Search::new();
+        let report = PathBuf::from("/report/report_re.txt");
+        search.insert_path(report.clone());
+        search.search("re").unwrap();
+        assert_eq!(search.match_ranges(&report), [0..2, 7..9]);
+
+        // Folders do not count in a full path search, and overlaps are merged.
+        search.reset_search_results();
+        search.set_haystack(Haystack::FullPath);
+        search.search("report AND port_r").unwrap();
+        assert_eq!(search.match_ranges(&report), [Range { start: 0, end: 8 }]);
+
+        search.reset_search_results();
+        search.set_haystack(Haystack::Name);
+        search.set_whole_word(true);
+        search.search("re").unwrap();
+        assert_eq!(search.match_ranges(&report), [Range { start: 7, end: 9 }]);
+
+        search.reset_search_results();
+        search.set_whole_word(false);
+        search.set_ignore_separators(true);
+        search.search("tre").unwrap();
+        assert_eq!(search.match_ranges(&report), [Range { start: 5, end: 9 }]);
+        assert_eq!(search.take_match_ranges().len(), 1);
+        assert!(search.match_ranges(&report).is_empty());
+    }
+
+    #[test]
+    fn test_search_stops_at_the_results_limit() {
+        let mut search = Search::new();
+        for i in 0..10 {
+            search.insert_path(PathBuf::from(format!("/a/file{}.txt", i)));
+        }
+        search.set_search_results_limit(4);
+        search.search(".").unwrap();
+        assert_eq!(search.get_results().len(), 4);
+        assert!(search.results_truncated());
+
+        search.reset_search_results();
+        assert!(!search.results_truncated());
+        search.set_search_results_limit(10);
+        search.search(".").unwrap();
+        assert_eq!(search.get_results().len(), 10);
+        assert!(!search.results_truncated());
+
+        search.reset_search_results();
+        search.set_search_results_limit(3);
+        search.fallback_search("file");
+        assert_eq!(search.get_results().len(), 3);
+        assert!(search.results_truncated());
+
+        // A further search only fills what is left below the limit.
+        search.set_search_results_limit(5);
+        search.search("file").unwrap();
+        assert_eq!(search.get_results().len(), 5);
+        search.set_ignore_separators(true);
+        search.search("file").unwrap();
+        assert_eq!(search.get_results().len(), 5);
+    }
+
+    #[test]
+    fn test_and_or_queries() {
+        let mut search = Search::new();
+        for file in [
+            "/bills/invoice_2024.pdf",
+            "/bills/invoice_2023.pdf",
+            "/photos/beach_2024.jpg",
+            "/photos/beach_2024.png",
+            "/photos/beach_2023.gif",
+        ] {
+            search.insert_path(PathBuf::from(file));
+        }
+        let found = |search: &mut Search, key: &str| -> Vec<(String, String)> {
+            search.reset_search_results();
+            search.search(key).unwrap();
+            search
+                .iter_results()
+                .map(|(path, matched)| {
+                    let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                    (name, matched.clone())
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            found(&mut search, "invoice AND 2024"),
+            [("invoice_2024.pdf".to_string(), "invoice".to_string())]
+        );
+        let names = |found: Vec<(String, String)>| -> Vec<String> {
+            found.into_iter().map(|(name, _)| name).collect()
+        };
+        assert_eq!(
+            names(found(&mut search, "jpg OR png")),
+            ["beach_2024.jpg", "beach_2024.png"]
+        );
+        assert_eq!(
+            names(found(&mut search, "(gif OR png) AND beach")),
+            ["beach_2024.png", "beach_2023.gif"]
+        );
+        search.set_ignore_separators(true);
+        assert_eq!(
+            names(found(&mut search, "beach2024 AND (jpg OR gif)")),
+            ["beach_2024.jpg"]
+        );
+        search.set_ignore_separators(false);
+        assert!(search.search("report[ OR x").is_err());
+    }
+
+    #[test]
+    fn test_glob_mode() {
+        let mut search = Search::new();
+        search.replace_index(entries(vec![
+            PathBuf::from("/src.rs/notes.txt"),
+            PathBuf::from("/src/main.rs"),
+            PathBuf::from("/src/main.rs.bak"),
+            PathBuf::from("/docs/abc.txt"),
+            PathBuf::from("/docs/a.c.txt"),
+            PathBuf::from("/docs/abbc.txt"),
+        ]));
+        search.set_search_mode(SearchMode::Glob);
+        // Globs always match the file name, even when set to match full paths.
+        search.set_haystack(Haystack::FullPath);
+        let names = |search: &mut Search, glob: &str| -> Vec<String> {
+            search.reset_search_results();
+            search.search(glob).unwrap();
+            search
+                .iter_results()
+                .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        assert_eq!(names(&mut search, "*.rs"), ["main.rs"]);
+        assert_eq!(names(&mut search, "a?c.txt"), ["abc.txt", "a.c.txt"]);
+        assert_eq!(names(&mut search, "*.RS"), ["main.rs"]);
+        assert!(names(&mut search, "*.zip").is_empty());
+        assert_eq!(glob_to_regex("a+(b)?*"), r"^a\+\(b\)..*$");
+    }
+
+    #[test]
+    fn test_search_view_shares_index_and_stops_when_cancelled() {
+        let mut search = Search::new();
+        search.replace_index(entries((0..100).map(|i| format!("file{}.txt", i))));
+        let mut view = search.search_view();
+        assert!(Arc::ptr_eq(&search.indexed_files, &view.indexed_files));
+
+        let mut worker = crate::worker::SearchWorker::default();
+        let (token_sender, tokens) = std::sync::mpsc::channel();
+        worker.start(move |token: &CancelToken| token_sender.send(token.clone()).ok());
+        let token = tokens.recv().unwrap();
+        view.set_cancel_token(Some(token.clone()));
+        view.search("file").unwrap();
+        assert_eq!(view.get_results().len(), 100);
+
+        worker.start(|_| Some(()));
+        assert!(token.is_cancelled());
+        view.reset_search_results();
+        view.search("file").unwrap();
+        view.fallback_search("file");
+        assert!(view.get_results().is_empty());
+        assert!(view.search_contents("file").is_empty());
+
+        // Changing the original copies the index rather than touching the view's.
+        search.insert_path(PathBuf::from("new.txt"));
+        assert_eq!(search.len(), 101);
+        assert_eq!(view.len(), 100);
+    }
+
+    #[test]
+    fn test_whole_word() {
+        let mut search = Search::new();
+        search.set_rank_results(false);
+        search.replace_index(entries(vec![
+            PathBuf::from("/docs/test.txt"),
+            PathBuf::from("/docs/testing.txt"),
+            PathBuf::from("/docs/contest.txt"),
+            PathBuf::from("/docs/test2.txt"),
+            PathBuf::from("/docs/unit_test.rs"),
+            PathBuf::from("/docs/TEST-plan.md"),
+            PathBuf::from("/docs/testtest test.txt"),
+        ]));
+        search.set_search_mode(SearchMode::Contains);
+        search.set_whole_word(true);
+        let names = |search: &mut Search, key: &str| -> Vec<String> {
+            search.reset_search_results();
+            search.search(key).unwrap();
+            search
+                .iter_results()
+                .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        assert_eq!(
+            names(&mut search, "test"),
+            [
+                "test.txt",
+                "unit_test.rs",
+                "TEST-plan.md",
+                "testtest test.txt"
+            ]
+        );
+        search.set_ignore_case(false);
+        assert_eq!(
+            names(&mut search, "test"),
+            ["test.txt", "unit_test.rs", "testtest test.txt"]
+        );
+        search.set_whole_word(false);
+        assert_eq!(names(&mut search, "test").len(), 6);
+    }
+
+    #[test]
+    fn test_insert_and_remove_path() {
+        let mut search = Search::new();
+        search.insert_path(PathBuf::from("a.txt"));
+        search.insert_path(PathBuf::from("b.txt"));
+        search.insert_path(PathBuf::from("a.txt"));
+        assert_eq!(search.len(), 2);
+
+        search.search("txt").unwrap();
+        assert!(search.remove_path(Path::new("a.txt")));
+        assert!(!search.remove_path(Path::new("a.txt")));
+        assert_eq!(index_paths(&search), vec![PathBuf::from("b.txt")]);
+        assert_eq!(search.get_results().len(), 1);
+
+        // Files sharing a name in different folders are removed one at a time.
+        search.insert_path(PathBuf::from("/x/same.txt"));
+        search.insert_path(PathBuf::from("/y/same.txt"));
+        search.reset_search_results();
+        search.search("same").unwrap();
+        assert!(search.remove_path(Path::new("/x/same.txt")));
+        assert_eq!(
+            index_paths(&search),
+            ["b.txt", "/y/same.txt"].map(PathBuf::from)
+        );
+        assert_eq!(search.get_results()[0].0, PathBuf::from("/y/same.txt"));
+        assert_eq!(search.get_results().len(), 1);
+    }
+
+    #[test]
+    fn test_complete_file_names() {
+        let mut search = Search::new();
+        search.replace_index(entries([
+            "/docs/Report_2024.pdf",
+            "/src/main.rs",
+            "/docs/readme.md",
+            "/old/report_2024.pdf",
+            "/docs/report.txt",
+            "/backup/report.txt",
+            "/reports/summary.txt",
+        ]));
+        assert_eq!(
+            search.complete("re", 10),
+            [
+                "readme.md",
+                "report.txt",
+                "Report_2024.pdf",
+                "report_2024.pdf"
+            ]
+        );
+        // The limit keeps the alphabetically first names, however they are indexed.
+        assert_eq!(search.complete("rep", 2), ["report.txt", "Report_2024.pdf"]);
+        assert!(search.complete("zzz", 10).is_empty());
+        assert!(search.complete("", 10).is_empty());
+        assert!(search.complete("re", 0).is_empty());
+
+        search.set_ignore_case(false);
+        assert_eq!(search.complete("Re", 10), ["Report_2024.pdf"]);
+    }
+
+    #[test]
+    fn test_merge_index() {
+        let file = |path: &str, size: u64| IndexedFile {
+            size,
+            ..IndexedFile::from(PathBuf::from(path))
+        };
+        // Into an empty index, dropping a file listed twice.
+        let mut search = Search::new();
+        search.merge_index(vec![
+            file("/a/1.txt", 1),
+            file("/a/2.txt", 2),
+            file("/a/1.txt", 3),
+        ]);
+        assert_eq!(
+            *search.get_index(),
+            [file("/a/1.txt", 3), file("/a/2.txt", 2)]
+        );
+
+        // Disjoint indexes are appended in order.
+        search.merge_index(vec![file("/b/1.txt", 4)]);
+        assert_eq!(
+            index_paths(&search),
+            ["/a/1.txt", "/a/2.txt", "/b/1.txt"].map(PathBuf::from)
+        );
+
+        // Overlapping ones keep each file once, with the metadata merged in.
+        search.merge_index(vec![file("/b/2.txt", 5), file("/a/2.txt", 6)]);
+        assert_eq!(
+            *search.get_index(),
+            [
+                file("/a/1.txt", 3),
+                file("/a/2.txt", 6),
+                file("/b/1.txt", 4),
+                file("/b/2.txt", 5)
+            ]
+        );
+        search.search("2").unwrap();
+        assert_eq!(search.get_results().len(), 2);
+    }
+
+    #[test]
+    fn test_fallback_search_finds_substring() {
+        let mut search = Search::new();
+        search.indexed_files = Arc::new(entries(vec![
+            PathBuf::from("annual_report_2023.pdf"),
+            PathBuf::from("report(1).txt"),
+            PathBuf::from("notes.txt"),
+        ]));
+        search.set_ignore_case(false);
+
+        search.search("REPORT").unwrap();
+        assert!(search.get_results().is_empty());
+        search.fallback_search("REPORT");
+        let found: Vec<_> = search
+            .get_results()
+            .iter()
+            .map(|(_, m)| m.as_str())
+            .collect();
+        assert_eq!(found, ["report", "report"]);
+
+        search.reset_search_results();
+        assert!(search.search("report(").is_err());
+        assert!(search.get_results().is_empty());
+        search.fallback_search("report(");
+        assert_eq!(
+            search.get_results(),
+            &vec![(PathBuf::from("report(1).txt"), "report(".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_search_contents() {
+        let dir = std::env::temp_dir().join("search_contents_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("notes.txt"),
+            "shopping list\nmilk\nBudget for May\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("other.txt"), "nothing to see\n").unwrap();
+        std::fs::write(dir.join("binary.txt"), [0x62, 0x75, 0x64, 0x67, 0xff, 0xfe]).unwrap();
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.generate_index().unwrap();
+        assert_eq!(
+            search.search_contents("budget"),
+            vec![(dir.join("notes.txt"), 3)]
+        );
+
+        search.set_content_size_limit(4);
+        assert!(search.search_contents("budget").is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_extension_filter() {
+        let mut search = Search::new();
+        search.replace_index(entries(vec![
+            PathBuf::from("/src/main.RS"),
+            PathBuf::from("/src/Cargo.toml"),
+            PathBuf::from("/src/main"),
+            PathBuf::from("/src/main.txt"),
+        ]));
+        search.set_extension_filter(parse_extension_list("rs, .TOML"));
+        search.search("").unwrap();
+        let found: Vec<&PathBuf> = search.iter_results().map(|(path, _)| path).collect();
+        assert_eq!(
+            found,
+            vec![
+                &PathBuf::from("/src/main.RS"),
+                &PathBuf::from("/src/Cargo.toml")
+            ]
+        );
+
+        search.reset_search_results();
+        search.set_extension_filter(Vec::new());
+        search.search("main").unwrap();
+        assert_eq!(search.get_results().len(), 3);
+    }
+
+    #[test]
+    fn test_excluded_dirs_are_not_indexed() {
+        let dir = std::env::temp_dir().join("search_exclude_dirs_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        for folder in ["src", "node_modules/pkg", "build/out", "cache-1", "docs"] {
+            std::fs::create_dir_all(dir.join(folder)).unwrap();
+            std::fs::write(dir.join(folder).join("file.txt"), "").unwrap();
+        }
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.set_exclude_dirs(vec![
+            PathBuf::from("NODE_MODULES"),
+            PathBuf::from("cache-*"),
+            dir.join("build"),
+        ]);
+        search.generate_index().unwrap();
+        let mut indexed = index_paths(&search);
+        indexed.sort();
+        assert_eq!(
+            indexed,
+            vec![
+                dir.join("docs").join("file.txt"),
+                dir.join("src").join("file.txt")
+            ]
+        );
+        assert!(!is_excluded_dir(
+            &[PathBuf::from("node_modules")],
+            Path::new("/a/node_modules_old")
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_gitignore_is_honored_when_enabled() {
+        let dir = std::env::temp_dir().join("search_gitignore_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("logs")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join(".gitignore"), "*.log\n/target/\n").unwrap();
+        std::fs::write(dir.join("logs").join(".gitignore"), "!keep.log\n").unwrap();
+        for file in [
+            "main.rs",
+            "debug.log",
+            "logs/trace.log",
+            "logs/keep.log",
+            "target/app",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.set_skip_hidden(false);
+        search.generate_index().unwrap();
+        assert_eq!(search.len(), 7);
+
+        search.set_respect_gitignore(true);
+        search.generate_index().unwrap();
+        let mut indexed = index_paths(&search);
+        indexed.sort();
+        assert_eq!(
+            indexed,
+            vec![
+                dir.join(".gitignore"),
+                dir.join("logs").join(".gitignore"),
+                dir.join("logs").join("keep.log"),
+                dir.join("main.rs"),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_dotfiles_are_skipped() {
+        let dir = std::env::temp_dir().join("search_dotfiles_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join(".git").join("HEAD"), "").unwrap();
+        std::fs::write(dir.join(".env"), "").unwrap();
+        std::fs::write(dir.join("visible.txt"), "").unwrap();
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.generate_index().unwrap();
+        assert_eq!(index_paths(&search), vec![dir.join("visible.txt")]);
+
+        search.set_skip_hidden(false);
+        search.generate_index().unwrap();
+        assert_eq!(search.len(), 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_hidden_and_system_files_are_skipped() {
+        let dir = std::env::temp_dir().join("search_hidden_files_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["desktop.ini", "hidden.txt", "visible.txt"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        for (flag, name) in [("+s", "desktop.ini"), ("+h", "hidden.txt")] {
+            let status = std::process::Command::new("attrib")
+                .arg(flag)
+                .arg(dir.join(name))
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.generate_index().unwrap();
+        assert_eq!(index_paths(&search), vec![dir.join("visible.txt")]);
+
+        search.set_skip_hidden(false);
+        search.generate_index().unwrap();
+        assert_eq!(search.len(), 3);
+        for name in ["desktop.ini", "hidden.txt"] {
+            let _ = std::process::Command::new("attrib")
+                .args(["-s", "-h"])
+                .arg(dir.join(name))
+                .status();
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_cycle_is_not_followed() {
+        let dir = std::env::temp_dir().join("search_symlink_cycle_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("a").join("b")).unwrap();
+        std::fs::write(dir.join("a").join("b").join("file.txt"), "").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("a").join("b").join("up")).unwrap();
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.generate_index().unwrap();
+        assert_eq!(
+            index_paths(&search),
+            vec![dir.join("a").join("b").join("file.txt")]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_junction_cycle_is_not_followed() {
+        let dir = std::env::temp_dir().join("search_junction_cycle_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        std::fs::write(dir.join("a").join("file.txt"), "").unwrap();
+        let status = std::process::Command::new("cmd")
+            .args(["/C", "mklink", "/J"])
+            .arg(dir.join("a").join("up"))
+            .arg(&dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.generate_index().unwrap();
+        assert_eq!(index_paths(&search), vec![dir.join("a").join("file.txt")]);
+        std::fs::remove_dir(dir.join("a").join("up")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parallel_index_equals_sequential() {
+        let dir = std::env::temp_dir().join("search_parallel_index_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        for top in 0..6 {
+            for sub in 0..3 {
+                let folder = dir.join(format!("top{}", top)).join(format!("sub{}", sub));
+                std::fs::create_dir_all(&folder).unwrap();
+                for file in 0..4 {
+                    std::fs::write(folder.join(format!("{}.txt", file)), "").unwrap();
+                }
+            }
+        }
+        std::fs::write(dir.join("root.txt"), "").unwrap();
+
+        let mut parallel = Search::new();
+        parallel.set_root_dir(dir.clone());
+        parallel.generate_index().unwrap();
+        let mut sequential = Search::new();
+        sequential.set_root_dir(dir.clone());
+        sequential
+            .generate_index_with_progress(usize::MAX, &mut |_| {})
+            .unwrap();
+
+        assert_eq!(parallel.len(), 6 * 3 * 4 + 1);
+        assert_eq!(parallel.get_index(), sequential.get_index());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_update_index_applies_changes() {
+        let dir = std::env::temp_dir().join("search_update_index_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        std::fs::write(dir.join("a").join("old.txt"), "v1").unwrap();
+        std::fs::write(dir.join("b").join("stays.txt"), "").unwrap();
+
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        search.generate_index().unwrap();
+        let sorted = |search: &Search| {
+            let mut files = index_paths(search);
+            files.sort();
+            files
+        };
+
+        std::fs::write(dir.join("a").join("new.txt"), "").unwrap();
+        std::fs::create_dir_all(dir.join("c")).unwrap();
+        std::fs::write(dir.join("c").join("deep.txt"), "").unwrap();
+        search.update_index().unwrap();
+        assert_eq!(
+            sorted(&search),
+            vec![
+                dir.join("a").join("new.txt"),
+                dir.join("a").join("old.txt"),
+                dir.join("b").join("stays.txt"),
+                dir.join("c").join("deep.txt"),
+            ]
+        );
+
+        std::fs::write(dir.join("a").join("old.txt"), "v2").unwrap();
+        assert!(!search.update_index().unwrap());
+        assert_eq!(search.len(), 4);
+
+        std::fs::remove_file(dir.join("a").join("old.txt")).unwrap();
+        std::fs::remove_dir_all(dir.join("c")).unwrap();
+        search.update_index().unwrap();
+        assert_eq!(
+            sorted(&search),
+            vec![
+                dir.join("a").join("new.txt"),
+                dir.join("b").join("stays.txt")
+            ]
+        );
+
+        // Folders whose time did not change are not read again.
+        #[cfg(unix)]
+        {
+            let time = modified_time(&dir.join("b")).unwrap();
+            std::fs::write(dir.join("b").join("unseen.txt"), "").unwrap();
+            File::open(dir.join("b"))
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+            search.update_index().unwrap();
+            assert!(!index_paths(&search).contains(&dir.join("b").join("unseen.txt")));
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_saved_index_is_compressed() {
+        let mut search = Search::new();
+        search.set_root_dir(PathBuf::from("search_compressed_index_test"));
+        let files: Vec<PathBuf> = (0..1000)
+            .map(|i| {
+                PathBuf::from(format!(
+                    "C:\\Users\\me\\Documents\\Projects\\{}\\notes.txt",
+                    i
+                ))
+            })
+            .collect();
+        search.replace_index(entries(files.clone()));
+        search.save_index().unwrap();
+
+        let index_file = search.get_index_file();
+        let compressed = std::fs::metadata(&index_file).unwrap().len();
+        let plain = bincode::serialize(search.get_index()).unwrap();
+        assert!(compressed * 4 < plain.len() as u64);
+
+        let mut reloaded = Search::new();
+        reloaded.set_root_dir(PathBuf::from("search_compressed_index_test"));
+        reloaded.load_index().unwrap();
+        assert_eq!(index_paths(&reloaded), files);
+
+        // Indexes saved before compression, which only hold the paths, still load.
+        std::fs::write(&index_file, bincode::serialize(&files).unwrap()).unwrap();
+        reloaded.load_index().unwrap();
+        assert_eq!(*reloaded.get_index(), entries(files));
+        std::fs::remove_file(index_file).unwrap();
+    }
+
+    #[test]
+    fn test_failed_write_keeps_the_previous_index_file() {
+        let mut search = Search::new();
+        search.set_root_dir(PathBuf::from("/search_atomic_write_test"));
+        search.replace_index(entries(["/good.txt"]));
+        search.save_index().unwrap();
+        let index_file = PathBuf::from(search.get_index_file());
+        let temp_file = PathBuf::from(format!("{}.tmp", search.get_index_file()));
+        let good = std::fs::read(&index_file).unwrap();
+        assert!(!temp_file.exists());
+
+        let failed = write_atomically(&index_file, |writer| {
+            writer.write_all(b"half an index")?;
+            Err(io::Error::other("serialization failed"))
+        });
+        assert!(failed.is_err());
+        assert_eq!(std::fs::read(&index_file).unwrap(), good);
+        assert!(!temp_file.exists());
+
+        search.replace_index(entries(["/newer.txt"]));
+        search.save_index().unwrap();
+        search.load_index().unwrap();
+        assert_eq!(index_paths(&search), [PathBuf::from("/newer.txt")]);
+        std::fs::remove_file(&index_file).unwrap();
+    }
+
+    #[test]
+    fn test_index_versions() {
+        let root = PathBuf::from("search_index_version_test");
+        let files = vec![PathBuf::from("C:\\a.txt"), PathBuf::from("C:\\b\\c.txt")];
+        let mut search = Search::new();
+        search.set_root_dir(root.clone());
+        search.replace_index(entries(files.clone()));
+        search.save_index().unwrap();
+        let index_file = search.get_index_file();
+        let current = std::fs::read(&index_file).unwrap();
+        assert_eq!(&current[..4], INDEX_MAGIC);
+        assert_eq!(current[4..8], INDEX_VERSION.to_le_bytes());
+
+        // Versions 1 and 2 hold the compressed paths, version 1 without the version
+        // number. Both load without metadata and are written as the current version
+        // when saved again.
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        bincode::serialize_into(&mut encoder, &files).unwrap();
+        let paths = encoder.finish().unwrap();
+        for header in [&b"SIDX"[..], b"SIDX\x02\0\0\0"] {
+            std::fs::write(&index_file, [header, &paths].concat()).unwrap();
+            let mut reloaded = Search::new();
+            reloaded.set_root_dir(root.clone());
+            reloaded.load_index().unwrap();
+            assert_eq!(*reloaded.get_index(), entries(files.clone()));
+            reloaded.save_index().unwrap();
+            assert_eq!(std::fs::read(&index_file).unwrap(), current);
+        }
+        // Version 3 entries have no creation time.
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let v3: Vec<(PathBuf, u64, SystemTime)> = files
+            .iter()
+            .map(|file| (file.clone(), 7, modified))
+            .collect();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        bincode::serialize_into(&mut encoder, &v3).unwrap();
+        let v3 = encoder.finish().unwrap();
+        std::fs::write(&index_file, [&b"SIDX\x03\0\0\0"[..], &v3].concat()).unwrap();
+        let mut reloaded = Search::new();
+        reloaded.set_root_dir(root.clone());
+        reloaded.load_index().unwrap();
+        let expected: Vec<IndexedFile> = files
+            .iter()
+            .map(|file| IndexedFile {
+                size: 7,
+                modified,
+                ..IndexedFile::from(file.clone())
+            })
+            .collect();
+        assert_eq!(*reloaded.get_index(), expected);
+
+        let mut bogus = current.clone();
+        bogus[4..8].copy_from_slice(&99u32.to_le_bytes());
+        std::fs::write(&index_file, bogus).unwrap();
+        assert!(matches!(
+            reloaded.load_index(),
+            Err(SearchError::InvalidIndex(e)) if e == "Unsupported index version 99"
+        ));
+        assert!(reloaded.get_index().is_empty());
+        std::fs::remove_file(index_file).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_index_loads_empty() {
+        let root = PathBuf::from("search_corrupt_index_test");
+        let mut search = Search::new();
+        search.set_root_dir(root.clone());
+        search.replace_index(entries(vec![PathBuf::from("C:\\a.txt"); 100]));
+        search.save_index().unwrap();
+        let index_file = search.get_index_file();
+        let saved = std::fs::read(&index_file).unwrap();
+
+        let garbage: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let truncated = saved[..saved.len() / 2].to_vec();
+        let mut header_only = INDEX_MAGIC.to_vec();
+        header_only.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        for contents in [garbage, truncated, header_only, Vec::new()] {
+            std::fs::write(&index_file, contents).unwrap();
+            let mut reloaded = Search::new();
+            reloaded.set_root_dir(root.clone());
+            reloaded.replace_index(entries(vec![PathBuf::from("stale.txt")]));
+            assert!(reloaded.load_index().is_err());
+            assert!(reloaded.get_index().is_empty());
+        }
+        std::fs::remove_file(index_file).unwrap();
+    }
+
+    #[test]
+    fn test_results_to_json() {
+        let dir = std::env::temp_dir().join("search_results_json_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a \"quoted\" name.txt");
+        std::fs::write(&file, "hello").unwrap();
+        let modified = file
+            .metadata()
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let missing = dir.join("missing.txt");
+        let mut search = Search::new();
+        search.replace_index(vec![
+            IndexedFile::read(file.clone()),
+            IndexedFile::read(missing.clone()),
+        ]);
+        search.set_rank_results(false);
+        search.search_substring(".txt").unwrap();
+        // The metadata comes from the index, not the file system.
+        std::fs::remove_file(&file).unwrap();
+
+        let path = file
+            .to_string_lossy()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        let missing = missing.to_string_lossy().replace('\\', "\\\\");
+        assert_eq!(
+            search.results_to_json(),
+            format!(
+                r#"[{{"path":"{}","name":"a \"quoted\" name.txt","size":5,"modified":{}}},{{"path":"{}","name":"missing.txt"}}]"#,
+                path, modified, missing
+            )
+        );
+        search.reset_search_results();
+        assert_eq!(search.results_to_json(), "[]");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_results_ranked_by_score() {
+        let mut search = Search::new();
+        search.replace_index(entries(vec![
+            PathBuf::from("/src/domain_rules.rs"),
+            PathBuf::from("/main/readme.txt"),
+            PathBuf::from("/src/mainframe.rs"),
+            PathBuf::from("/src/main.rs"),
+        ]));
+        search.set_haystack(Haystack::FullPath);
+        search.search("main").unwrap();
+        let ranked: Vec<&Path> = search
+            .iter_results()
+            .map(|(path, _)| path.as_path())
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                Path::new("/src/main.rs"),
+                Path::new("/src/mainframe.rs"),
+                Path::new("/src/domain_rules.rs"),
+                Path::new("/main/readme.txt"),
+            ]
+        );
+        let scores: Vec<u32> = search
+            .scored_results()
+            .iter()
+            .map(|result| result.score)
+            .collect();
+        assert!(scores.windows(2).all(|pair| pair[0] > pair[1]));
+
+        search.reset_search_results();
+        search.set_rank_results(false);
+        search.search("main").unwrap();
+        assert_eq!(
+            search.iter_results().next().unwrap().0,
+            PathBuf::from("/src/domain_rules.rs")
+        );
+    }
+
+    #[test]
+    fn test_query_filters_apply_before_the_results_limit() {
+        let mut search = Search::new();
+        let mut paths: Vec<PathBuf> = (0..20)
+            .map(|i| PathBuf::from(format!("/docs/report{}.txt", i)))
+            .collect();
+        paths.push(PathBuf::from("/photos/report.png"));
+        paths.push(PathBuf::from("D:\\report.txt"));
+        search.replace_index(entries(paths));
+        search.set_search_results_limit(5);
+
+        let (key, filters) = QueryFilters::parse("report category:image");
+        search.set_query_filters(filters, Categories::default());
+        search.search(&key).unwrap();
+        assert_eq!(
+            search.get_results(),
+            &vec![(PathBuf::from("/photos/report.png"), "report".to_string())]
+        );
+
+        search.reset_search_results();
+        let (key, filters) = QueryFilters::parse("report -report1* -report2* -report3*");
+        search.set_query_filters(filters, Categories::default());
+        search.set_drive_filter(Some(PathBuf::from("D:")));
+        search.search(&key).unwrap();
+        assert_eq!(search.get_results().len(), 1);
+        assert_eq!(search.get_results()[0].0, PathBuf::from("D:\\report.txt"));
+    }
+
+    #[test]
+    fn test_ranking_looks_past_the_results_limit() {
+        let mut search = Search::new();
+        let mut paths: Vec<PathBuf> = (0..50)
+            .map(|i| PathBuf::from(format!("/src/domain_{}.rs", i)))
+            .collect();
+        paths.push(PathBuf::from("/src/main.rs"));
+        paths.push(PathBuf::from("/src/mainframe.rs"));
+        search.replace_index(entries(paths));
+        search.set_search_results_limit(3);
+        search.search("main").unwrap();
+        let ranked: Vec<&Path> = search
+            .iter_results()
+            .map(|(path, _)| path.as_path())
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                Path::new("/src/main.rs"),
+                Path::new("/src/mainframe.rs"),
+                Path::new("/src/domain_0.rs"),
+            ]
+        );
+        assert!(search.results_truncated());
+        assert_eq!(search.take_match_ranges().len(), 3);
+
+        search.reset_search_results();
+        search.fallback_search("MAIN");
+        assert_eq!(search.get_results()[0].0, PathBuf::from("/src/main.rs"));
+        assert_eq!(search.get_results().len(), 3);
+
+        search.reset_search_results();
+        search.set_rank_results(false);
+        search.search("main").unwrap();
+        assert_eq!(search.get_results()[0].0, PathBuf::from("/src/domain_0.rs"));
+        assert!(search.results_truncated());
+    }
+
+    #[test]
+    fn test_count_files() {
+        let dir = std::env::temp_dir().join("search_count_files_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+        std::fs::write(dir.join("sub").join("b.txt"), "").unwrap();
+        let mut search = Search::new();
+        search.set_root_dir(dir.clone());
+        assert_eq!(search.count_files(), 0);
+        search.generate_index().unwrap();
+        assert_eq!(search.count_files(), 2);
+        if cfg!(windows) {
+            search.insert_path(stream_entry(&dir.join("a.txt"), "Zone.Identifier"));
+            assert_eq!(search.len(), 3);
+            assert_eq!(search.count_files(), 2);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
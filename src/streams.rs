@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+/// Whether NTFS alternate data streams are indexed when `configured` asks for
+/// them. Always `false` unless built with the `ads` feature for Windows.
+pub(crate) fn streams_enabled(configured: bool) -> bool {
+    cfg!(all(windows, feature = "ads")) && configured
+}
+
+/// The synthetic index entry for stream `stream` of `file`, e.g. `file.txt:stream`.
+pub(crate) fn stream_entry(file: &Path, stream: &str) -> PathBuf {
+    let mut entry = file.as_os_str().to_os_string();
+    entry.push(":");
+    entry.push(stream);
+    PathBuf::from(entry)
+}
+
+/// Whether `entry` was made by [`stream_entry`] rather than being a file. Only
+/// Windows names streams this way, since `:` cannot occur in its file names.
+pub(crate) fn is_stream_entry(entry: &Path) -> bool {
+    cfg!(windows)
+        && entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().contains(':'))
+}
+
+/// Extracts the name from a raw stream name such as `:stream:$DATA`. The unnamed
+/// default stream `::$DATA` yields `None`.
+#[cfg(any(all(windows, feature = "ads"), test))]
+fn parse_stream_name(raw: &str) -> Option<&str> {
+    let name = raw.strip_prefix(':')?.strip_suffix(":$DATA")?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// Names of the alternate data streams of `file`.
+#[cfg(all(windows, feature = "ads"))]
+pub(crate) fn alternate_streams(file: &Path) -> Vec<String> {
+    use std::{ffi::c_void, os::windows::ffi::OsStrExt};
+    use windows_sys::Win32::{
+        Foundation::INVALID_HANDLE_VALUE,
+        Storage::FileSystem::{
+            FindClose, FindFirstStreamW, FindNextStreamW, FindStreamInfoStandard,
+            WIN32_FIND_STREAM_DATA,
+        },
+    };
+
+    let wide: Vec<u16> = file.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut data: WIN32_FIND_STREAM_DATA = unsafe { std::mem::zeroed() };
+    let handle = unsafe {
+        FindFirstStreamW(
+            wide.as_ptr(),
+            FindStreamInfoStandard,
+            &mut data as *mut _ as *mut c_void,
+            0,
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Vec::new();
+    }
+    let mut streams = Vec::new();
+    loop {
+        let len = data
+            .cStreamName
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(data.cStreamName.len());
+        let raw = String::from_utf16_lossy(&data.cStreamName[..len]);
+        if let Some(name) = parse_stream_name(&raw) {
+            streams.push(name.to_string());
+        }
+        if unsafe { FindNextStreamW(handle, &mut data as *mut _ as *mut c_void) } == 0 {
+            break;
+        }
+    }
+    unsafe { FindClose(handle) };
+    streams
+}
+
+#[cfg(not(all(windows, feature = "ads")))]
+pub(crate) fn alternate_streams(_file: &Path) -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_name() {
+        assert_eq!(
+            parse_stream_name(":Zone.Identifier:$DATA"),
+            Some("Zone.Identifier")
+        );
+        assert_eq!(parse_stream_name("::$DATA"), None);
+        assert_eq!(parse_stream_name("stream"), None);
+    }
+
+    #[test]
+    fn test_stream_entry() {
+        let entry = stream_entry(Path::new("docs/file.txt"), "notes");
+        assert_eq!(entry.file_name().unwrap(), "file.txt:notes");
+    }
+
+    #[cfg(not(all(windows, feature = "ads")))]
+    #[test]
+    fn test_streams_are_a_no_op_without_the_feature() {
+        assert!(alternate_streams(Path::new("Cargo.toml")).is_empty());
+        assert!(!streams_enabled(true));
+    }
+
+    #[cfg(all(windows, feature = "ads"))]
+    #[test]
+    fn test_index_named_stream() {
+        use crate::search_engine::{Search, SearchEngine};
+
+        let dir = std::env::temp_dir().join("search_ads_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("file.txt");
+        std::fs::write(&file, "main").unwrap();
+        std::fs::write(stream_entry(&file, "hidden"), "stream").unwrap();
+        assert_eq!(alternate_streams(&file), vec!["hidden".to_string()]);
+
+        let mut engine = Search::new();
+        engine.set_root_dir(dir.clone());
+        engine.set_index_streams(true);
+        engine.generate_index().unwrap();
+        engine.search("hidden");
+        assert_eq!(engine.get_results()[0].0, stream_entry(&file, "hidden"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
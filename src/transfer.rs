@@ -0,0 +1,92 @@
+//! Compact binary encoding of search results for programmatic consumers.
+//!
+//! The stream is a sequence of frames. Each frame is a little-endian `u32` byte
+//! length followed by that many bytes of a bincode-encoded [`ResultRecord`]:
+//!
+//! ```text
+//! frame   := len:u32le record:[u8; len]
+//! record  := path:string matched:string     (bincode 1.x, default options)
+//! string  := len:u64le utf8:[u8; len]
+//! stream  := frame* 0u32le
+//! ```
+//!
+//! A zero length marks the end of a result set, so several result sets can be sent
+//! over one socket. Paths that are not valid UTF-8 are encoded lossily.
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct ResultRecord {
+    path: String,
+    matched: String,
+}
+
+/// Writes `results` to `writer` as one terminated result set.
+pub fn write_results<W: Write>(mut writer: W, results: &[(PathBuf, String)]) -> io::Result<()> {
+    for (path, matched) in results {
+        let record = ResultRecord {
+            path: path.to_string_lossy().into_owned(),
+            matched: matched.clone(),
+        };
+        let bytes = bincode::serialize(&record).map_err(io::Error::other)?;
+        let len = u32::try_from(bytes.len()).map_err(io::Error::other)?;
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(&bytes)?;
+    }
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.flush()
+}
+
+/// Reads one terminated result set from `reader`.
+pub fn read_results<R: Read>(mut reader: R) -> io::Result<Vec<(PathBuf, String)>> {
+    let mut results = Vec::new();
+    loop {
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len == 0 {
+            return Ok(results);
+        }
+        let mut bytes = vec![0u8; len];
+        reader.read_exact(&mut bytes)?;
+        let record: ResultRecord = bincode::deserialize(&bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        results.push((PathBuf::from(record.path), record.matched));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let results = vec![
+            (
+                PathBuf::from("C:\\Users\\me\\report.pdf"),
+                "report".to_string(),
+            ),
+            (PathBuf::from("/home/me/照片.jpg"), "照片".to_string()),
+        ];
+        let mut buf = Vec::new();
+        write_results(&mut buf, &results).unwrap();
+        write_results(&mut buf, &[]).unwrap();
+
+        let mut reader = buf.as_slice();
+        assert_eq!(read_results(&mut reader).unwrap(), results);
+        assert!(read_results(&mut reader).unwrap().is_empty());
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_truncated_stream_is_an_error() {
+        let mut buf = Vec::new();
+        write_results(&mut buf, &[(PathBuf::from("a.txt"), "a".to_string())]).unwrap();
+        buf.truncate(buf.len() - 6);
+        assert!(read_results(buf.as_slice()).is_err());
+    }
+}
@@ -1,53 +1,250 @@
 use std::{
+    collections::HashMap,
     fs::File,
-    io::{Read, Write},
-    ops::AddAssign,
-    path::PathBuf,
+    io::Read,
+    ops::{AddAssign, Range},
+    path::{Path, PathBuf},
     process::Command,
-    sync::mpsc::Sender,
-    time::{Duration, SystemTime},
+    sync::mpsc::{Receiver, Sender},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime},
 };
 
-use crate::search_engine::{Search, SearchEngine};
-use egui::{FontDefinitions, FontFamily};
+use crate::blacklist::Blacklist;
+use crate::category::Categories;
+use crate::command::{parse_update_interval, AppCommand, CommandRegistry, COMMAND_SIGIL};
+use crate::config::{Config, Theme, CONFIG_FILE};
+use crate::csv::write_results_csv;
+use crate::debounce::Debounce;
+use crate::drives::{default_roots, DEFAULT_DRIVE_KINDS};
+use crate::file_ops::{
+    copy_plan, execute_copy_plan, open_elevated, rename_indexed, trash_indexed, FileAction,
+};
+use crate::filter::QueryFilters;
+use crate::fonts::{load_font, setup_custom_fonts, FONT_FILE};
+use crate::history::{SearchHistory, HISTORY_FILE};
+use crate::icons::{load_icon_atlas, FileIcon, ICON_SIZE};
+use crate::message::EngineMessage;
+use crate::preview::{load_preview, Preview};
+use crate::results::{
+    cap_per_directory, count_by_directory, dedup_case_insensitive, group_by_directory,
+    highlight_segments, parent_dir, result_drives, DirectoryCap,
+};
+use crate::saved::{SavedSearch, SavedSearches, SearchOptions, SAVED_SEARCHES_FILE};
+use crate::search_engine::{
+    parse_extension_list, parse_path_list, Haystack, IndexMode, IndexProgress, IndexSnapshot,
+    Search, SearchEngine, SearchMode, UPDATE_TIME_FILE,
+};
+use crate::worker::{join_with_timeout, CancelToken, SearchWorker};
+
+/// How often an in-memory index is written to disk while the app is running.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often the index is polled while it is still loading.
+const LOADING_REPAINT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often a search running in the background is checked for its results.
+const SEARCH_POLL_INTERVAL: Duration = Duration::from_millis(30);
+
+/// Default for the most results a search returns, the engine's
+/// [`set_search_results_limit`](SearchEngine::set_search_results_limit).
+const DEFAULT_MAX_RESULTS: usize = 10_000;
+
+/// Most file names suggested below the search box while typing.
+const SUGGESTION_LIMIT: usize = 8;
+
+/// How long closing the window waits for the background threads to stop.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Represents the main application structure for the search functionality.
 pub struct SearchApp {
     search_command: String,
+    suggestions: Vec<String>,
+    suggestion_index: Option<usize>,
+    suggest_next_search: bool,
     search_results: Vec<(PathBuf, String)>,
+    match_ranges: HashMap<PathBuf, Vec<Range<usize>>>,
     search_engine: Search,
     display_dialog: bool,
     root_directory: String,
+    root_directories: Vec<String>,
+    /// Whether a root was added or removed in the settings, so that the roots are
+    /// saved. Otherwise `config.toml` keeps none and drives are followed.
+    roots_edited: bool,
     notification_message: Option<String>,
-    message_sender: Option<Sender<String>>,
+    message_sender: Option<Sender<EngineMessage>>,
+    background_threads: Vec<JoinHandle<()>>,
     loading_status: bool,
     updating_status: bool,
     last_active_time: SystemTime,
     current_active_time: SystemTime,
     avg_suspend_duration: Duration,
+    /// The update interval being typed in the settings window, in seconds.
+    interval_input: String,
+    /// Why `interval_input` was not applied, shown next to it.
+    interval_error: Option<String>,
+    /// Where a newly set update interval is saved, `updateTime.ini` by default.
+    update_time_file: PathBuf,
+    categories: Categories,
+    category_filter: Option<String>,
+    fallback_used: bool,
+    pending_file_action: Option<FileAction>,
+    show_directory_panel: bool,
+    /// Whether results are listed under one collapsible header per folder.
+    group_results: bool,
+    directory_counts: Vec<(PathBuf, usize)>,
+    directory_filter: Option<PathBuf>,
+    /// The drives or roots the results are on, offered by the drive filter.
+    result_drives: Vec<PathBuf>,
+    /// Searches only the files on this drive, see
+    /// [`result_drive`](crate::results::result_drive).
+    drive_filter: Option<PathBuf>,
+    scope_input: String,
+    scope_len: usize,
+    per_directory_limit: usize,
+    merge_case_variants: bool,
+    max_results: usize,
+    results_truncated: bool,
+    directory_cap: DirectoryCap,
+    commands: CommandRegistry,
+    index_mode: IndexMode,
+    index_receiver: Option<Receiver<IndexSnapshot>>,
+    progress_receiver: Option<Receiver<IndexProgress>>,
+    index_progress: Option<IndexProgress>,
+    indexing_paused: bool,
+    partial_index: bool,
+    last_query: Option<String>,
+    refine_base: Option<(String, SearchOutcome)>,
+    pattern_error: bool,
+    last_snapshot: Instant,
+    ignore_separators: bool,
+    match_case: bool,
+    /// Whether closing the window minimizes it, keeping indexing running.
+    minimize_on_close: bool,
+    /// Set by [`AppCommand::Quit`] to close the window for real.
+    quit_requested: bool,
+    whole_word: bool,
+    rank_results: bool,
+    idle_repaint_interval: Duration,
+    search_debounce: Debounce,
+    search_worker: SearchWorker<SearchOutcome>,
+    search_mode: SearchMode,
+    haystack: Haystack,
+    allow_elevated_open: bool,
+    selected_results: Vec<PathBuf>,
+    copy_destination: String,
+    csv_export_path: String,
+    search_file_contents: bool,
+    content_matches: Vec<(PathBuf, usize)>,
+    extension_input: String,
+    skip_hidden: bool,
+    history: SearchHistory,
+    /// Where the history is saved, `history.txt` by default.
+    history_file: PathBuf,
+    saved_searches: SavedSearches,
+    saved_search_name: String,
+    selected_index: Option<usize>,
+    scroll_to_selected: bool,
+    show_preview: bool,
+    preview: Option<(PathBuf, Preview)>,
+    file_icons: Option<egui::TextureHandle>,
+    config: Option<Config>,
+    /// Where the settings are saved, `config.toml` by default.
+    config_file: PathBuf,
 }
 
 impl Default for SearchApp {
     fn default() -> Self {
         let mut update_interval = 600;
-        if let Ok(mut file) = File::open("updateTime.ini") {
+        if let Ok(mut file) = File::open(UPDATE_TIME_FILE) {
             let mut buffer = String::new();
             file.read_to_string(&mut buffer).unwrap();
             update_interval = buffer.parse::<u64>().unwrap_or(600);
         }
+        let root_directories = root_names(&default_roots(DEFAULT_DRIVE_KINDS));
+        let mut search_engine = Search::new();
+        set_engine_roots(&mut search_engine, &root_directories);
         SearchApp {
             search_command: String::new(),
+            suggestions: Vec::new(),
+            suggestion_index: None,
+            suggest_next_search: false,
             search_results: Vec::new(),
-            search_engine: Search::new(),
+            match_ranges: HashMap::new(),
+            search_engine,
             display_dialog: false,
-            root_directory: String::from("C:\\"),
+            root_directory: String::new(),
+            root_directories,
+            roots_edited: false,
             notification_message: None,
             message_sender: None,
+            background_threads: Vec::new(),
             loading_status: false,
             updating_status: false,
             last_active_time: SystemTime::now(),
             current_active_time: SystemTime::now(),
             avg_suspend_duration: Duration::from_secs(update_interval),
+            interval_input: update_interval.to_string(),
+            interval_error: None,
+            update_time_file: PathBuf::from(UPDATE_TIME_FILE),
+            categories: Categories::default(),
+            category_filter: None,
+            fallback_used: false,
+            pending_file_action: None,
+            show_directory_panel: false,
+            group_results: false,
+            directory_counts: Vec::new(),
+            directory_filter: None,
+            result_drives: Vec::new(),
+            drive_filter: None,
+            scope_input: String::new(),
+            scope_len: 0,
+            per_directory_limit: 0,
+            merge_case_variants: cfg!(windows),
+            max_results: DEFAULT_MAX_RESULTS,
+            results_truncated: false,
+            directory_cap: DirectoryCap::default(),
+            commands: CommandRegistry::default(),
+            index_mode: IndexMode::default(),
+            index_receiver: None,
+            progress_receiver: None,
+            index_progress: None,
+            indexing_paused: false,
+            partial_index: false,
+            last_query: None,
+            refine_base: None,
+            pattern_error: false,
+            last_snapshot: Instant::now(),
+            ignore_separators: false,
+            match_case: false,
+            minimize_on_close: false,
+            quit_requested: false,
+            whole_word: false,
+            rank_results: true,
+            idle_repaint_interval: Duration::from_secs(5),
+            search_debounce: Debounce::default(),
+            search_worker: SearchWorker::default(),
+            search_mode: SearchMode::default(),
+            haystack: Haystack::default(),
+            allow_elevated_open: false,
+            selected_results: Vec::new(),
+            copy_destination: String::new(),
+            csv_export_path: String::from("results.csv"),
+            search_file_contents: false,
+            content_matches: Vec::new(),
+            extension_input: String::new(),
+            skip_hidden: true,
+            history: SearchHistory::default(),
+            history_file: PathBuf::from(HISTORY_FILE),
+            saved_searches: SavedSearches::default(),
+            saved_search_name: String::new(),
+            selected_index: None,
+            scroll_to_selected: false,
+            show_preview: false,
+            preview: None,
+            file_icons: None,
+            config: None,
+            config_file: PathBuf::from(CONFIG_FILE),
         }
     }
 }
@@ -58,34 +255,776 @@ pub(crate) trait SearchAppEngine {
     fn render_settings_window(&mut self, ctx: &egui::Context, ui: &mut egui::Ui);
     fn render_search_input(&mut self, ui: &mut egui::Ui);
     fn render_loading_status(&mut self, ui: &mut egui::Ui);
+    fn render_file_action_window(&mut self, ctx: &egui::Context);
+    fn render_directory_panel(&mut self, ctx: &egui::Context);
+    fn render_preview_panel(&mut self, ctx: &egui::Context);
+    fn render_selection_bar(&mut self, ui: &mut egui::Ui);
+    fn render_content_matches(&mut self, ui: &mut egui::Ui);
+    fn toggle_selected(&mut self, path: PathBuf);
+    fn apply_file_action(&mut self, action: FileAction);
     fn update_interface(&mut self, ctx: &egui::Context);
+    fn handle_navigation_keys(&mut self, ctx: &egui::Context);
+    fn visible_result_indices(&self) -> Vec<usize>;
+    fn move_selection(&mut self, forward: bool);
+    fn open_selected(&mut self) -> bool;
     fn execute_search(&mut self);
-    fn set_message_sender(&mut self, sender: Sender<String>);
+    fn receive_search(&mut self);
+    fn rerun_last_search(&mut self);
+    fn submit_search(&mut self);
+    fn set_search_within_results(&mut self, enabled: bool);
+    fn accept_suggestion(&mut self) -> bool;
+    fn search_history(&self) -> &[String];
+    fn saved_searches(&self) -> &[SavedSearch];
+    fn add_saved_search(&mut self, name: &str);
+    fn remove_saved_search(&mut self, name: &str);
+    fn run_saved_search(&mut self, search: SavedSearch);
+    fn run_command(&mut self, command: AppCommand);
+    fn set_message_sender(&mut self, sender: Sender<EngineMessage>);
+    fn set_background_threads(&mut self, threads: Vec<JoinHandle<()>>);
+    fn set_index_receiver(&mut self, receiver: Receiver<IndexSnapshot>);
+    fn receive_index(&mut self);
+    fn set_progress_receiver(&mut self, receiver: Receiver<IndexProgress>);
+    fn receive_progress(&mut self);
+    fn snapshot_index(&mut self, now: Instant);
     fn new(cc: &eframe::CreationContext<'_>) -> Self;
     fn refresh_index(&self);
     fn validate_index(&mut self);
     fn update_avg_suspend_duration(&mut self);
 }
 
+/// A search to run on a background thread, with everything it needs from the UI.
+struct SearchJob {
+    engine: Search,
+    key: String,
+    search_contents: bool,
+    merge_case_variants: bool,
+    /// How many file names starting with the key to suggest; 0 for none.
+    suggestion_limit: usize,
+}
+
+/// The results of a finished [`SearchJob`], before the UI caps them.
+struct SearchOutcome {
+    results: Vec<(PathBuf, String)>,
+    match_ranges: HashMap<PathBuf, Vec<Range<usize>>>,
+    content_matches: Vec<(PathBuf, usize)>,
+    fallback_used: bool,
+    /// Whether the engine stopped at its result limit with more matches left.
+    truncated: bool,
+    pattern_error: Option<String>,
+    suggestions: Vec<String>,
+}
+
+impl SearchJob {
+    /// Runs the search, giving up with `None` as soon as `token` is cancelled.
+    fn run(mut self, token: &CancelToken) -> Option<SearchOutcome> {
+        self.engine.set_cancel_token(Some(token.clone()));
+        let pattern_error = self.engine.search(&self.key).err().map(|e| e.to_string());
+        let fallback_used = self.engine.get_results().is_empty() && !self.key.is_empty();
+        if fallback_used {
+            self.engine.fallback_search(&self.key);
+        }
+        let truncated = self.engine.results_truncated();
+        let mut results = self.engine.take_results();
+        let match_ranges = self.engine.take_match_ranges();
+        let content_matches = if self.search_contents && !self.key.is_empty() {
+            self.engine.search_contents(&self.key)
+        } else {
+            Vec::new()
+        };
+        let mut suggestions = self.engine.complete(&self.key, self.suggestion_limit);
+        // Completing to what was typed already offers nothing.
+        suggestions.retain(|name| *name != self.key);
+        if token.is_cancelled() {
+            return None;
+        }
+        if self.merge_case_variants {
+            dedup_case_insensitive(&mut results);
+        }
+        Some(SearchOutcome {
+            results,
+            match_ranges,
+            content_matches,
+            fallback_used,
+            truncated,
+            pattern_error,
+            suggestions,
+        })
+    }
+}
+
+impl SearchApp {
+    /// Draws the result at `index` as one row: its icon, the file name with the
+    /// matched parts in bold, and the buttons to reveal and copy it. Clicking the
+    /// name opens the file, or toggles its selection with the command key held.
+    fn render_result_row(
+        &self,
+        ui: &mut egui::Ui,
+        index: usize,
+        row_height: f32,
+        requested_action: &mut Option<FileAction>,
+        toggled_selection: &mut Option<PathBuf>,
+    ) -> egui::Response {
+        let (path, _) = &self.search_results[index];
+        let selected = self.selected_index == Some(index);
+        // Reserve a shape below the row so the highlight is drawn behind it.
+        let highlight = ui.painter().add(egui::Shape::Noop);
+        let row = ui.horizontal(|ui| {
+            ui.set_min_height(row_height);
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            let file_name = format!("-{} ", file_name);
+            // Shifted past the leading dash.
+            let ranges: Vec<Range<usize>> = self
+                .match_ranges
+                .get(path)
+                .into_iter()
+                .flatten()
+                .map(|range| range.start + 1..range.end + 1)
+                .collect();
+            let default_visuals = ui.visuals().clone();
+            let file_path = path.to_str().unwrap();
+            let select_clicked = ui.input(|i| i.modifiers.command);
+            if let Some(atlas) = &self.file_icons {
+                let icon = FileIcon::for_file(path, &self.categories);
+                ui.add(file_icon(atlas, icon));
+            }
+            if self.selected_results.contains(path) {
+                ui.strong("✔");
+            }
+            for (part, matched) in highlight_segments(&file_name, &ranges) {
+                let label = if matched {
+                    ui.strong(part)
+                } else {
+                    ui.label(part)
+                };
+                if label.clicked() {
+                    if select_clicked {
+                        *toggled_selection = Some(path.clone());
+                    } else {
+                        let _ = open::that_detached(file_path);
+                    }
+                }
+                label
+                    .clone()
+                    .on_hover_cursor(egui::CursorIcon::PointingHand);
+                ui.add_space(-8.5);
+                label.on_hover_text(file_path).context_menu(|ui| {
+                    result_context_menu(
+                        ui,
+                        path,
+                        self.allow_elevated_open,
+                        requested_action,
+                        toggled_selection,
+                    )
+                });
+            }
+            ui.visuals_mut().override_text_color = Some(default_visuals.hyperlink_color);
+            if !self.search_command.is_empty() {
+                ui.add_space(1.0);
+                let explorer_button = ui
+                    .label("σ")
+                    .on_hover_cursor(egui::CursorIcon::PointingHand);
+                if explorer_button.clicked() {
+                    reveal_in_file_manager(path);
+                }
+            }
+            ui.add_space(1.0);
+            let copy_button = ui
+                .label("📋")
+                .on_hover_cursor(egui::CursorIcon::PointingHand)
+                .on_hover_text("Copy path (Shift+click: file name)");
+            if copy_button.clicked() {
+                let text = if ui.input(|i| i.modifiers.shift) {
+                    file_name_text(path)
+                } else {
+                    full_path_text(path)
+                };
+                ui.output_mut(|o| o.copied_text = text);
+            }
+        });
+        if selected {
+            let rect = row.response.rect.expand(1.0);
+            ui.painter().set(
+                highlight,
+                egui::Shape::rect_filled(rect, 2.0, ui.visuals().selection.bg_fill),
+            );
+        }
+        row.response
+    }
+
+    /// Lists the results at `visible` under one collapsible header per folder,
+    /// each followed by the row for the results the per-folder cap trimmed there.
+    fn render_grouped_results(
+        &self,
+        ui: &mut egui::Ui,
+        visible: &[usize],
+        row_height: f32,
+        requested_action: &mut Option<FileAction>,
+        requested_directory: &mut Option<PathBuf>,
+        toggled_selection: &mut Option<PathBuf>,
+    ) {
+        let hidden_dirs = match self.directory_filter {
+            Some(_) => &[][..],
+            None => &self.directory_cap.hidden[..],
+        };
+        egui::ScrollArea::vertical()
+            .id_salt("grouped results")
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                for (dir, indices) in group_by_directory(&self.search_results, visible) {
+                    egui::CollapsingHeader::new(format!("{} ({})", dir.display(), indices.len()))
+                        .id_salt(&dir)
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for index in indices {
+                                let row = self.render_result_row(
+                                    ui,
+                                    index,
+                                    row_height,
+                                    requested_action,
+                                    toggled_selection,
+                                );
+                                if self.scroll_to_selected && self.selected_index == Some(index) {
+                                    row.scroll_to_me(None);
+                                }
+                            }
+                            let hidden = hidden_dirs.iter().find(|(hidden, _)| *hidden == dir);
+                            if let Some((_, hidden)) = hidden {
+                                if self.render_hidden_row(ui, &dir, *hidden, row_height) {
+                                    *requested_directory = Some(dir.clone());
+                                }
+                            }
+                        });
+                }
+            });
+    }
+
+    /// Draws the row standing for the results of `dir` trimmed by the per-folder
+    /// cap and returns whether it was clicked.
+    fn render_hidden_row(
+        &self,
+        ui: &mut egui::Ui,
+        dir: &Path,
+        hidden: usize,
+        row_height: f32,
+    ) -> bool {
+        ui.horizontal(|ui| {
+            ui.set_min_height(row_height);
+            if let Some(atlas) = &self.file_icons {
+                ui.add(file_icon(atlas, FileIcon::Folder));
+            }
+            ui.weak(format!("{} more in {}…", hidden, dir.display()))
+                .on_hover_cursor(egui::CursorIcon::PointingHand)
+                .clicked()
+        })
+        .inner
+    }
+
+    /// Makes `interval` the time between automatic index updates, for the update
+    /// thread and in `update_time_file`.
+    fn set_update_interval(&mut self, interval: Duration) {
+        self.avg_suspend_duration = interval;
+        self.interval_input = interval.as_secs().to_string();
+        self.interval_error = None;
+        if let Some(sender) = &self.message_sender {
+            let _ = sender.send(EngineMessage::SetInterval(interval));
+        }
+        let _ = std::fs::write(&self.update_time_file, interval.as_secs().to_string());
+    }
+
+    /// Applies the interval typed in the settings window, or shows why it is not
+    /// valid and leaves the current interval as it is.
+    fn apply_interval_input(&mut self) {
+        match parse_update_interval(&self.interval_input) {
+            Ok(interval) => self.set_update_interval(interval),
+            Err(e) => self.interval_error = Some(e),
+        }
+    }
+
+    /// The number of indexed files and the size of the index file on disk.
+    fn index_stats(&self) -> String {
+        let index_size = std::fs::metadata(self.search_engine.get_index_file())
+            .map_or(0, |metadata| metadata.len());
+        format!(
+            "Indexed files: {}, index file size: {} bytes",
+            self.search_engine.count_files(),
+            index_size
+        )
+    }
+
+    /// The viewport commands that handle closing this frame: a close request is
+    /// turned into minimizing while `minimize_on_close` is set, unless quitting
+    /// was asked for, which closes the window.
+    fn close_commands(&self, close_requested: bool) -> Vec<egui::ViewportCommand> {
+        match (close_requested, self.quit_requested) {
+            (false, true) => vec![egui::ViewportCommand::Close],
+            (true, false) if self.minimize_on_close => vec![
+                egui::ViewportCommand::CancelClose,
+                egui::ViewportCommand::Minimized(true),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Takes over the settings from `config.toml` that the UI owns.
+    fn apply_config(&mut self, config: &Config, ctx: &egui::Context) {
+        if let Some(interval) = config.update_interval {
+            self.avg_suspend_duration = interval;
+            self.interval_input = interval.as_secs().to_string();
+        }
+        self.root_directories = if config.roots.is_empty() {
+            let drive_kinds = config.drive_kinds.as_deref().unwrap_or(DEFAULT_DRIVE_KINDS);
+            root_names(&default_roots(drive_kinds))
+        } else {
+            root_names(&config.roots)
+        };
+        set_engine_roots(&mut self.search_engine, &self.root_directories);
+        if let Some(exclude_dirs) = &config.exclude_dirs {
+            self.search_engine.set_exclude_dirs(exclude_dirs.clone());
+        }
+        self.search_engine.set_max_depth(config.max_depth);
+        self.search_engine
+            .set_resource_limits(config.resource_limits);
+        self.search_engine.set_blacklist(Blacklist::from_entries(
+            config.blacklist.iter().map(String::as_str),
+        ));
+        self.categories = Categories::with_overrides(&config.categories);
+        self.index_mode = config.index_mode;
+        self.skip_hidden = config.skip_hidden;
+        self.match_case = config.match_case;
+        self.minimize_on_close = config.minimize_on_close;
+        self.search_engine.set_ignore_case(!self.match_case);
+        match config.theme {
+            Theme::System => {}
+            Theme::Light => ctx.set_visuals(egui::Visuals::light()),
+            Theme::Dark => ctx.set_visuals(egui::Visuals::dark()),
+        }
+    }
+
+    fn apply_search_outcome(&mut self, outcome: SearchOutcome) {
+        match outcome.pattern_error {
+            Some(e) => {
+                self.pattern_error = true;
+                self.notification_message = Some(e);
+            }
+            None if self.pattern_error => {
+                self.pattern_error = false;
+                self.notification_message = None;
+            }
+            None => {}
+        }
+        self.fallback_used = outcome.fallback_used;
+        self.search_results = outcome.results;
+        self.match_ranges = outcome.match_ranges;
+        self.content_matches = outcome.content_matches;
+        self.suggestions = outcome.suggestions;
+        self.suggestion_index = None;
+        self.results_truncated = outcome.truncated;
+        self.directory_counts = count_by_directory(&self.search_results);
+        self.directory_cap = cap_per_directory(&self.search_results, self.per_directory_limit);
+        if let Some(dir) = &self.directory_filter {
+            if !self
+                .directory_counts
+                .iter()
+                .any(|(counted, _)| counted == dir)
+            {
+                self.directory_filter = None;
+            }
+        }
+        let drives = result_drives(&self.search_results, &self.search_engine.get_root_dirs());
+        if self.drive_filter.is_some() {
+            // The search only found results on the chosen drive, so the other
+            // drives stay on offer.
+            self.result_drives.extend(drives);
+            self.result_drives.sort();
+            self.result_drives.dedup();
+        } else {
+            self.result_drives = drives;
+        }
+    }
+}
+
 impl SearchAppEngine for SearchApp {
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let _ = cc;
-        Self::default()
+        let mut app = SearchApp {
+            history: SearchHistory::load(Path::new(HISTORY_FILE)),
+            file_icons: load_icon_atlas(&cc.egui_ctx),
+            ..Self::default()
+        };
+        match SavedSearches::load(SAVED_SEARCHES_FILE) {
+            Ok(saved_searches) => app.saved_searches = saved_searches,
+            Err(e) => app.notification_message = Some(e),
+        }
+        match Config::load(&app.config_file.to_string_lossy()) {
+            Ok(config) => {
+                app.apply_config(&config, &cc.egui_ctx);
+                app.config = Some(config);
+            }
+            // Left unset so that the broken file is not overwritten on exit.
+            Err(e) => app.notification_message = Some(e),
+        }
+        let font_file = app
+            .config
+            .as_ref()
+            .and_then(|config| config.font.clone())
+            .unwrap_or_else(|| PathBuf::from(FONT_FILE));
+        setup_custom_fonts(&cc.egui_ctx, load_font(&font_file));
+        app
     }
 
-    fn set_message_sender(&mut self, sender: Sender<String>) {
+    fn set_message_sender(&mut self, sender: Sender<EngineMessage>) {
         self.message_sender = Some(sender);
     }
 
+    /// Keeps the background threads so that closing the window can wait for them.
+    fn set_background_threads(&mut self, threads: Vec<JoinHandle<()>>) {
+        self.background_threads = threads;
+    }
+
+    fn set_index_receiver(&mut self, receiver: Receiver<IndexSnapshot>) {
+        self.index_receiver = Some(receiver);
+    }
+
+    fn receive_index(&mut self) {
+        let Some(receiver) = &self.index_receiver else {
+            return;
+        };
+        let mut received = false;
+        while let Ok(snapshot) = receiver.try_recv() {
+            if snapshot.roots == self.search_engine.get_root_dirs() {
+                self.search_engine.replace_index(snapshot.files);
+                self.partial_index = !snapshot.complete;
+                received = true;
+            }
+        }
+        if received && !self.search_command.is_empty() {
+            self.execute_search();
+        }
+    }
+
+    fn set_progress_receiver(&mut self, receiver: Receiver<IndexProgress>) {
+        self.progress_receiver = Some(receiver);
+    }
+
+    /// Keeps the latest progress report of the indexing threads for
+    /// `render_loading_status`.
+    fn receive_progress(&mut self) {
+        if let Some(receiver) = &self.progress_receiver {
+            if let Some(progress) = receiver.try_iter().last() {
+                self.index_progress = Some(progress);
+            }
+        }
+    }
+
+    fn snapshot_index(&mut self, now: Instant) {
+        if self.index_mode != IndexMode::Memory
+            || self.partial_index
+            || now.duration_since(self.last_snapshot) < SNAPSHOT_INTERVAL
+        {
+            return;
+        }
+        self.last_snapshot = now;
+        if let Err(e) = self.search_engine.save_index() {
+            self.notification_message = Some(format!("Failed to save index: {}", e));
+        }
+    }
+
+    /// Starts searching for the query in the search box on a background thread,
+    /// cancelling a search that is still running. `receive_search` shows the
+    /// results once they are ready. While searching within results, only the
+    /// results kept when that was turned on are searched.
     fn execute_search(&mut self) {
-        self.search_engine.reset_search_results();
-        self.search_engine.search(&self.search_command);
-        self.search_results = self.search_engine.get_results().clone();
+        self.last_query = Some(self.search_command.clone());
+        self.selected_index = None;
+        let (key, mut filters) = QueryFilters::parse(&self.search_command);
+        if filters.category.is_none() {
+            filters.category = self.category_filter.clone();
+        }
+        self.search_engine
+            .set_extension_filter(parse_extension_list(&self.extension_input));
+        let mut engine = self.search_engine.search_view();
+        engine.set_query_filters(filters, self.categories.clone());
+        engine.set_drive_filter(self.drive_filter.clone());
+        engine.set_search_results_limit(self.max_results);
+        if let Some((_, base)) = &self.refine_base {
+            engine.replace_index(self.search_engine.result_files(&base.results));
+        }
+        let job = SearchJob {
+            engine,
+            key,
+            search_contents: self.search_file_contents,
+            merge_case_variants: self.merge_case_variants,
+            suggestion_limit: if std::mem::take(&mut self.suggest_next_search) {
+                SUGGESTION_LIMIT
+            } else {
+                0
+            },
+        };
+        self.search_worker.start(move |token| job.run(token));
+    }
+
+    /// Shows the results of the latest search once it has finished.
+    fn receive_search(&mut self) {
+        let Some(outcome) = self.search_worker.try_finish() else {
+            return;
+        };
+        self.apply_search_outcome(outcome);
+    }
+
+    /// Runs the most recently executed query again, e.g. after the index or the
+    /// matching settings changed. Does nothing before the first search.
+    fn rerun_last_search(&mut self) {
+        if let Some(query) = self.last_query.clone() {
+            self.search_command = query;
+            self.execute_search();
+        }
+    }
+
+    /// Runs the query or command in the search box. Queries are recorded in the
+    /// history here rather than in `execute_search`, which also runs while typing.
+    fn submit_search(&mut self) {
+        match self.commands.parse(&self.search_command) {
+            Some(Ok(command)) => self.run_command(command),
+            Some(Err(message)) => self.notification_message = Some(message),
+            None => {
+                self.search_debounce.cancel();
+                self.execute_search();
+                self.history.record(&self.search_command);
+                let _ = self.history.save(&self.history_file);
+            }
+        }
+    }
+
+    /// Turning this on keeps the current query and results, and makes further
+    /// searches narrow those results instead of searching the whole index.
+    /// Turning it off brings the kept query and results back.
+    fn set_search_within_results(&mut self, enabled: bool) {
+        if enabled {
+            if self.refine_base.is_none() {
+                let query = self.last_query.clone().unwrap_or_default();
+                let base = SearchOutcome {
+                    results: self.search_results.clone(),
+                    match_ranges: self.match_ranges.clone(),
+                    content_matches: self.content_matches.clone(),
+                    fallback_used: self.fallback_used,
+                    truncated: self.results_truncated,
+                    pattern_error: None,
+                    suggestions: Vec::new(),
+                };
+                self.refine_base = Some((query, base));
+            }
+            return;
+        }
+        let Some((query, outcome)) = self.refine_base.take() else {
+            return;
+        };
+        self.search_command = query.clone();
+        self.last_query = Some(query);
+        self.selected_index = None;
+        // Goes through the worker so that a narrowing search still running
+        // cannot land on top of the restored results.
+        self.search_worker.start(move |_| Some(outcome));
+    }
+
+    /// Puts the highlighted suggestion, or the first one, into the search box and
+    /// searches for it. Returns whether there was a suggestion to take.
+    fn accept_suggestion(&mut self) -> bool {
+        let index = self.suggestion_index.unwrap_or(0);
+        let Some(name) = self.suggestions.get(index).cloned() else {
+            return false;
+        };
+        self.search_command = name;
+        self.search_debounce.cancel();
+        self.execute_search();
+        self.suggestions.clear();
+        self.suggestion_index = None;
+        true
+    }
+
+    fn search_history(&self) -> &[String] {
+        self.history.queries()
+    }
+
+    fn saved_searches(&self) -> &[SavedSearch] {
+        self.saved_searches.list()
+    }
+
+    /// Saves the query in the search box and the options next to it as `name`,
+    /// replacing a saved search of the same name.
+    fn add_saved_search(&mut self, name: &str) {
+        self.saved_searches.add(SavedSearch {
+            name: name.trim().to_string(),
+            query: self.search_command.clone(),
+            options: SearchOptions {
+                extensions: self.extension_input.clone(),
+                category: self.category_filter.clone(),
+            },
+        });
+        if let Err(e) = self.saved_searches.save(SAVED_SEARCHES_FILE) {
+            self.notification_message = Some(format!("Failed to save searches: {}", e));
+        }
+    }
+
+    fn remove_saved_search(&mut self, name: &str) {
+        if !self.saved_searches.remove(name) {
+            return;
+        }
+        if let Err(e) = self.saved_searches.save(SAVED_SEARCHES_FILE) {
+            self.notification_message = Some(format!("Failed to save searches: {}", e));
+        }
+    }
+
+    /// Puts `search` into the search box and the options next to it and runs it.
+    fn run_saved_search(&mut self, search: SavedSearch) {
+        self.search_command = search.query;
+        self.extension_input = search.options.extensions;
+        self.category_filter = search
+            .options
+            .category
+            .filter(|category| self.categories.names().any(|name| name == category));
+        self.submit_search();
+    }
+
+    fn run_command(&mut self, command: AppCommand) {
+        self.search_command.clear();
+        match command {
+            AppCommand::Reindex => {
+                self.refresh_index();
+                self.notification_message = Some("Reindexing started".to_string());
+            }
+            AppCommand::Settings => self.display_dialog = true,
+            AppCommand::Clear => {
+                self.last_query = None;
+                self.suggestions.clear();
+                self.suggestion_index = None;
+                self.refine_base = None;
+                self.selected_index = None;
+                self.search_results.clear();
+                self.match_ranges.clear();
+                self.directory_counts.clear();
+                self.directory_cap = DirectoryCap::default();
+                self.directory_filter = None;
+                self.result_drives.clear();
+                self.drive_filter = None;
+                self.fallback_used = false;
+                self.notification_message = None;
+            }
+            AppCommand::Stats => self.notification_message = Some(self.index_stats()),
+            AppCommand::Interval(interval) => {
+                self.set_update_interval(interval);
+                self.notification_message = Some(format!(
+                    "Automatic index update interval set to {} seconds",
+                    interval.as_secs()
+                ));
+            }
+            AppCommand::Help => {
+                self.notification_message = Some(format!("Commands: {}", self.commands.usage()));
+            }
+            AppCommand::Quit => self.quit_requested = true,
+        }
+    }
+
+    /// Up and Down move the highlighted result, wrapping around at either end, and
+    /// Escape clears the search. Enter opening the highlighted result is handled
+    /// by the search box, which otherwise submits the query.
+    ///
+    /// While file names are suggested, Up and Down move through the suggestions
+    /// instead, Tab takes one and Escape hides them.
+    fn handle_navigation_keys(&mut self, ctx: &egui::Context) {
+        if self.display_dialog || self.pending_file_action.is_some() {
+            return;
+        }
+        let (down, up, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if !self.suggestions.is_empty() {
+            let listed: Vec<usize> = (0..self.suggestions.len()).collect();
+            if down || up {
+                self.suggestion_index = step_selection(&listed, self.suggestion_index, down);
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)) {
+                self.accept_suggestion();
+            }
+            if escape {
+                self.suggestions.clear();
+                self.suggestion_index = None;
+            }
+            return;
+        }
+        if down {
+            self.move_selection(true);
+        }
+        if up {
+            self.move_selection(false);
+        }
+        if escape {
+            self.run_command(AppCommand::Clear);
+        }
+    }
+
+    /// The indices into `search_results` of the rows `render_results_list` shows.
+    fn visible_result_indices(&self) -> Vec<usize> {
+        let visible: Vec<usize> = self
+            .search_results
+            .iter()
+            .enumerate()
+            .filter(|(index, (path, matched))| {
+                !matched.is_empty()
+                    && match &self.directory_filter {
+                        Some(dir) => parent_dir(path) == dir,
+                        None => self
+                            .directory_cap
+                            .shown
+                            .get(*index)
+                            .copied()
+                            .unwrap_or(true),
+                    }
+            })
+            .map(|(index, _)| index)
+            .collect();
+        if !self.group_results {
+            return visible;
+        }
+        group_by_directory(&self.search_results, &visible)
+            .into_iter()
+            .flat_map(|(_, indices)| indices)
+            .collect()
+    }
+
+    fn move_selection(&mut self, forward: bool) {
+        let visible = self.visible_result_indices();
+        self.selected_index = step_selection(&visible, self.selected_index, forward);
+        self.scroll_to_selected = true;
+    }
+
+    /// Opens the highlighted result, returning whether there was one.
+    fn open_selected(&mut self) -> bool {
+        let Some((path, _)) = self
+            .selected_index
+            .and_then(|index| self.search_results.get(index))
+        else {
+            return false;
+        };
+        let _ = open::that(path);
+        true
     }
 
     fn update_interface(&mut self, ctx: &egui::Context) {
+        if self.show_directory_panel {
+            self.render_directory_panel(ctx);
+        }
+        if self.show_preview {
+            self.render_preview_panel(ctx);
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::F5)) {
+            self.rerun_last_search();
+        }
+        self.handle_navigation_keys(ctx);
         egui::CentralPanel::default().show(ctx, |ui| {
-            if ui.ui_contains_pointer() {
+            if ui.ui_contains_pointer() || self.loading_status {
                 self.validate_index();
             }
             ui.vertical(|ui| {
@@ -93,9 +1032,52 @@ impl SearchAppEngine for SearchApp {
                 if self.display_dialog {
                     self.render_settings_window(ctx, ui);
                 }
+                if self.pending_file_action.is_some() {
+                    self.render_file_action_window(ctx);
+                } else if !self.display_dialog {
+                    if let Some(ref message) = self.notification_message {
+                        ui.label(message);
+                    }
+                }
                 if self.loading_status {
                     self.render_loading_status(ui);
                 }
+                if self.partial_index {
+                    ui.weak(format!(
+                        "Indexing in progress, searching the first {} files found",
+                        self.search_engine.len()
+                    ));
+                }
+                if self.search_command.starts_with(COMMAND_SIGIL) {
+                    ui.weak(format!(
+                        "Press Enter to run a command: {}",
+                        self.commands.usage()
+                    ));
+                }
+                if self.scope_len > 0 {
+                    ui.weak(format!("Searching within {} folders", self.scope_len));
+                }
+                if self.results_truncated {
+                    ui.weak(format!(
+                        "Showing the first {} of many results, refine the query to see the rest",
+                        self.search_results.len()
+                    ));
+                }
+                if self.fallback_used && !self.search_results.is_empty() {
+                    ui.weak("No exact matches, showing names that contain the text instead");
+                }
+                if self.last_query.is_some() && !self.search_command.is_empty() {
+                    ui.weak(match self.search_results.len() {
+                        1 => "1 result".to_string(),
+                        count => format!("{} results", count),
+                    });
+                }
+                if !self.selected_results.is_empty() {
+                    self.render_selection_bar(ui);
+                }
+                if !self.content_matches.is_empty() {
+                    self.render_content_matches(ui);
+                }
                 self.render_results_list(ui);
             });
         });
@@ -106,15 +1088,154 @@ impl SearchAppEngine for SearchApp {
             let search_input = ui.add(
                 egui::TextEdit::singleline(&mut self.search_command)
                     .hint_text("Search")
-                    .desired_width(ui.available_width() - 40.0),
+                    .desired_width(ui.available_width() - 315.0),
             );
             if !self.display_dialog {
                 search_input.request_focus();
             }
             if search_input.changed() {
                 self.update_avg_suspend_duration();
+                self.search_debounce.changed(Instant::now());
+            }
+            if self.search_debounce.poll(Instant::now())
+                && self.commands.parse(&self.search_command).is_none()
+            {
+                self.suggest_next_search = true;
+                self.execute_search();
+            }
+            if search_input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let accepted = self.suggestion_index.is_some() && self.accept_suggestion();
+                if !accepted && !self.open_selected() {
+                    self.submit_search();
+                }
+            }
+            let popup_id = ui.make_persistent_id("suggestions");
+            if !self.suggestions.is_empty() {
+                ui.memory_mut(|memory| memory.open_popup(popup_id));
+            } else if ui.memory(|memory| memory.is_popup_open(popup_id)) {
+                ui.memory_mut(|memory| memory.close_popup());
+            }
+            let mut clicked = None;
+            egui::popup_below_widget(
+                ui,
+                popup_id,
+                &search_input,
+                egui::PopupCloseBehavior::IgnoreClicks,
+                |ui| {
+                    ui.set_min_width(search_input.rect.width());
+                    for (index, name) in self.suggestions.iter().enumerate() {
+                        let highlighted = self.suggestion_index == Some(index);
+                        if ui.selectable_label(highlighted, name).clicked() {
+                            clicked = Some(index);
+                        }
+                    }
+                },
+            );
+            if clicked.is_some() {
+                self.suggestion_index = clicked;
+                self.accept_suggestion();
+            }
+            let mut recalled = None;
+            let mut saved_to_run = None;
+            let has_entries =
+                !self.search_history().is_empty() || !self.saved_searches().is_empty();
+            ui.add_enabled_ui(has_entries, |ui| {
+                ui.menu_button("🕘", |ui| {
+                    ui.set_max_height(300.0);
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for saved in self.saved_searches() {
+                            if ui
+                                .button(format!("★ {}", saved.name))
+                                .on_hover_text(&saved.query)
+                                .clicked()
+                            {
+                                saved_to_run = Some(saved.clone());
+                                ui.close_menu();
+                            }
+                        }
+                        if !self.saved_searches().is_empty() && !self.search_history().is_empty() {
+                            ui.separator();
+                        }
+                        for query in self.search_history() {
+                            if ui.button(query).clicked() {
+                                recalled = Some(query.clone());
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                })
+                .response
+                .on_hover_text("Saved and recent searches");
+            });
+            if let Some(query) = recalled {
+                self.search_command = query;
+                self.submit_search();
+            }
+            if let Some(saved) = saved_to_run {
+                self.run_saved_search(saved);
+            }
+            let extension_input = ui.add(
+                egui::TextEdit::singleline(&mut self.extension_input)
+                    .hint_text("ext: rs,toml")
+                    .desired_width(70.0),
+            );
+            if extension_input.changed() {
                 self.execute_search();
             }
+            let previous_category = self.category_filter.clone();
+            egui::ComboBox::from_id_salt("category")
+                .width(90.0)
+                .selected_text(self.category_filter.as_deref().unwrap_or("all"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.category_filter, None, "all");
+                    for name in self.categories.names() {
+                        ui.selectable_value(
+                            &mut self.category_filter,
+                            Some(name.to_string()),
+                            name,
+                        );
+                    }
+                });
+            if self.category_filter != previous_category {
+                self.execute_search();
+            }
+            let previous_drive = self.drive_filter.clone();
+            if self.result_drives.len() > 1 || self.drive_filter.is_some() {
+                let selected = self
+                    .drive_filter
+                    .as_ref()
+                    .map_or("all drives".into(), |drive| drive.to_string_lossy());
+                egui::ComboBox::from_id_salt("drive")
+                    .width(90.0)
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.drive_filter, None, "all drives");
+                        for drive in &self.result_drives {
+                            ui.selectable_value(
+                                &mut self.drive_filter,
+                                Some(drive.clone()),
+                                drive.to_string_lossy(),
+                            );
+                        }
+                    });
+            }
+            if self.drive_filter != previous_drive {
+                self.execute_search();
+            }
+            let mut within_results = self.refine_base.is_some();
+            if ui
+                .toggle_value(&mut within_results, "⊂")
+                .on_hover_text("Search within the current results")
+                .changed()
+            {
+                self.set_search_within_results(within_results);
+            }
+            ui.toggle_value(&mut self.show_directory_panel, "▤")
+                .on_hover_text("Matches per folder");
+            ui.toggle_value(&mut self.group_results, "🗀")
+                .on_hover_text("Group results by folder");
+            ui.toggle_value(&mut self.show_preview, "👁")
+                .on_hover_text("Preview the highlighted result");
             if ui.button("Set").clicked() {
                 self.display_dialog = true;
             }
@@ -123,91 +1244,631 @@ impl SearchAppEngine for SearchApp {
 
     fn render_settings_window(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
         let _ = ui;
+        let mut scope_changed = None;
+        let mut matching_changed = false;
+        let mut saved_to_run = None;
+        let mut saved_to_remove = None;
+        let mut save_current = false;
+        let index_stats = self.index_stats();
+        let mut apply_interval = false;
         egui::Window::new("Setting")
             .open(&mut self.display_dialog)
             .show(ctx, |ui| {
-                ui.heading("Root Path");
+                ui.heading("Root Paths");
+                let mut removed = None;
+                for (index, root) in self.root_directories.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(root);
+                        if self.root_directories.len() > 1 && ui.small_button("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = removed {
+                    self.root_directories.remove(index);
+                    self.roots_edited = true;
+                }
                 ui.horizontal(|ui| {
                     if ui.text_edit_singleline(&mut self.root_directory).changed() {
                         self.notification_message = None;
                     }
+                    let root = self.root_directory.trim().to_string();
+                    if ui.button("Add").clicked()
+                        && !root.is_empty()
+                        && !self.root_directories.contains(&root)
+                    {
+                        self.root_directories.push(root);
+                        self.root_directory.clear();
+                        self.roots_edited = true;
+                    }
                     if ui.button("Switch").clicked() {
-                        self.search_engine
-                            .set_root_dir([self.root_directory.clone()].iter().collect());
-                        self.search_engine.load_index();
-                        self.notification_message =
-                            Some("Root directory switched successfully".to_string());
+                        set_engine_roots(&mut self.search_engine, &self.root_directories);
+                        self.notification_message = Some(match self.search_engine.load_index() {
+                            Ok(()) => "Root directories switched successfully".to_string(),
+                            Err(e) => e.to_string(),
+                        });
                     }
                 });
                 if let Some(ref message) = self.notification_message {
                     ui.label(message);
                 }
                 ui.heading("Update Index");
-                ui.label(format!(
-                    "Automatic index update interval: {} seconds",
-                    self.avg_suspend_duration.as_secs().to_string()
+                ui.label(index_stats);
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Automatic index update interval: {} seconds",
+                        self.avg_suspend_duration.as_secs()
+                    ));
+                    let input = ui.add(
+                        egui::TextEdit::singleline(&mut self.interval_input).desired_width(60.0),
+                    );
+                    let entered =
+                        input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.button("Set").clicked() || entered {
+                        apply_interval = true;
+                    }
+                });
+                if let Some(ref error) = self.interval_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Update Index Immediately").clicked() {
+                        if let Some(sender) = &self.message_sender {
+                            let _ = sender.send(reindex_message(&self.root_directories));
+                        }
+                    }
+                    let label = if self.indexing_paused {
+                        "Resume Indexing"
+                    } else {
+                        "Pause Indexing"
+                    };
+                    if ui
+                        .button(label)
+                        .on_hover_text("Stops reading the disk until resumed")
+                        .clicked()
+                    {
+                        if let Some(sender) = &self.message_sender {
+                            self.indexing_paused = !self.indexing_paused;
+                            let _ = sender.send(if self.indexing_paused {
+                                EngineMessage::PauseIndexing
+                            } else {
+                                EngineMessage::ResumeIndexing
+                            });
+                        }
+                    }
+                });
+                if ui
+                    .checkbox(&mut self.skip_hidden, "Skip hidden and system files")
+                    .on_hover_text("Applies from the next index update")
+                    .changed()
+                {
+                    // Saved at once, since the indexing threads read it from the file.
+                    if let Some(config) = &mut self.config {
+                        config.skip_hidden = self.skip_hidden;
+                        if let Err(e) = config.save(&self.config_file) {
+                            self.notification_message = Some(format!(
+                                "Failed to save {}: {}",
+                                self.config_file.display(),
+                                e
+                            ));
+                        }
+                    }
+                }
+                ui.checkbox(
+                    &mut self.minimize_on_close,
+                    "Keep indexing when the window is closed",
+                )
+                .on_hover_text(format!(
+                    "Closing minimizes the window; type {}quit to exit",
+                    COMMAND_SIGIL
                 ));
-                if ui.button("Update Index Immediately").clicked() {
-                    if let Some(sender) = &self.message_sender {
-                        let _ = sender.send(self.root_directory.clone());
+                ui.heading("Matching");
+                ui.horizontal(|ui| {
+                    ui.label("Pattern:");
+                    for (mode, label) in [
+                        (SearchMode::Regex, "Regex"),
+                        (SearchMode::Auto, "Auto (regex only when it looks like one)"),
+                        (SearchMode::Prefix, "Prefix"),
+                        (SearchMode::Contains, "Contains"),
+                        (SearchMode::Glob, "Glob (*.rs, report_??.pdf)"),
+                    ] {
+                        if ui.radio_value(&mut self.search_mode, mode, label).changed() {
+                            matching_changed = true;
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Match against:");
+                    for (haystack, label) in [
+                        (Haystack::Name, "Name"),
+                        (Haystack::NameWithExtension, "Name and extension"),
+                        (Haystack::FullPath, "Full path"),
+                    ] {
+                        if ui
+                            .radio_value(&mut self.haystack, haystack, label)
+                            .changed()
+                        {
+                            matching_changed = true;
+                        }
                     }
+                });
+                if ui.checkbox(&mut self.match_case, "Match case").changed() {
+                    matching_changed = true;
+                }
+                if ui
+                    .checkbox(&mut self.whole_word, "Match whole words only")
+                    .changed()
+                {
+                    matching_changed = true;
+                }
+                if ui
+                    .checkbox(&mut self.rank_results, "Show best matches first")
+                    .changed()
+                {
+                    matching_changed = true;
+                }
+                if ui
+                    .checkbox(
+                        &mut self.ignore_separators,
+                        "Ignore separators and case (mainrs finds main.rs)",
+                    )
+                    .changed()
+                {
+                    matching_changed = true;
+                }
+                if ui
+                    .checkbox(
+                        &mut self.search_file_contents,
+                        "Also search inside text files (slow)",
+                    )
+                    .changed()
+                {
+                    matching_changed = true;
+                }
+                ui.horizontal(|ui| {
+                    let mut secs = self.idle_repaint_interval.as_secs();
+                    ui.label("Idle refresh interval (seconds):");
+                    if ui
+                        .add(egui::DragValue::new(&mut secs).range(1..=600))
+                        .changed()
+                    {
+                        self.idle_repaint_interval = Duration::from_secs(secs);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut millis = self.search_debounce.delay().as_millis() as u64;
+                    ui.label("Search after typing pauses for (ms):");
+                    if ui
+                        .add(egui::DragValue::new(&mut millis).range(0..=2000))
+                        .changed()
+                    {
+                        self.search_debounce
+                            .set_delay(Duration::from_millis(millis));
+                    }
+                });
+                ui.heading("Results");
+                ui.horizontal(|ui| {
+                    ui.label("Results per folder (0 = no limit):");
+                    if ui
+                        .add(egui::DragValue::new(&mut self.per_directory_limit).range(0..=1000))
+                        .changed()
+                    {
+                        self.directory_cap =
+                            cap_per_directory(&self.search_results, self.per_directory_limit);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Results kept at most:");
+                    if ui
+                        .add(egui::DragValue::new(&mut self.max_results).range(1..=1_000_000))
+                        .changed()
+                    {
+                        matching_changed = true;
+                    }
+                });
+                if ui
+                    .checkbox(
+                        &mut self.merge_case_variants,
+                        "Merge paths that differ only in letter case",
+                    )
+                    .changed()
+                {
+                    matching_changed = true;
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Export results to CSV:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.csv_export_path)
+                            .hint_text("results.csv")
+                            .desired_width(200.0),
+                    );
+                    let out = PathBuf::from(self.csv_export_path.trim());
+                    if ui
+                        .add_enabled(
+                            !out.as_os_str().is_empty() && !self.search_results.is_empty(),
+                            egui::Button::new("Export"),
+                        )
+                        .clicked()
+                    {
+                        let files = self.search_engine.result_files(&self.search_results);
+                        self.notification_message = Some(match write_results_csv(&files, &out) {
+                            Ok(()) => format!(
+                                "Exported {} results to {}",
+                                self.search_results.len(),
+                                out.display()
+                            ),
+                            Err(e) => format!("Failed to export results: {}", e),
+                        });
+                    }
+                });
+                if cfg!(windows) {
+                    ui.heading("Actions");
+                    ui.checkbox(
+                        &mut self.allow_elevated_open,
+                        "Offer \"Open as administrator\" on results",
+                    );
                 }
+                ui.heading("Search Scope");
+                ui.label("Only search within these folders (one per line):");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.scope_input)
+                        .desired_rows(3)
+                        .hint_text("C:\\Projects\nD:\\Photos"),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() {
+                        scope_changed = Some(parse_path_list(&self.scope_input));
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.scope_input.clear();
+                        scope_changed = Some(Vec::new());
+                    }
+                });
+                ui.heading("Saved Searches");
+                for saved in self.saved_searches.list() {
+                    ui.horizontal(|ui| {
+                        if ui.button(&saved.name).on_hover_text(&saved.query).clicked() {
+                            saved_to_run = Some(saved.clone());
+                        }
+                        if ui.small_button("Remove").clicked() {
+                            saved_to_remove = Some(saved.name.clone());
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.saved_search_name)
+                            .hint_text("Name")
+                            .desired_width(120.0),
+                    );
+                    let can_save = !self.saved_search_name.trim().is_empty()
+                        && !self.search_command.trim().is_empty();
+                    if ui
+                        .add_enabled(can_save, egui::Button::new("Save current search"))
+                        .clicked()
+                    {
+                        save_current = true;
+                    }
+                });
             });
+        if apply_interval {
+            self.apply_interval_input();
+        }
+        if save_current {
+            let name = std::mem::take(&mut self.saved_search_name);
+            self.add_saved_search(&name);
+        }
+        if let Some(name) = saved_to_remove {
+            self.remove_saved_search(&name);
+        }
+        if let Some(saved) = saved_to_run {
+            self.display_dialog = false;
+            self.run_saved_search(saved);
+        }
+        if let Some(scope) = scope_changed {
+            self.scope_len = scope.len();
+            self.search_engine.set_search_scope(scope);
+            self.execute_search();
+        }
+        if matching_changed {
+            self.search_engine
+                .set_ignore_separators(self.ignore_separators);
+            self.search_engine.set_search_mode(self.search_mode);
+            self.search_engine.set_haystack(self.haystack);
+            self.search_engine.set_ignore_case(!self.match_case);
+            self.search_engine.set_whole_word(self.whole_word);
+            self.search_engine.set_rank_results(self.rank_results);
+            self.execute_search();
+        }
     }
 
     fn render_results_list(&mut self, ui: &mut egui::Ui) {
-        egui::ScrollArea::vertical().show(ui, |ui| {
-            ui.set_width(ui.available_width());
-            for (path, matched) in &self.search_results {
-                if matched.is_empty() {
-                    continue;
+        let mut requested_action = None;
+        let mut requested_directory = None;
+        let mut toggled_selection = None;
+        let visible = self.visible_result_indices();
+        let hidden_dirs = match self.directory_filter {
+            Some(_) => &[][..],
+            None => &self.directory_cap.hidden[..],
+        };
+        // Every row has the same height so that only the rows in view need laying out.
+        let row_height = ui
+            .text_style_height(&egui::TextStyle::Body)
+            .max(ui.spacing().interact_size.y);
+        if self.group_results {
+            self.render_grouped_results(
+                ui,
+                &visible,
+                row_height,
+                &mut requested_action,
+                &mut requested_directory,
+                &mut toggled_selection,
+            );
+        } else {
+            let row_span = row_height + ui.spacing().item_spacing.y;
+            let mut scroll_area = egui::ScrollArea::vertical().id_salt("results");
+            let selected_row = self
+                .selected_index
+                .filter(|_| self.scroll_to_selected)
+                .and_then(|selected| visible.iter().position(|&index| index == selected));
+            if let Some(row) = selected_row {
+                // The selected row may not be laid out, so scroll to where it would be.
+                let id = ui.make_persistent_id("results");
+                let offset = egui::scroll_area::State::load(ui.ctx(), id)
+                    .map_or(0.0, |state| state.offset.y);
+                let top = row as f32 * row_span;
+                let viewport = ui.available_height();
+                if top < offset {
+                    scroll_area = scroll_area.vertical_scroll_offset(top);
+                } else if top + row_height > offset + viewport {
+                    scroll_area = scroll_area.vertical_scroll_offset(top + row_height - viewport);
                 }
-                ui.horizontal(|ui| {
-                    let file_name = path.file_name().unwrap().to_str().unwrap();
-                    let file_name = format!("-{} ", file_name);
-                    let default_visuals = ui.visuals().clone();
-                    let file_name_parts: Vec<&str> = file_name.split(matched).collect();
-                    let file_path = path.to_str().unwrap();
-                    for part in file_name_parts {
-                        {
-                            let label = ui.label(part);
-                            if label.clicked() && open::that(file_path).is_ok() {}
-                            label
-                                .clone()
-                                .on_hover_cursor(egui::CursorIcon::PointingHand);
-                            ui.add_space(-8.5);
-                            label.on_hover_text(file_path);
-                            if !part.ends_with(' ') {
-                                let matched_label = ui.strong(matched);
-                                if matched_label.clicked() && open::that_detached(file_path).is_ok()
-                                {
-                                }
-                                matched_label
-                                    .clone()
-                                    .on_hover_cursor(egui::CursorIcon::PointingHand);
-                                matched_label.on_hover_text(file_path);
-                                ui.add_space(-8.5);
+            }
+            let total_rows = visible.len() + hidden_dirs.len();
+            scroll_area.show_rows(ui, row_height, total_rows, |ui, rows| {
+                ui.set_width(ui.available_width());
+                for row in rows {
+                    let Some(&index) = visible.get(row) else {
+                        let (dir, hidden) = &hidden_dirs[row - visible.len()];
+                        if self.render_hidden_row(ui, dir, *hidden, row_height) {
+                            requested_directory = Some(dir.clone());
+                        }
+                        continue;
+                    };
+                    self.render_result_row(
+                        ui,
+                        index,
+                        row_height,
+                        &mut requested_action,
+                        &mut toggled_selection,
+                    );
+                }
+            });
+        }
+        self.scroll_to_selected = false;
+        if requested_action.is_some() {
+            self.pending_file_action = requested_action;
+        }
+        if requested_directory.is_some() {
+            self.directory_filter = requested_directory;
+        }
+        if let Some(path) = toggled_selection {
+            self.toggle_selected(path);
+        }
+    }
+
+    fn toggle_selected(&mut self, path: PathBuf) {
+        match self
+            .selected_results
+            .iter()
+            .position(|selected| *selected == path)
+        {
+            Some(index) => {
+                self.selected_results.remove(index);
+            }
+            None => self.selected_results.push(path),
+        }
+    }
+
+    fn render_content_matches(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing(
+            format!(
+                "{} matching lines in file contents",
+                self.content_matches.len()
+            ),
+            |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("content_matches")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for (path, line) in &self.content_matches {
+                            if ui.link(format!("{}:{}", path.display(), line)).clicked() {
+                                let _ = open::that(path);
                             }
                         }
+                    });
+            },
+        );
+    }
+
+    fn render_selection_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} selected, copy to:",
+                self.selected_results.len()
+            ));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.copy_destination)
+                    .hint_text("Destination folder")
+                    .desired_width(ui.available_width() - 120.0),
+            );
+            let destination = PathBuf::from(self.copy_destination.trim());
+            if ui
+                .add_enabled(destination.is_dir(), egui::Button::new("Copy"))
+                .clicked()
+            {
+                let plan = copy_plan(&self.selected_results, &destination, Path::exists);
+                self.notification_message = Some(execute_copy_plan(&plan));
+                self.selected_results.clear();
+            }
+            if ui.button("Clear").clicked() {
+                self.selected_results.clear();
+            }
+        });
+    }
+
+    /// Shows the start of the result highlighted with the arrow keys. The preview is
+    /// read once per highlighted file rather than every frame.
+    fn render_preview_panel(&mut self, ctx: &egui::Context) {
+        let selected = self
+            .selected_index
+            .and_then(|index| self.search_results.get(index))
+            .map(|(path, _)| path);
+        match (selected, &self.preview) {
+            (Some(path), Some((previewed, _))) if path == previewed => {}
+            (Some(path), _) => self.preview = Some((path.clone(), load_preview(path))),
+            (None, _) => self.preview = None,
+        }
+        egui::SidePanel::right("preview_panel")
+            .resizable(true)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                let Some((path, preview)) = &self.preview else {
+                    ui.weak("Highlight a result with the arrow keys to preview it");
+                    return;
+                };
+                ui.strong(file_name_text(path))
+                    .on_hover_text(full_path_text(path));
+                match preview {
+                    Preview::Text(text) => {
+                        egui::ScrollArea::both().show(ui, |ui| {
+                            ui.add(
+                                egui::TextEdit::multiline(&mut text.as_str())
+                                    .code_editor()
+                                    .desired_width(f32::INFINITY),
+                            );
+                        });
+                    }
+                    Preview::Unavailable => {
+                        ui.weak("No preview available");
+                    }
+                }
+            });
+    }
+
+    fn render_directory_panel(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::left("directory_panel")
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.heading("Folders");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    if ui
+                        .selectable_label(
+                            self.directory_filter.is_none(),
+                            format!("All ({})", self.search_results.len()),
+                        )
+                        .clicked()
+                    {
+                        self.directory_filter = None;
                     }
-                    ui.visuals_mut().override_text_color = Some(default_visuals.hyperlink_color);
-                    if !self.search_command.is_empty() {
-                        ui.add_space(1.0);
-                        let explorer_button = ui
-                            .label("σ")
-                            .on_hover_cursor(egui::CursorIcon::PointingHand);
-                        if explorer_button.clicked() {
-                            let _ = Command::new("explorer").arg("/select,").arg(path).spawn();
+                    for (dir, count) in &self.directory_counts {
+                        let selected = self.directory_filter.as_ref() == Some(dir);
+                        let name = dir
+                            .file_name()
+                            .map(|name| name.to_string_lossy())
+                            .unwrap_or_else(|| dir.to_string_lossy());
+                        let label = ui
+                            .selectable_label(selected, format!("{} ({})", name, count))
+                            .on_hover_text(dir.to_string_lossy());
+                        if label.clicked() {
+                            self.directory_filter = if selected { None } else { Some(dir.clone()) };
                         }
                     }
                 });
+            });
+    }
+
+    fn render_file_action_window(&mut self, ctx: &egui::Context) {
+        let mut confirmed = None;
+        let mut cancelled = false;
+        egui::Window::new("Confirm")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| match &mut self.pending_file_action {
+                Some(FileAction::Rename { path, new_name }) => {
+                    ui.label(format!("Rename {}", path.display()));
+                    ui.text_edit_singleline(new_name);
+                    ui.horizontal(|ui| {
+                        if ui.button("Rename").clicked() {
+                            confirmed = self.pending_file_action.clone();
+                        }
+                        cancelled = ui.button("Cancel").clicked();
+                    });
+                }
+                Some(FileAction::Delete(path)) => {
+                    ui.label(format!("Move {} to the recycle bin?", path.display()));
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            confirmed = self.pending_file_action.clone();
+                        }
+                        cancelled = ui.button("Cancel").clicked();
+                    });
+                }
+                Some(FileAction::OpenElevated(path)) => {
+                    ui.label(format!(
+                        "Open {} as administrator? Windows will ask for confirmation.",
+                        path.display()
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("Open").clicked() {
+                            confirmed = self.pending_file_action.clone();
+                        }
+                        cancelled = ui.button("Cancel").clicked();
+                    });
+                }
+                None => {}
+            });
+        if cancelled {
+            self.pending_file_action = None;
+        }
+        if let Some(action) = confirmed {
+            self.pending_file_action = None;
+            self.apply_file_action(action);
+        }
+    }
+
+    fn apply_file_action(&mut self, action: FileAction) {
+        let outcome = match action {
+            FileAction::Rename { path, new_name } => {
+                rename_indexed(&mut self.search_engine, &path, &new_name)
+                    .map(|renamed| format!("Renamed to {}", renamed.display()))
+                    .map_err(|e| e.to_string())
             }
-        });
+            FileAction::Delete(path) => trash_indexed(&mut self.search_engine, &path)
+                .map(|()| format!("Moved {} to the recycle bin", path.display())),
+            FileAction::OpenElevated(path) => {
+                if let Err(e) = open_elevated(&path) {
+                    self.notification_message = Some(format!("File operation failed: {}", e));
+                }
+                return;
+            }
+        };
+        match outcome {
+            Ok(message) => {
+                let saved = self.search_engine.save_index();
+                self.execute_search();
+                self.notification_message = Some(match saved {
+                    Ok(()) => message,
+                    Err(e) => format!("Failed to save index: {}", e),
+                });
+            }
+            Err(e) => self.notification_message = Some(format!("File operation failed: {}", e)),
+        }
     }
 
     fn refresh_index(&self) {
         if let Some(sender) = &self.message_sender {
-            let _ = sender.send(self.root_directory.clone());
+            let _ = sender.send(reindex_message(&self.root_directories));
         }
     }
 
@@ -217,30 +1878,47 @@ impl SearchAppEngine for SearchApp {
                 self.updating_status = true;
                 self.refresh_index();
             }
-            self.search_engine.load_index();
+            // An unreadable index stays empty and is rebuilt by `refresh_index`.
+            if let Err(e) = self.search_engine.load_index() {
+                self.notification_message = Some(e.to_string());
+            }
             self.loading_status = true
         } else {
             self.loading_status = false;
             self.updating_status = false;
+            self.index_progress = None;
         }
     }
 
     fn render_loading_status(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Loading...");
+        ui.heading(if self.indexing_paused {
+            "Loading... (indexing paused)"
+        } else {
+            "Loading..."
+        });
+        if let Some(progress) = &self.index_progress {
+            ui.label(format!("{} files indexed", progress.files));
+            ui.add(
+                egui::Label::new(
+                    egui::RichText::new(progress.current_dir.display().to_string()).weak(),
+                )
+                .truncate(),
+            );
+        }
     }
 
     fn update_avg_suspend_duration(&mut self) {
         self.current_active_time = SystemTime::now();
-        if let Ok(suspend_duration) = self.current_active_time.duration_since(self.last_active_time) {
+        if let Ok(suspend_duration) = self
+            .current_active_time
+            .duration_since(self.last_active_time)
+        {
             if suspend_duration.as_secs() >= 300 {
                 self.last_active_time = self.current_active_time;
                 self.avg_suspend_duration.add_assign(suspend_duration);
                 self.avg_suspend_duration = self.avg_suspend_duration.div_f32(2.0);
                 if let Some(sender) = &self.message_sender {
-                    let _ = sender.send(format!(
-                        ":{}",
-                        self.avg_suspend_duration.as_secs().to_string()
-                    ));
+                    let _ = sender.send(EngineMessage::SetInterval(self.avg_suspend_duration));
                 }
             }
         };
@@ -250,37 +1928,759 @@ impl SearchAppEngine for SearchApp {
 impl eframe::App for SearchApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         let _ = frame;
-        setup_custom_fonts(ctx);
+        let close_requested = ctx.input(|i| i.viewport().close_requested());
+        for command in self.close_commands(close_requested) {
+            ctx.send_viewport_cmd(command);
+        }
+        self.receive_index();
+        self.receive_progress();
+        self.receive_search();
+        self.snapshot_index(Instant::now());
         self.update_interface(ctx);
+        if let Some(delay) = repaint_delay(
+            self.loading_status,
+            self.index_mode == IndexMode::Memory,
+            self.idle_repaint_interval,
+        ) {
+            ctx.request_repaint_after(delay);
+        }
+        if let Some(delay) = self.search_debounce.remaining(Instant::now()) {
+            ctx.request_repaint_after(delay);
+        }
+        if self.search_worker.is_pending() {
+            ctx.request_repaint_after(SEARCH_POLL_INTERVAL);
+        }
     }
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        if let Ok(mut file) = File::create("updateTime.ini") {
-            file.write(
-                self.avg_suspend_duration
-                    .as_secs()
-                    .to_string()
-                    .as_bytes(),
-            )
-            .unwrap();
+        // Stop indexing first so no thread writes an index file while exiting.
+        if let Some(sender) = &self.message_sender {
+            let _ = sender.send(EngineMessage::Shutdown);
+        }
+        let threads = std::mem::take(&mut self.background_threads);
+        if !join_with_timeout(threads, SHUTDOWN_TIMEOUT) {
+            eprintln!("Background threads did not stop in time");
+        }
+        if self.index_mode == IndexMode::Memory && !self.partial_index {
+            if let Err(e) = self.search_engine.save_index() {
+                eprintln!("Failed to save index: {}", e);
+            }
+        }
+        if let Some(config) = &mut self.config {
+            config.update_interval = Some(self.avg_suspend_duration);
+            if self.roots_edited {
+                config.roots = self.root_directories.iter().map(PathBuf::from).collect();
+            }
+            config.match_case = self.match_case;
+            config.minimize_on_close = self.minimize_on_close;
+            config.skip_hidden = self.skip_hidden;
+            if let Err(e) = config.save(&self.config_file) {
+                eprintln!("Failed to save {}: {}", self.config_file.display(), e);
+            }
         }
     }
 }
 
-fn setup_custom_fonts(ctx: &egui::Context) {
-    let mut fonts = FontDefinitions::default();
+/// The roots as shown and edited in the settings.
+fn root_names(roots: &[PathBuf]) -> Vec<String> {
+    roots
+        .iter()
+        .map(|root| root.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Points `engine` at `roots`, the first of which names the index file.
+fn set_engine_roots(engine: &mut Search, roots: &[String]) {
+    let mut roots = roots.iter().map(PathBuf::from);
+    if let Some(first) = roots.next() {
+        engine.set_root_dir(first);
+    }
+    for root in roots {
+        engine.add_root_dir(root);
+    }
+}
+
+/// Decides when the next frame must be drawn even without user input.
+///
+/// egui already repaints on input, so an idle window only needs timed repaints while
+/// the index is loading, to pick it up as soon as it is ready, or while background
+/// threads may deliver a new index. `None` leaves the window idle until the next input.
+fn repaint_delay(loading: bool, background_updates: bool, idle: Duration) -> Option<Duration> {
+    if loading {
+        Some(LOADING_REPAINT_INTERVAL.min(idle))
+    } else if background_updates {
+        Some(idle)
+    } else {
+        None
+    }
+}
+
+/// Asks the indexing threads to index the roots entered in the settings.
+fn reindex_message(root_directories: &[String]) -> EngineMessage {
+    EngineMessage::Reindex(
+        root_directories
+            .iter()
+            .flat_map(|root| parse_path_list(root))
+            .collect(),
+    )
+}
+
+/// The result after `current` among the `visible` ones, or before it when not
+/// `forward`, wrapping around at either end. Without a current one the first or
+/// last is chosen; with nothing visible, none.
+fn step_selection(visible: &[usize], current: Option<usize>, forward: bool) -> Option<usize> {
+    let last = visible.len().checked_sub(1)?;
+    let position = current.and_then(|current| visible.iter().position(|&index| index == current));
+    let next = match (position, forward) {
+        (None, true) => 0,
+        (None, false) => last,
+        (Some(position), true) if position == last => 0,
+        (Some(position), true) => position + 1,
+        (Some(0), false) => last,
+        (Some(position), false) => position - 1,
+    };
+    Some(visible[next])
+}
+
+fn result_context_menu(
+    ui: &mut egui::Ui,
+    path: &Path,
+    allow_elevated_open: bool,
+    action: &mut Option<FileAction>,
+    toggled_selection: &mut Option<PathBuf>,
+) {
+    if ui.button("Open").clicked() {
+        let _ = open::that_detached(path);
+        ui.close_menu();
+    }
+    if ui.button("Open containing folder").clicked() {
+        reveal_in_file_manager(path);
+        ui.close_menu();
+    }
+    if ui.button("Copy full path").clicked() {
+        ui.output_mut(|o| o.copied_text = full_path_text(path));
+        ui.close_menu();
+    }
+    if ui.button("Copy file name").clicked() {
+        ui.output_mut(|o| o.copied_text = file_name_text(path));
+        ui.close_menu();
+    }
+    ui.separator();
+    if ui.button("Select / deselect (Ctrl+click)").clicked() {
+        *toggled_selection = Some(path.to_path_buf());
+        ui.close_menu();
+    }
+    if ui.button("Rename…").clicked() {
+        let new_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        *action = Some(FileAction::Rename {
+            path: path.to_path_buf(),
+            new_name,
+        });
+        ui.close_menu();
+    }
+    if ui.button("Delete").clicked() {
+        *action = Some(FileAction::Delete(path.to_path_buf()));
+        ui.close_menu();
+    }
+    if allow_elevated_open && ui.button("Open as administrator…").clicked() {
+        *action = Some(FileAction::OpenElevated(path.to_path_buf()));
+        ui.close_menu();
+    }
+}
 
-    // Load a font that supports Chinese characters
-    fonts.font_data.insert(
-        "my_font".to_owned(),
-        egui::FontData::from_static(include_bytes!("./font/NotoSerifCJKsc-Regular.otf")),
-    );
+fn file_icon(atlas: &egui::TextureHandle, icon: FileIcon) -> egui::Image<'static> {
+    egui::Image::new((atlas.id(), egui::vec2(ICON_SIZE, ICON_SIZE))).uv(icon.uv())
+}
+
+/// Shows `path` selected in its folder in Explorer, or opens the folder on other
+/// systems.
+fn reveal_in_file_manager(path: &Path) {
+    if cfg!(windows) {
+        let _ = Command::new("explorer").arg("/select,").arg(path).spawn();
+    } else {
+        let _ = open::that_detached(parent_dir(path));
+    }
+}
 
-    // Insert the font into the font family
-    fonts
-        .families
-        .entry(FontFamily::Proportional)
-        .or_default()
-        .insert(0, "my_font".to_owned());
+/// The text "Copy full path" puts on the clipboard.
+fn full_path_text(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// The text "Copy file name" puts on the clipboard.
+fn file_name_text(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| full_path_text(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_engine::{index_file_name, IndexedFile};
+
+    fn app_with_index(files: &[&str]) -> SearchApp {
+        let mut app = SearchApp {
+            history_file: std::env::temp_dir().join("search_ui_history_test.txt"),
+            ..Default::default()
+        };
+        for file in files {
+            app.search_engine.insert_path(PathBuf::from(file));
+        }
+        app
+    }
+
+    /// Waits for the search started by the last call and shows its results.
+    fn finish_search(app: &mut SearchApp) {
+        if let Some(outcome) = app.search_worker.wait() {
+            app.apply_search_outcome(outcome);
+        }
+    }
+
+    #[test]
+    fn test_in_memory_index_serves_queries_without_disk() {
+        let root = PathBuf::from("search_in_memory_mode_test");
+        let mut app = SearchApp {
+            index_mode: IndexMode::Memory,
+            ..Default::default()
+        };
+        app.search_engine.set_root_dir(root.clone());
+        let index_file = index_file_name(app.search_engine.get_root_dir());
+        let (sender, receiver) = std::sync::mpsc::channel();
+        app.set_index_receiver(receiver);
+
+        sender
+            .send(IndexSnapshot {
+                roots: vec![PathBuf::from("elsewhere")],
+                files: vec![PathBuf::from("other.txt").into()],
+                complete: true,
+            })
+            .unwrap();
+        sender
+            .send(IndexSnapshot {
+                roots: vec![root.clone()],
+                files: vec![root.join("notes.txt").into()],
+                complete: true,
+            })
+            .unwrap();
+        app.search_command = "notes".to_string();
+        app.receive_index();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 1);
+
+        sender
+            .send(IndexSnapshot {
+                roots: vec![root.clone()],
+                files: vec![root.join("notes-v2.txt").into()],
+                complete: true,
+            })
+            .unwrap();
+        app.receive_index();
+        finish_search(&mut app);
+        assert_eq!(app.search_results[0].0, root.join("notes-v2.txt"));
+        assert!(!Path::new(&index_file).exists());
+
+        app.snapshot_index(app.last_snapshot + SNAPSHOT_INTERVAL);
+        assert!(Path::new(&index_file).exists());
+        std::fs::remove_file(&index_file).unwrap();
+    }
+
+    #[test]
+    fn test_superseded_search_never_lands() {
+        let mut app = app_with_index(&[]);
+        app.search_engine.replace_index(
+            (0..200_000)
+                .map(|i| PathBuf::from(format!("/data/file{}.txt", i)))
+                .chain([PathBuf::from("/docs/notes.txt")])
+                .map(IndexedFile::from)
+                .collect(),
+        );
+        app.max_results = usize::MAX;
+        app.search_command = "file".to_string();
+        app.execute_search();
+        app.search_command = "notes".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        assert_eq!(
+            app.search_results,
+            [(PathBuf::from("/docs/notes.txt"), "notes".to_string())]
+        );
+        std::thread::sleep(Duration::from_millis(50));
+        app.receive_search();
+        assert_eq!(app.search_results.len(), 1);
+    }
+
+    #[test]
+    fn test_repaint_delay() {
+        let idle = Duration::from_secs(5);
+        assert_eq!(
+            repaint_delay(true, false, idle),
+            Some(LOADING_REPAINT_INTERVAL)
+        );
+        assert_eq!(
+            repaint_delay(true, true, Duration::from_millis(100)),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(repaint_delay(false, true, idle), Some(idle));
+        assert_eq!(repaint_delay(false, false, idle), None);
+    }
+
+    #[test]
+    fn test_commands_dispatch() {
+        let mut app = app_with_index(&["/a/report.txt"]);
+        app.search_command = "report".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 1);
+
+        app.search_command = ">clear".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert!(app.search_results.is_empty());
+        assert!(app.search_command.is_empty());
+
+        app.search_command = ">settings".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert!(app.display_dialog);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        app.set_message_sender(sender);
+        app.update_time_file = std::env::temp_dir().join("search_command_interval_test.ini");
+        app.search_command = ">interval 300".to_string();
+        app.submit_search();
+        assert_eq!(
+            receiver.try_recv(),
+            Ok(EngineMessage::SetInterval(Duration::from_secs(300)))
+        );
+        assert_eq!(app.avg_suspend_duration, Duration::from_secs(300));
+        let _ = std::fs::remove_file(&app.update_time_file);
+
+        app.search_command = ">interval 5".to_string();
+        app.submit_search();
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(app.search_command, ">interval 5");
+        assert!(app.notification_message.is_some());
+
+        app.search_command = ">stats".to_string();
+        app.submit_search();
+        let stats = app.notification_message.clone().unwrap();
+        assert!(stats.starts_with("Indexed files: 1,"), "{}", stats);
+
+        app.search_command = ">frobnicate".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert!(app.search_results.is_empty());
+        let error = app.notification_message.clone().unwrap();
+        assert!(error.starts_with("Unknown command"), "{}", error);
+    }
 
-    ctx.set_fonts(fonts);
+    #[test]
+    fn test_search_within_results() {
+        let mut app = app_with_index(&[
+            "/a/report-2023.txt",
+            "/a/report-2024.txt",
+            "/b/summary-2023.txt",
+        ]);
+        app.search_command = "report".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 2);
+
+        app.set_search_within_results(true);
+        app.search_command = "2023".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        let paths: Vec<&PathBuf> = app.search_results.iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, vec![&PathBuf::from("/a/report-2023.txt")]);
+        // Narrowing again still searches the results kept when it was turned on.
+        app.search_command = "2024".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 1);
+        assert_eq!(app.search_results[0].0, PathBuf::from("/a/report-2024.txt"));
+
+        app.search_command = "report".to_string();
+        app.execute_search();
+        app.set_search_within_results(false);
+        finish_search(&mut app);
+        assert_eq!(app.search_command, "report");
+        assert_eq!(app.search_results.len(), 2);
+
+        app.search_command = "2023".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 2);
+    }
+
+    #[test]
+    fn test_suggestions_while_typing() {
+        let mut app = app_with_index(&["/a/report.txt", "/b/readme.md", "/c/notes.txt"]);
+        app.search_command = "re".to_string();
+        app.suggest_next_search = true;
+        app.execute_search();
+        finish_search(&mut app);
+        assert_eq!(app.suggestions, ["readme.md", "report.txt"]);
+
+        app.suggestion_index = Some(1);
+        assert!(app.accept_suggestion());
+        assert!(app.suggestions.is_empty());
+        finish_search(&mut app);
+        assert_eq!(app.search_command, "report.txt");
+        assert_eq!(app.search_results.len(), 1);
+
+        // Only searches run while typing suggest names.
+        app.search_command = "re".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        assert!(app.suggestions.is_empty());
+        assert!(!app.accept_suggestion());
+    }
+
+    #[test]
+    fn test_rerun_last_search_uses_updated_index() {
+        let mut app = app_with_index(&["/a/report.txt"]);
+        app.rerun_last_search();
+        finish_search(&mut app);
+        assert!(app.search_results.is_empty());
+        assert!(app.search_command.is_empty());
+
+        app.search_command = "report".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 1);
+
+        app.search_command = "repo".to_string();
+        app.search_engine
+            .insert_path(PathBuf::from("/b/report-final.txt"));
+        app.rerun_last_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_command, "report");
+        let paths: Vec<&PathBuf> = app.search_results.iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                &PathBuf::from("/a/report.txt"),
+                &PathBuf::from("/b/report-final.txt")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stored_results_never_exceed_cap() {
+        let files: Vec<String> = (0..50).map(|i| format!("/a/report{}.txt", i)).collect();
+        let files: Vec<&str> = files.iter().map(String::as_str).collect();
+        let mut app = app_with_index(&files);
+        app.max_results = 20;
+        app.search_command = "report".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 20);
+        assert!(app.results_truncated);
+
+        app.search_command = "report1".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 11);
+        assert!(!app.results_truncated);
+    }
+
+    #[test]
+    fn test_max_results_raises_the_engine_limit() {
+        let files: Vec<String> = (0..300).map(|i| format!("/a/report{}.txt", i)).collect();
+        let files: Vec<&str> = files.iter().map(String::as_str).collect();
+        let mut app = app_with_index(&files);
+        assert!(app.search_engine.get_search_results_limit() < 250);
+        app.max_results = 250;
+        app.search_command = "report".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 250);
+        assert!(app.results_truncated);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_reported() {
+        let mut app = app_with_index(&["/a/report(1).txt", "/a/report.txt"]);
+        app.search_command = "rep[a-z]rt".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_results.len(), 2);
+        assert_eq!(app.notification_message, None);
+
+        app.search_command = "report[".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        let message = app.notification_message.clone().unwrap();
+        assert!(message.starts_with("Invalid pattern"), "{}", message);
+
+        app.search_command = "report".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert_eq!(app.notification_message, None);
+        assert_eq!(app.search_results.len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_command_is_reported_instead_of_searched() {
+        let mut app = app_with_index(&["/a/>report.txt", "/a/report.txt"]);
+        app.search_command = ">report".to_string();
+        app.submit_search();
+        finish_search(&mut app);
+        assert_eq!(app.search_command, ">report");
+        assert!(app.search_results.is_empty());
+        assert_eq!(
+            app.notification_message.as_deref(),
+            Some("Unknown command >report, type >help for the list")
+        );
+    }
+
+    #[test]
+    fn test_step_selection_wraps_around() {
+        assert_eq!(step_selection(&[], None, true), None);
+        assert_eq!(step_selection(&[], Some(3), false), None);
+        let visible = [1, 4, 7];
+        assert_eq!(step_selection(&visible, None, true), Some(1));
+        assert_eq!(step_selection(&visible, None, false), Some(7));
+        assert_eq!(step_selection(&visible, Some(4), true), Some(7));
+        assert_eq!(step_selection(&visible, Some(7), true), Some(1));
+        assert_eq!(step_selection(&visible, Some(1), false), Some(7));
+        // A selection that is no longer shown starts over.
+        assert_eq!(step_selection(&visible, Some(5), true), Some(1));
+    }
+
+    #[test]
+    fn test_selection_follows_visible_results() {
+        let mut app = app_with_index(&["/a/main.rs", "/b/main.rs"]);
+        app.move_selection(true);
+        assert_eq!(app.selected_index, None);
+        app.search_command = "main".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        app.move_selection(true);
+        app.move_selection(true);
+        assert_eq!(app.selected_index, Some(1));
+        app.move_selection(true);
+        assert_eq!(app.selected_index, Some(0));
+        app.directory_filter = Some(PathBuf::from("/b"));
+        app.move_selection(false);
+        assert_eq!(app.selected_index, Some(1));
+        app.run_command(AppCommand::Clear);
+        assert_eq!(app.selected_index, None);
+        assert!(!app.open_selected());
+    }
+
+    #[test]
+    fn test_interval_input_is_validated_before_sending() {
+        let mut app = SearchApp::default();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        app.set_message_sender(sender);
+        app.update_time_file = std::env::temp_dir().join("search_interval_input_test.ini");
+        let _ = std::fs::remove_file(&app.update_time_file);
+        let interval = app.avg_suspend_duration;
+
+        for input in ["30", "90000", "ten", ""] {
+            app.interval_input = input.to_string();
+            app.apply_interval_input();
+            assert!(app.interval_error.is_some(), "{} should be rejected", input);
+            assert!(receiver.try_recv().is_err());
+            assert_eq!(app.avg_suspend_duration, interval);
+        }
+        assert!(!app.update_time_file.exists());
+
+        app.interval_input = " 900 ".to_string();
+        app.apply_interval_input();
+        assert_eq!(app.interval_error, None);
+        assert_eq!(app.interval_input, "900");
+        assert_eq!(app.avg_suspend_duration, Duration::from_secs(900));
+        assert_eq!(
+            receiver.try_recv(),
+            Ok(EngineMessage::SetInterval(Duration::from_secs(900)))
+        );
+        assert_eq!(
+            std::fs::read_to_string(&app.update_time_file).unwrap(),
+            "900"
+        );
+        std::fs::remove_file(&app.update_time_file).unwrap();
+    }
+
+    /// An app started from the default settings, as on a first run, that saves
+    /// them to `config_file` on exit.
+    fn app_with_default_config(config_file: &Path) -> SearchApp {
+        let _ = std::fs::remove_file(config_file);
+        let mut app = SearchApp {
+            config_file: config_file.to_path_buf(),
+            ..Default::default()
+        };
+        let config = Config::load(&config_file.to_string_lossy()).unwrap();
+        app.apply_config(&config, &egui::Context::default());
+        app.config = Some(config);
+        app
+    }
+
+    #[test]
+    fn test_exit_saves_roots_only_once_edited() {
+        let config_file = std::env::temp_dir().join("search_ui_exit_roots_test.toml");
+        let mut app = app_with_default_config(&config_file);
+        assert!(!app.root_directories.is_empty());
+        eframe::App::on_exit(&mut app, None);
+        let saved = Config::load(&config_file.to_string_lossy()).unwrap();
+        assert!(saved.roots.is_empty());
+
+        app.root_directories.push(String::from("D:\\Data"));
+        app.roots_edited = true;
+        eframe::App::on_exit(&mut app, None);
+        let saved = Config::load(&config_file.to_string_lossy()).unwrap();
+        assert_eq!(saved.roots.last(), Some(&PathBuf::from("D:\\Data")));
+        std::fs::remove_file(&config_file).unwrap();
+    }
+
+    #[test]
+    fn test_exit_keeps_following_drives() {
+        let config_file = std::env::temp_dir().join("search_ui_exit_drives_test.toml");
+        let mut app = app_with_default_config(&config_file);
+        assert!(crate::app::follows_drives(app.config.as_ref().unwrap()));
+        eframe::App::on_exit(&mut app, None);
+        let saved = Config::load(&config_file.to_string_lossy()).unwrap();
+        assert!(crate::app::follows_drives(&saved));
+        std::fs::remove_file(&config_file).unwrap();
+    }
+
+    #[test]
+    fn test_closing_minimizes_until_quit() {
+        let mut app = SearchApp::default();
+        assert!(app.close_commands(true).is_empty());
+        app.minimize_on_close = true;
+        assert!(app.close_commands(false).is_empty());
+        assert_eq!(
+            app.close_commands(true),
+            [
+                egui::ViewportCommand::CancelClose,
+                egui::ViewportCommand::Minimized(true)
+            ]
+        );
+        app.search_command = format!("{}quit", COMMAND_SIGIL);
+        app.submit_search();
+        assert_eq!(app.close_commands(false), [egui::ViewportCommand::Close]);
+        assert!(app.close_commands(true).is_empty());
+    }
+
+    #[test]
+    fn test_drive_filter_narrows_the_search() {
+        let mut app = app_with_index(&["C:\\a\\main.rs", "D:\\main.rs", "d:\\b\\main.rs"]);
+        app.search_command = "main".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        assert_eq!(app.result_drives, ["C:", "D:"].map(PathBuf::from));
+        assert_eq!(app.visible_result_indices().len(), 3);
+
+        app.drive_filter = Some(PathBuf::from("D:"));
+        app.max_results = 2;
+        app.execute_search();
+        finish_search(&mut app);
+        let shown: Vec<&PathBuf> = app
+            .visible_result_indices()
+            .into_iter()
+            .map(|index| &app.search_results[index].0)
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                &PathBuf::from("D:\\main.rs"),
+                &PathBuf::from("d:\\b\\main.rs")
+            ]
+        );
+        assert!(!app.results_truncated);
+        // The other drives stay on offer while one is chosen.
+        assert_eq!(app.result_drives, ["C:", "D:"].map(PathBuf::from));
+
+        app.search_engine
+            .insert_path(PathBuf::from("C:\\notes.txt"));
+        app.search_command = "notes".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        assert!(app.search_results.is_empty());
+        app.drive_filter = None;
+        app.execute_search();
+        finish_search(&mut app);
+        assert_eq!(app.visible_result_indices().len(), 1);
+    }
+
+    #[test]
+    fn test_grouped_results_follow_folder_order() {
+        let mut app = app_with_index(&["/b/main.rs", "/a/main.rs", "/b/sub/main.rs"]);
+        app.search_command = "main".to_string();
+        app.execute_search();
+        finish_search(&mut app);
+        let paths = |app: &SearchApp| -> Vec<PathBuf> {
+            app.visible_result_indices()
+                .into_iter()
+                .map(|index| app.search_results[index].0.clone())
+                .collect()
+        };
+        let flat = paths(&app);
+
+        app.group_results = true;
+        assert_eq!(
+            paths(&app),
+            ["/a/main.rs", "/b/main.rs", "/b/sub/main.rs"].map(PathBuf::from)
+        );
+        app.move_selection(true);
+        assert_eq!(
+            app.search_results[app.selected_index.unwrap()].0,
+            PathBuf::from("/a/main.rs")
+        );
+        let ctx = egui::Context::default();
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.render_results_list(ui));
+        });
+        assert!(!app.scroll_to_selected);
+        app.group_results = false;
+        assert_eq!(paths(&app), flat);
+    }
+
+    #[test]
+    fn test_only_rows_in_view_are_laid_out() {
+        let mut app = SearchApp {
+            search_command: "file".to_string(),
+            search_results: (0..100_000)
+                .map(|n| {
+                    (
+                        PathBuf::from(format!("/data/file{}.txt", n)),
+                        "file".to_string(),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let ctx = egui::Context::default();
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(800.0, 600.0),
+            )),
+            ..Default::default()
+        };
+        let output = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| app.render_results_list(ui));
+        });
+        // A few shapes per row in view, not per result.
+        assert!(
+            output.shapes.len() < 1_000,
+            "{} shapes",
+            output.shapes.len()
+        );
+    }
+
+    #[test]
+    fn test_copied_text() {
+        let path = PathBuf::from("C:\\Users\\me\\report 2024.txt");
+        assert_eq!(full_path_text(&path), "C:\\Users\\me\\report 2024.txt");
+        let path = Path::new("/home/me/report 2024.txt");
+        assert_eq!(full_path_text(path), "/home/me/report 2024.txt");
+        assert_eq!(file_name_text(path), "report 2024.txt");
+        assert_eq!(file_name_text(Path::new("/")), "/");
+    }
 }
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use crate::error::SearchError;
+use crate::search_engine::{Search, SearchEngine};
+
+/// How often watched folders are checked for changes.
+pub(crate) const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Keeps the index of a set of roots current by polling folder modification times
+/// with [`SearchEngine::update_index`].
+///
+/// A poll that finds changes only marks the index as settling; it is reported as
+/// changed by the first following poll that finds nothing new. A burst of changes,
+/// such as a large copy, is therefore published once after it ends.
+pub(crate) struct IndexWatcher {
+    engine: Search,
+    settling: bool,
+}
+
+impl IndexWatcher {
+    /// Indexes the roots of `engine` in full before watching them.
+    pub(crate) fn new(mut engine: Search) -> Result<Self, SearchError> {
+        engine.generate_index()?;
+        Ok(IndexWatcher {
+            engine,
+            settling: false,
+        })
+    }
+
+    /// Checks the watched folders once and returns whether changes have settled
+    /// and should be published.
+    pub(crate) fn poll(&mut self) -> Result<bool, SearchError> {
+        if self.engine.update_index()? {
+            self.settling = true;
+            return Ok(false);
+        }
+        Ok(std::mem::take(&mut self.settling))
+    }
+
+    pub(crate) fn engine(&self) -> &Search {
+        &self.engine
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_engine::IndexedFile;
+    use std::thread;
+
+    #[test]
+    fn test_new_file_appears_after_changes_settle() {
+        let dir = std::env::temp_dir().join("search_watch_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        let mut engine = Search::new();
+        engine.set_root_dir(dir.clone());
+        let mut watcher = IndexWatcher::new(engine).unwrap();
+        assert!(!watcher.poll().unwrap());
+
+        let created = dir.join("docs").join("created.txt");
+        std::fs::write(&created, "").unwrap();
+        let mut published = false;
+        for _ in 0..50 {
+            if watcher.poll().unwrap() {
+                published = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(published);
+        assert_eq!(
+            watcher.engine().get_index()[..],
+            [IndexedFile::read(created)]
+        );
+        assert!(!watcher.poll().unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
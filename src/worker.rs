@@ -0,0 +1,192 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How often [`join_with_timeout`] checks whether the threads have finished.
+const JOIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Waits up to `timeout` for every thread in `handles` to finish and joins the
+/// ones that did. Returns whether all of them finished; the others are left
+/// running and end with the process.
+pub(crate) fn join_with_timeout(handles: Vec<JoinHandle<()>>, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while handles.iter().any(|handle| !handle.is_finished()) && Instant::now() < deadline {
+        thread::sleep(JOIN_POLL_INTERVAL);
+    }
+    let mut all_finished = true;
+    for handle in handles {
+        if handle.is_finished() {
+            let _ = handle.join();
+        } else {
+            all_finished = false;
+        }
+    }
+    all_finished
+}
+
+/// Tells a running search whether a newer one has replaced it.
+#[derive(Clone, Debug)]
+pub(crate) struct CancelToken {
+    latest: Arc<AtomicU64>,
+    generation: u64,
+}
+
+impl CancelToken {
+    /// Whether a newer search was started since this one.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.latest.load(Ordering::Relaxed) != self.generation
+    }
+}
+
+/// Runs searches on background threads, one per query, so that typing never
+/// waits for a slow search.
+///
+/// Each search gets a generation number. Starting a search cancels the ones
+/// before it through their [`CancelToken`], and only the outcome of the latest
+/// search is ever handed out, however late the older ones finish.
+pub(crate) struct SearchWorker<T> {
+    latest: Arc<AtomicU64>,
+    sender: Sender<(u64, T)>,
+    receiver: Receiver<(u64, T)>,
+    pending: bool,
+}
+
+impl<T: Send + 'static> SearchWorker<T> {
+    /// Runs `job` on a new thread, superseding any search still running. The job
+    /// should give up and return `None` once its token is cancelled.
+    pub(crate) fn start<F>(&mut self, job: F)
+    where
+        F: FnOnce(&CancelToken) -> Option<T> + Send + 'static,
+    {
+        let generation = self.latest.fetch_add(1, Ordering::Relaxed) + 1;
+        let token = CancelToken {
+            latest: Arc::clone(&self.latest),
+            generation,
+        };
+        let sender = self.sender.clone();
+        self.pending = true;
+        thread::spawn(move || {
+            if let Some(outcome) = job(&token) {
+                if !token.is_cancelled() {
+                    let _ = sender.send((generation, outcome));
+                }
+            }
+        });
+    }
+
+    /// Whether the latest search has not delivered its outcome yet.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Returns the outcome of the latest search if it finished, dropping the
+    /// outcomes of superseded searches.
+    pub(crate) fn try_finish(&mut self) -> Option<T> {
+        let mut finished = None;
+        while let Ok((generation, outcome)) = self.receiver.try_recv() {
+            if generation == self.latest.load(Ordering::Relaxed) {
+                finished = Some(outcome);
+            }
+        }
+        if finished.is_some() {
+            self.pending = false;
+        }
+        finished
+    }
+
+    /// Blocks until the latest search finishes and returns its outcome, or
+    /// returns `None` at once when no search is pending.
+    #[cfg(test)]
+    pub(crate) fn wait(&mut self) -> Option<T> {
+        while self.pending {
+            let (generation, outcome) = self.receiver.recv().ok()?;
+            if generation == self.latest.load(Ordering::Relaxed) {
+                self.pending = false;
+                return Some(outcome);
+            }
+        }
+        None
+    }
+}
+
+impl<T> Default for SearchWorker<T> {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        SearchWorker {
+            latest: Arc::new(AtomicU64::new(0)),
+            sender,
+            receiver,
+            pending: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_latest_search_lands() {
+        let mut worker = SearchWorker::default();
+        let (started_sender, started) = channel();
+        // A slow search that keeps going until it is told to stop.
+        worker.start(move |token| {
+            started_sender.send(()).unwrap();
+            while !token.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Some("stale")
+        });
+        started.recv().unwrap();
+        assert!(worker.is_pending());
+        assert_eq!(worker.try_finish(), None);
+
+        worker.start(|_| Some("fresh"));
+        assert_eq!(worker.wait(), Some("fresh"));
+        assert!(!worker.is_pending());
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(worker.try_finish(), None);
+    }
+
+    #[test]
+    fn test_token_cancelled_by_newer_search() {
+        let mut worker = SearchWorker::default();
+        let (token_sender, tokens) = channel();
+        for _ in 0..2 {
+            let token_sender = token_sender.clone();
+            worker.start(move |token| {
+                token_sender.send(token.clone()).unwrap();
+                Some(())
+            });
+        }
+        let mut tokens: Vec<CancelToken> = tokens.iter().take(2).collect();
+        tokens.sort_by_key(|token| token.generation);
+        assert!(tokens[0].is_cancelled());
+        assert!(!tokens[1].is_cancelled());
+        assert_eq!(worker.wait(), Some(()));
+    }
+
+    #[test]
+    fn test_join_with_timeout() {
+        let quick = thread::spawn(|| {});
+        assert!(join_with_timeout(vec![quick], Duration::from_secs(5)));
+
+        let (release, blocked) = channel::<()>();
+        let stuck = thread::spawn(move || {
+            let _ = blocked.recv();
+        });
+        let started = Instant::now();
+        assert!(!join_with_timeout(
+            vec![stuck, thread::spawn(|| {})],
+            Duration::from_millis(50)
+        ));
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        drop(release);
+    }
+}